@@ -1,10 +1,60 @@
 let wasm;
 
+function _assertClass(instance, klass) {
+    if (!(instance instanceof klass)) {
+        throw new Error(`expected instance of ${klass.name}`);
+    }
+}
+
+function getArrayF32FromWasm0(ptr, len) {
+    ptr = ptr >>> 0;
+    return getFloat32ArrayMemory0().subarray(ptr / 4, ptr / 4 + len);
+}
+
+function getArrayF64FromWasm0(ptr, len) {
+    ptr = ptr >>> 0;
+    return getFloat64ArrayMemory0().subarray(ptr / 8, ptr / 8 + len);
+}
+
+function getArrayU32FromWasm0(ptr, len) {
+    ptr = ptr >>> 0;
+    return getUint32ArrayMemory0().subarray(ptr / 4, ptr / 4 + len);
+}
+
+function getArrayU8FromWasm0(ptr, len) {
+    ptr = ptr >>> 0;
+    return getUint8ArrayMemory0().subarray(ptr / 1, ptr / 1 + len);
+}
+
+let cachedFloat32ArrayMemory0 = null;
+function getFloat32ArrayMemory0() {
+    if (cachedFloat32ArrayMemory0 === null || cachedFloat32ArrayMemory0.byteLength === 0) {
+        cachedFloat32ArrayMemory0 = new Float32Array(wasm.memory.buffer);
+    }
+    return cachedFloat32ArrayMemory0;
+}
+
+let cachedFloat64ArrayMemory0 = null;
+function getFloat64ArrayMemory0() {
+    if (cachedFloat64ArrayMemory0 === null || cachedFloat64ArrayMemory0.byteLength === 0) {
+        cachedFloat64ArrayMemory0 = new Float64Array(wasm.memory.buffer);
+    }
+    return cachedFloat64ArrayMemory0;
+}
+
 function getStringFromWasm0(ptr, len) {
     ptr = ptr >>> 0;
     return decodeText(ptr, len);
 }
 
+let cachedUint32ArrayMemory0 = null;
+function getUint32ArrayMemory0() {
+    if (cachedUint32ArrayMemory0 === null || cachedUint32ArrayMemory0.byteLength === 0) {
+        cachedUint32ArrayMemory0 = new Uint32Array(wasm.memory.buffer);
+    }
+    return cachedUint32ArrayMemory0;
+}
+
 let cachedUint8ArrayMemory0 = null;
 function getUint8ArrayMemory0() {
     if (cachedUint8ArrayMemory0 === null || cachedUint8ArrayMemory0.byteLength === 0) {
@@ -13,6 +63,26 @@ function getUint8ArrayMemory0() {
     return cachedUint8ArrayMemory0;
 }
 
+function passArray8ToWasm0(arg, malloc) {
+    const ptr = malloc(arg.length * 1, 1) >>> 0;
+    getUint8ArrayMemory0().set(arg, ptr / 1);
+    WASM_VECTOR_LEN = arg.length;
+    return ptr;
+}
+
+function passArrayF32ToWasm0(arg, malloc) {
+    const ptr = malloc(arg.length * 4, 4) >>> 0;
+    getFloat32ArrayMemory0().set(arg, ptr / 4);
+    WASM_VECTOR_LEN = arg.length;
+    return ptr;
+}
+
+function takeFromExternrefTable0(idx) {
+    const value = wasm.__wbindgen_externrefs.get(idx);
+    wasm.__externref_table_dealloc(idx);
+    return value;
+}
+
 let cachedTextDecoder = new TextDecoder('utf-8', { ignoreBOM: true, fatal: true });
 cachedTextDecoder.decode();
 const MAX_SAFARI_DECODE_BYTES = 2146435072;
@@ -27,283 +97,2436 @@ function decodeText(ptr, len) {
     return cachedTextDecoder.decode(getUint8ArrayMemory0().subarray(ptr, ptr + len));
 }
 
+let WASM_VECTOR_LEN = 0;
+
+const BodyStateFinalization = (typeof FinalizationRegistry === 'undefined')
+    ? { register: () => {}, unregister: () => {} }
+    : new FinalizationRegistry(ptr => wasm.__wbg_bodystate_free(ptr >>> 0, 1));
+
+const CollisionEventFinalization = (typeof FinalizationRegistry === 'undefined')
+    ? { register: () => {}, unregister: () => {} }
+    : new FinalizationRegistry(ptr => wasm.__wbg_collisionevent_free(ptr >>> 0, 1));
+
+const DiagnosticsFinalization = (typeof FinalizationRegistry === 'undefined')
+    ? { register: () => {}, unregister: () => {} }
+    : new FinalizationRegistry(ptr => wasm.__wbg_diagnostics_free(ptr >>> 0, 1));
+
+const EmitterFinalization = (typeof FinalizationRegistry === 'undefined')
+    ? { register: () => {}, unregister: () => {} }
+    : new FinalizationRegistry(ptr => wasm.__wbg_emitter_free(ptr >>> 0, 1));
+
+const FrameStatsFinalization = (typeof FinalizationRegistry === 'undefined')
+    ? { register: () => {}, unregister: () => {} }
+    : new FinalizationRegistry(ptr => wasm.__wbg_framestats_free(ptr >>> 0, 1));
+
 const ParticleFinalization = (typeof FinalizationRegistry === 'undefined')
     ? { register: () => {}, unregister: () => {} }
     : new FinalizationRegistry(ptr => wasm.__wbg_particle_free(ptr >>> 0, 1));
 
+const PlayerFinalization = (typeof FinalizationRegistry === 'undefined')
+    ? { register: () => {}, unregister: () => {} }
+    : new FinalizationRegistry(ptr => wasm.__wbg_player_free(ptr >>> 0, 1));
+
+const RecorderFinalization = (typeof FinalizationRegistry === 'undefined')
+    ? { register: () => {}, unregister: () => {} }
+    : new FinalizationRegistry(ptr => wasm.__wbg_recorder_free(ptr >>> 0, 1));
+
 const SimulationFinalization = (typeof FinalizationRegistry === 'undefined')
     ? { register: () => {}, unregister: () => {} }
     : new FinalizationRegistry(ptr => wasm.__wbg_simulation_free(ptr >>> 0, 1));
 
-export class Particle {
+/**
+ * @enum {0 | 1 | 2}
+ */
+export const BodyMotion = Object.freeze({
+    /**
+     * Stays put until `move_body` gives it a new pose, which it glides to
+     * over the next step.
+     */
+    Scripted: 0, "0": "Scripted",
+    /**
+     * Swings back and forth along a line through its anchor, like a
+     * piston.
+     */
+    Oscillate: 1, "1": "Oscillate",
+    /**
+     * Spins about its position at a steady rate, like a drum.
+     */
+    Rotate: 2, "2": "Rotate",
+});
+
+/**
+ * Where a body is and how fast it is moving. `angle` is in radians,
+ * clockwise on screen; velocities are per reference frame, and `spin` is
+ * the angular velocity about (x, y).
+ */
+export class BodyState {
+    static __wrap(ptr) {
+        ptr = ptr >>> 0;
+        const obj = Object.create(BodyState.prototype);
+        obj.__wbg_ptr = ptr;
+        BodyStateFinalization.register(obj, obj.__wbg_ptr, obj);
+        return obj;
+    }
     __destroy_into_raw() {
         const ptr = this.__wbg_ptr;
         this.__wbg_ptr = 0;
-        ParticleFinalization.unregister(this);
+        BodyStateFinalization.unregister(this);
         return ptr;
     }
     free() {
         const ptr = this.__destroy_into_raw();
-        wasm.__wbg_particle_free(ptr, 0);
+        wasm.__wbg_bodystate_free(ptr, 0);
     }
     /**
      * @returns {number}
      */
     get x() {
-        const ret = wasm.__wbg_get_particle_x(this.__wbg_ptr);
+        const ret = wasm.__wbg_get_bodystate_x(this.__wbg_ptr);
         return ret;
     }
     /**
      * @param {number} arg0
      */
     set x(arg0) {
-        wasm.__wbg_set_particle_x(this.__wbg_ptr, arg0);
+        wasm.__wbg_set_bodystate_x(this.__wbg_ptr, arg0);
     }
     /**
      * @returns {number}
      */
     get y() {
-        const ret = wasm.__wbg_get_particle_y(this.__wbg_ptr);
+        const ret = wasm.__wbg_get_bodystate_y(this.__wbg_ptr);
         return ret;
     }
     /**
      * @param {number} arg0
      */
     set y(arg0) {
-        wasm.__wbg_set_particle_y(this.__wbg_ptr, arg0);
+        wasm.__wbg_set_bodystate_y(this.__wbg_ptr, arg0);
     }
     /**
      * @returns {number}
      */
-    get radius() {
-        const ret = wasm.__wbg_get_particle_radius(this.__wbg_ptr);
+    get angle() {
+        const ret = wasm.__wbg_get_bodystate_angle(this.__wbg_ptr);
         return ret;
     }
     /**
      * @param {number} arg0
      */
-    set radius(arg0) {
-        wasm.__wbg_set_particle_radius(this.__wbg_ptr, arg0);
+    set angle(arg0) {
+        wasm.__wbg_set_bodystate_angle(this.__wbg_ptr, arg0);
     }
     /**
      * @returns {number}
      */
-    get mass() {
-        const ret = wasm.__wbg_get_particle_mass(this.__wbg_ptr);
+    get vx() {
+        const ret = wasm.__wbg_get_bodystate_vx(this.__wbg_ptr);
         return ret;
     }
     /**
      * @param {number} arg0
      */
-    set mass(arg0) {
-        wasm.__wbg_set_particle_mass(this.__wbg_ptr, arg0);
+    set vx(arg0) {
+        wasm.__wbg_set_bodystate_vx(this.__wbg_ptr, arg0);
     }
     /**
      * @returns {number}
      */
-    get vx() {
-        const ret = wasm.__wbg_get_particle_vx(this.__wbg_ptr);
+    get vy() {
+        const ret = wasm.__wbg_get_bodystate_vy(this.__wbg_ptr);
         return ret;
     }
     /**
      * @param {number} arg0
      */
-    set vx(arg0) {
-        wasm.__wbg_set_particle_vx(this.__wbg_ptr, arg0);
+    set vy(arg0) {
+        wasm.__wbg_set_bodystate_vy(this.__wbg_ptr, arg0);
     }
     /**
      * @returns {number}
      */
-    get vy() {
-        const ret = wasm.__wbg_get_particle_vy(this.__wbg_ptr);
+    get spin() {
+        const ret = wasm.__wbg_get_bodystate_spin(this.__wbg_ptr);
         return ret;
     }
     /**
      * @param {number} arg0
      */
-    set vy(arg0) {
-        wasm.__wbg_set_particle_vy(this.__wbg_ptr, arg0);
+    set spin(arg0) {
+        wasm.__wbg_set_bodystate_spin(this.__wbg_ptr, arg0);
     }
+}
+if (Symbol.dispose) BodyState.prototype[Symbol.dispose] = BodyState.prototype.free;
+
+/**
+ * @enum {0 | 1 | 2 | 3 | 4}
+ */
+export const BoundaryMode = Object.freeze({
     /**
-     * @returns {boolean}
+     * Particles bounce off the four sides of the box.
      */
-    get colliding() {
-        const ret = wasm.__wbg_get_particle_colliding(this.__wbg_ptr);
-        return ret !== 0;
+    Walls: 0, "0": "Walls",
+    /**
+     * Leaving one side brings a particle back in at the opposite one, and
+     * particles near opposite sides collide across the seam.
+     */
+    Periodic: 1, "1": "Periodic",
+    /**
+     * Particles that leave the box entirely are removed.
+     */
+    Open: 2, "2": "Open",
+    /**
+     * Particles bounce around inside the largest circle that fits the box.
+     */
+    Circle: 3, "3": "Circle",
+    /**
+     * Particles bounce around inside the polygon from
+     * `set_boundary_polygon`.
+     */
+    Polygon: 4, "4": "Polygon",
+});
+
+/**
+ * @enum {0 | 1 | 2 | 3}
+ */
+export const BroadphaseKind = Object.freeze({
+    ZOrder: 0, "0": "ZOrder",
+    UniformGrid: 1, "1": "UniformGrid",
+    LooseQuadtree: 2, "2": "LooseQuadtree",
+    BruteForce: 3, "3": "BruteForce",
+});
+
+/**
+ * One resolved collision. `a` and `b` are particle ids, `(x, y)` is the
+ * contact point on `a`'s surface and `(nx, ny)` the unit normal pointing
+ * from `b` to `a`. `impulse` is the magnitude of the impulse exchanged. A
+ * bounce off an obstacle or a body has `b` set to `NO_PARTICLE`, with the
+ * normal pointing out of the surface.
+ */
+export class CollisionEvent {
+    __destroy_into_raw() {
+        const ptr = this.__wbg_ptr;
+        this.__wbg_ptr = 0;
+        CollisionEventFinalization.unregister(this);
+        return ptr;
+    }
+    free() {
+        const ptr = this.__destroy_into_raw();
+        wasm.__wbg_collisionevent_free(ptr, 0);
     }
     /**
-     * @param {boolean} arg0
+     * @returns {number}
      */
-    set colliding(arg0) {
-        wasm.__wbg_set_particle_colliding(this.__wbg_ptr, arg0);
+    get a() {
+        const ret = wasm.__wbg_get_collisionevent_a(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @param {number} arg0
+     */
+    set a(arg0) {
+        wasm.__wbg_set_collisionevent_a(this.__wbg_ptr, arg0);
     }
     /**
      * @returns {number}
      */
-    get impulse_x() {
-        const ret = wasm.__wbg_get_particle_impulse_x(this.__wbg_ptr);
+    get b() {
+        const ret = wasm.__wbg_get_collisionevent_b(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @param {number} arg0
+     */
+    set b(arg0) {
+        wasm.__wbg_set_collisionevent_b(this.__wbg_ptr, arg0);
+    }
+    /**
+     * @returns {number}
+     */
+    get x() {
+        const ret = wasm.__wbg_get_collisionevent_x(this.__wbg_ptr);
         return ret;
     }
     /**
      * @param {number} arg0
      */
-    set impulse_x(arg0) {
-        wasm.__wbg_set_particle_impulse_x(this.__wbg_ptr, arg0);
+    set x(arg0) {
+        wasm.__wbg_set_collisionevent_x(this.__wbg_ptr, arg0);
     }
     /**
      * @returns {number}
      */
-    get impulse_y() {
-        const ret = wasm.__wbg_get_particle_impulse_y(this.__wbg_ptr);
+    get y() {
+        const ret = wasm.__wbg_get_collisionevent_y(this.__wbg_ptr);
         return ret;
     }
     /**
      * @param {number} arg0
      */
-    set impulse_y(arg0) {
-        wasm.__wbg_set_particle_impulse_y(this.__wbg_ptr, arg0);
+    set y(arg0) {
+        wasm.__wbg_set_collisionevent_y(this.__wbg_ptr, arg0);
     }
     /**
      * @returns {number}
      */
-    get color_temp() {
-        const ret = wasm.__wbg_get_particle_color_temp(this.__wbg_ptr);
+    get nx() {
+        const ret = wasm.__wbg_get_collisionevent_nx(this.__wbg_ptr);
         return ret;
     }
     /**
      * @param {number} arg0
      */
-    set color_temp(arg0) {
-        wasm.__wbg_set_particle_color_temp(this.__wbg_ptr, arg0);
+    set nx(arg0) {
+        wasm.__wbg_set_collisionevent_nx(this.__wbg_ptr, arg0);
+    }
+    /**
+     * @returns {number}
+     */
+    get ny() {
+        const ret = wasm.__wbg_get_collisionevent_ny(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @param {number} arg0
+     */
+    set ny(arg0) {
+        wasm.__wbg_set_collisionevent_ny(this.__wbg_ptr, arg0);
+    }
+    /**
+     * @returns {number}
+     */
+    get impulse() {
+        const ret = wasm.__wbg_get_collisionevent_impulse(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @param {number} arg0
+     */
+    set impulse(arg0) {
+        wasm.__wbg_set_collisionevent_impulse(this.__wbg_ptr, arg0);
     }
 }
-if (Symbol.dispose) Particle.prototype[Symbol.dispose] = Particle.prototype.free;
+if (Symbol.dispose) CollisionEvent.prototype[Symbol.dispose] = CollisionEvent.prototype.free;
 
-export class Simulation {
+/**
+ * A summary of the whole simulation, from `Simulation::diagnostics`.
+ */
+export class Diagnostics {
+    static __wrap(ptr) {
+        ptr = ptr >>> 0;
+        const obj = Object.create(Diagnostics.prototype);
+        obj.__wbg_ptr = ptr;
+        DiagnosticsFinalization.register(obj, obj.__wbg_ptr, obj);
+        return obj;
+    }
     __destroy_into_raw() {
         const ptr = this.__wbg_ptr;
         this.__wbg_ptr = 0;
-        SimulationFinalization.unregister(this);
+        DiagnosticsFinalization.unregister(this);
         return ptr;
     }
     free() {
         const ptr = this.__destroy_into_raw();
-        wasm.__wbg_simulation_free(ptr, 0);
+        wasm.__wbg_diagnostics_free(ptr, 0);
     }
     /**
-     * @param {number} gravity
+     * @returns {number}
      */
-    set_gravity(gravity) {
-        wasm.simulation_set_gravity(this.__wbg_ptr, gravity);
+    get kinetic_energy() {
+        const ret = wasm.__wbg_get_diagnostics_kinetic_energy(this.__wbg_ptr);
+        return ret;
     }
     /**
-     * @param {number} x
-     * @param {number} y
+     * @param {number} arg0
      */
-    add_particle(x, y) {
-        wasm.simulation_add_particle(this.__wbg_ptr, x, y);
+    set kinetic_energy(arg0) {
+        wasm.__wbg_set_diagnostics_kinetic_energy(this.__wbg_ptr, arg0);
     }
     /**
-     * @param {number} size
+     * @returns {number}
      */
-    set_max_size(size) {
-        wasm.simulation_set_max_size(this.__wbg_ptr, size);
+    get potential_energy() {
+        const ret = wasm.__wbg_get_diagnostics_potential_energy(this.__wbg_ptr);
+        return ret;
     }
     /**
-     * @param {number} size
+     * @param {number} arg0
      */
-    set_min_size(size) {
-        wasm.simulation_set_min_size(this.__wbg_ptr, size);
+    set potential_energy(arg0) {
+        wasm.__wbg_set_diagnostics_potential_energy(this.__wbg_ptr, arg0);
     }
     /**
-     * @param {number} speed
+     * @returns {number}
      */
-    set_fan_speed(speed) {
-        wasm.simulation_set_fan_speed(this.__wbg_ptr, speed);
+    get momentum_x() {
+        const ret = wasm.__wbg_get_diagnostics_momentum_x(this.__wbg_ptr);
+        return ret;
     }
     /**
-     * @param {number} speed
+     * @param {number} arg0
      */
-    set_max_speed(speed) {
-        wasm.simulation_set_max_speed(this.__wbg_ptr, speed);
+    set momentum_x(arg0) {
+        wasm.__wbg_set_diagnostics_momentum_x(this.__wbg_ptr, arg0);
     }
     /**
-     * @param {number} elasticity
+     * @returns {number}
      */
-    set_elasticity(elasticity) {
-        wasm.simulation_set_elasticity(this.__wbg_ptr, elasticity);
+    get momentum_y() {
+        const ret = wasm.__wbg_get_diagnostics_momentum_y(this.__wbg_ptr);
+        return ret;
     }
     /**
-     * @param {number} range
+     * @param {number} arg0
      */
-    set_search_range(range) {
-        wasm.simulation_set_search_range(this.__wbg_ptr, range);
+    set momentum_y(arg0) {
+        wasm.__wbg_set_diagnostics_momentum_y(this.__wbg_ptr, arg0);
     }
     /**
+     * Pairs whose collision was resolved during the last step, summed
+     * over its substeps.
      * @returns {number}
      */
-    get_particles_ptr() {
-        const ret = wasm.simulation_get_particles_ptr(this.__wbg_ptr);
+    get collisions() {
+        const ret = wasm.__wbg_get_diagnostics_collisions(this.__wbg_ptr);
         return ret >>> 0;
     }
+    /**
+     * Pairs whose collision was resolved during the last step, summed
+     * over its substeps.
+     * @param {number} arg0
+     */
+    set collisions(arg0) {
+        wasm.__wbg_set_diagnostics_collisions(this.__wbg_ptr, arg0);
+    }
     /**
      * @returns {number}
      */
-    get_particle_count() {
-        const ret = wasm.simulation_get_particle_count(this.__wbg_ptr);
-        return ret >>> 0;
+    get mean_speed() {
+        const ret = wasm.__wbg_get_diagnostics_mean_speed(this.__wbg_ptr);
+        return ret;
     }
     /**
-     * @param {number} count
+     * @param {number} arg0
      */
-    set_particle_count(count) {
-        wasm.simulation_set_particle_count(this.__wbg_ptr, count);
+    set mean_speed(arg0) {
+        wasm.__wbg_set_diagnostics_mean_speed(this.__wbg_ptr, arg0);
     }
     /**
-     * @param {number} width
-     * @param {number} height
-     * @param {number} particle_count
-     * @param {number} min_size
-     * @param {number} max_size
-     * @param {number} max_speed
-     * @param {number} gravity
-     * @param {number} elasticity
-     * @param {number} fan_speed
-     * @param {number} search_range
+     * @returns {number}
      */
-    constructor(width, height, particle_count, min_size, max_size, max_speed, gravity, elasticity, fan_speed, search_range) {
-        const ret = wasm.simulation_new(width, height, particle_count, min_size, max_size, max_speed, gravity, elasticity, fan_speed, search_range);
-        this.__wbg_ptr = ret >>> 0;
-        SimulationFinalization.register(this, this.__wbg_ptr, this);
-        return this;
+    get max_speed() {
+        const ret = wasm.__wbg_get_diagnostics_max_speed(this.__wbg_ptr);
+        return ret;
     }
-    reset() {
-        wasm.simulation_reset(this.__wbg_ptr);
+    /**
+     * @param {number} arg0
+     */
+    set max_speed(arg0) {
+        wasm.__wbg_set_diagnostics_max_speed(this.__wbg_ptr, arg0);
     }
-    update() {
-        wasm.simulation_update(this.__wbg_ptr);
+    /**
+     * Kinetic energy taken out by inelastic collisions and wall bounces
+     * during the last step, less any work moving bodies did on the
+     * particles, so it can be negative.
+     * @returns {number}
+     */
+    get energy_lost() {
+        const ret = wasm.__wbg_get_diagnostics_energy_lost(this.__wbg_ptr);
+        return ret;
     }
-}
-if (Symbol.dispose) Simulation.prototype[Symbol.dispose] = Simulation.prototype.free;
-
-const EXPECTED_RESPONSE_TYPES = new Set(['basic', 'cors', 'default']);
-
-async function __wbg_load(module, imports) {
-    if (typeof Response === 'function' && module instanceof Response) {
-        if (typeof WebAssembly.instantiateStreaming === 'function') {
-            try {
-                return await WebAssembly.instantiateStreaming(module, imports);
-            } catch (e) {
-                const validResponse = module.ok && EXPECTED_RESPONSE_TYPES.has(module.type);
-
-                if (validResponse && module.headers.get('Content-Type') !== 'application/wasm') {
-                    console.warn("`WebAssembly.instantiateStreaming` failed because your server does not serve Wasm with `application/wasm` MIME type. Falling back to `WebAssembly.instantiate` which is slower. Original error:\n", e);
-
-                } else {
-                    throw e;
-                }
-            }
-        }
+    /**
+     * Kinetic energy taken out by inelastic collisions and wall bounces
+     * during the last step, less any work moving bodies did on the
+     * particles, so it can be negative.
+     * @param {number} arg0
+     */
+    set energy_lost(arg0) {
+        wasm.__wbg_set_diagnostics_energy_lost(this.__wbg_ptr, arg0);
+    }
+    /**
+     * `energy_lost` summed since the last `reset`, `restore` or rewind.
+     * @returns {number}
+     */
+    get total_energy_lost() {
+        const ret = wasm.__wbg_get_diagnostics_total_energy_lost(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * `energy_lost` summed since the last `reset`, `restore` or rewind.
+     * @param {number} arg0
+     */
+    set total_energy_lost(arg0) {
+        wasm.__wbg_set_diagnostics_total_energy_lost(this.__wbg_ptr, arg0);
+    }
+    /**
+     * Mean kinetic energy per particle. With two degrees of freedom this is
+     * `kT`, the temperature of the fitted Maxwell–Boltzmann distribution.
+     * @returns {number}
+     */
+    get temperature() {
+        const ret = wasm.__wbg_get_diagnostics_temperature(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * Mean kinetic energy per particle. With two degrees of freedom this is
+     * `kT`, the temperature of the fitted Maxwell–Boltzmann distribution.
+     * @param {number} arg0
+     */
+    set temperature(arg0) {
+        wasm.__wbg_set_diagnostics_temperature(this.__wbg_ptr, arg0);
+    }
+}
+if (Symbol.dispose) Diagnostics.prototype[Symbol.dispose] = Diagnostics.prototype.free;
+
+/**
+ * One emitter. Fans and wind cover the rectangle that starts at (x, y),
+ * runs `reach` along `angle` (radians, clockwise on screen from +x) and is
+ * `width` across. The other kinds cover the circle of radius `reach` around
+ * (x, y) and ignore `angle` and `width`. `strength` is the peak
+ * acceleration.
+ */
+export class Emitter {
+    static __wrap(ptr) {
+        ptr = ptr >>> 0;
+        const obj = Object.create(Emitter.prototype);
+        obj.__wbg_ptr = ptr;
+        EmitterFinalization.register(obj, obj.__wbg_ptr, obj);
+        return obj;
+    }
+    __destroy_into_raw() {
+        const ptr = this.__wbg_ptr;
+        this.__wbg_ptr = 0;
+        EmitterFinalization.unregister(this);
+        return ptr;
+    }
+    free() {
+        const ptr = this.__destroy_into_raw();
+        wasm.__wbg_emitter_free(ptr, 0);
+    }
+    /**
+     * @returns {EmitterKind}
+     */
+    get kind() {
+        const ret = wasm.__wbg_get_emitter_kind(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @param {EmitterKind} arg0
+     */
+    set kind(arg0) {
+        wasm.__wbg_set_emitter_kind(this.__wbg_ptr, arg0);
+    }
+    /**
+     * @returns {number}
+     */
+    get x() {
+        const ret = wasm.__wbg_get_emitter_x(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @param {number} arg0
+     */
+    set x(arg0) {
+        wasm.__wbg_set_emitter_x(this.__wbg_ptr, arg0);
+    }
+    /**
+     * @returns {number}
+     */
+    get y() {
+        const ret = wasm.__wbg_get_emitter_y(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @param {number} arg0
+     */
+    set y(arg0) {
+        wasm.__wbg_set_emitter_y(this.__wbg_ptr, arg0);
+    }
+    /**
+     * @returns {number}
+     */
+    get angle() {
+        const ret = wasm.__wbg_get_emitter_angle(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @param {number} arg0
+     */
+    set angle(arg0) {
+        wasm.__wbg_set_emitter_angle(this.__wbg_ptr, arg0);
+    }
+    /**
+     * @returns {number}
+     */
+    get width() {
+        const ret = wasm.__wbg_get_emitter_width(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @param {number} arg0
+     */
+    set width(arg0) {
+        wasm.__wbg_set_emitter_width(this.__wbg_ptr, arg0);
+    }
+    /**
+     * @returns {number}
+     */
+    get reach() {
+        const ret = wasm.__wbg_get_emitter_reach(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @param {number} arg0
+     */
+    set reach(arg0) {
+        wasm.__wbg_set_emitter_reach(this.__wbg_ptr, arg0);
+    }
+    /**
+     * @returns {number}
+     */
+    get strength() {
+        const ret = wasm.__wbg_get_emitter_strength(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @param {number} arg0
+     */
+    set strength(arg0) {
+        wasm.__wbg_set_emitter_strength(this.__wbg_ptr, arg0);
+    }
+    /**
+     * @param {EmitterKind} kind
+     * @param {number} x
+     * @param {number} y
+     * @param {number} angle
+     * @param {number} width
+     * @param {number} reach
+     * @param {number} strength
+     */
+    constructor(kind, x, y, angle, width, reach, strength) {
+        const ret = wasm.emitter_new(kind, x, y, angle, width, reach, strength);
+        this.__wbg_ptr = ret >>> 0;
+        EmitterFinalization.register(this, this.__wbg_ptr, this);
+        return this;
+    }
+}
+if (Symbol.dispose) Emitter.prototype[Symbol.dispose] = Emitter.prototype.free;
+
+/**
+ * @enum {0 | 1 | 2 | 3 | 4}
+ */
+export const EmitterKind = Object.freeze({
+    /**
+     * Blows along `angle` across a `width`-wide band, hardest at the
+     * middle of its base and fading to nothing at `reach` and at the edges.
+     */
+    Fan: 0, "0": "Fan",
+    /**
+     * Pulls towards (x, y), fading linearly to nothing at `reach`.
+     */
+    Attractor: 1, "1": "Attractor",
+    /**
+     * Pushes away from (x, y), fading linearly to nothing at `reach`.
+     */
+    Repulsor: 2, "2": "Repulsor",
+    /**
+     * Spins particles around (x, y), clockwise on screen for a positive
+     * strength, fading linearly to nothing at `reach`.
+     */
+    Vortex: 3, "3": "Vortex",
+    /**
+     * Blows along `angle` with the same strength everywhere in its band.
+     */
+    Wind: 4, "4": "Wind",
+});
+
+/**
+ * Where the time went during the last `update` (all of its steps) or the
+ * last direct `step`, from `Simulation::get_stats`. Times are in
+ * milliseconds and summed over steps and substeps.
+ */
+export class FrameStats {
+    static __wrap(ptr) {
+        ptr = ptr >>> 0;
+        const obj = Object.create(FrameStats.prototype);
+        obj.__wbg_ptr = ptr;
+        FrameStatsFinalization.register(obj, obj.__wbg_ptr, obj);
+        return obj;
+    }
+    __destroy_into_raw() {
+        const ptr = this.__wbg_ptr;
+        this.__wbg_ptr = 0;
+        FrameStatsFinalization.unregister(this);
+        return ptr;
+    }
+    free() {
+        const ptr = this.__destroy_into_raw();
+        wasm.__wbg_framestats_free(ptr, 0);
+    }
+    /**
+     * Fixed steps taken.
+     * @returns {number}
+     */
+    get steps() {
+        const ret = wasm.__wbg_get_framestats_steps(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * Fixed steps taken.
+     * @param {number} arg0
+     */
+    set steps(arg0) {
+        wasm.__wbg_set_framestats_steps(this.__wbg_ptr, arg0);
+    }
+    /**
+     * Gravity, emitters, cooling, motion and the boundary.
+     * @returns {number}
+     */
+    get integrate_ms() {
+        const ret = wasm.__wbg_get_framestats_integrate_ms(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * Gravity, emitters, cooling, motion and the boundary.
+     * @param {number} arg0
+     */
+    set integrate_ms(arg0) {
+        wasm.__wbg_set_framestats_integrate_ms(this.__wbg_ptr, arg0);
+    }
+    /**
+     * Recomputing every particle's Z-order code.
+     * @returns {number}
+     */
+    get z_code_ms() {
+        const ret = wasm.__wbg_get_framestats_z_code_ms(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * Recomputing every particle's Z-order code.
+     * @param {number} arg0
+     */
+    set z_code_ms(arg0) {
+        wasm.__wbg_set_framestats_z_code_ms(this.__wbg_ptr, arg0);
+    }
+    /**
+     * Sorting particles by Z-order code (`get_sort_time_ms` per step).
+     * @returns {number}
+     */
+    get sort_ms() {
+        const ret = wasm.__wbg_get_framestats_sort_ms(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * Sorting particles by Z-order code (`get_sort_time_ms` per step).
+     * @param {number} arg0
+     */
+    set sort_ms(arg0) {
+        wasm.__wbg_set_framestats_sort_ms(this.__wbg_ptr, arg0);
+    }
+    /**
+     * Gathering positions into Z-order, building the broadphase and
+     * querying it, including the exact overlap test of every candidate.
+     * @returns {number}
+     */
+    get broadphase_ms() {
+        const ret = wasm.__wbg_get_framestats_broadphase_ms(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * Gathering positions into Z-order, building the broadphase and
+     * querying it, including the exact overlap test of every candidate.
+     * @param {number} arg0
+     */
+    set broadphase_ms(arg0) {
+        wasm.__wbg_set_framestats_broadphase_ms(this.__wbg_ptr, arg0);
+    }
+    /**
+     * Ordering the overlapping pairs and resolving their collisions, then
+     * bouncing particles off obstacles.
+     * @returns {number}
+     */
+    get narrow_phase_ms() {
+        const ret = wasm.__wbg_get_framestats_narrow_phase_ms(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * Ordering the overlapping pairs and resolving their collisions, then
+     * bouncing particles off obstacles.
+     * @param {number} arg0
+     */
+    set narrow_phase_ms(arg0) {
+        wasm.__wbg_set_framestats_narrow_phase_ms(this.__wbg_ptr, arg0);
+    }
+    /**
+     * Candidate pairs the broadphase tested for overlap.
+     * @returns {bigint}
+     */
+    get candidate_pairs() {
+        const ret = wasm.__wbg_get_framestats_candidate_pairs(this.__wbg_ptr);
+        return BigInt.asUintN(64, ret);
+    }
+    /**
+     * Candidate pairs the broadphase tested for overlap.
+     * @param {bigint} arg0
+     */
+    set candidate_pairs(arg0) {
+        wasm.__wbg_set_framestats_candidate_pairs(this.__wbg_ptr, arg0);
+    }
+    /**
+     * Candidates that overlapped.
+     * @returns {bigint}
+     */
+    get contacts() {
+        const ret = wasm.__wbg_get_framestats_contacts(this.__wbg_ptr);
+        return BigInt.asUintN(64, ret);
+    }
+    /**
+     * Candidates that overlapped.
+     * @param {bigint} arg0
+     */
+    set contacts(arg0) {
+        wasm.__wbg_set_framestats_contacts(this.__wbg_ptr, arg0);
+    }
+}
+if (Symbol.dispose) FrameStats.prototype[Symbol.dispose] = FrameStats.prototype.free;
+
+/**
+ * @enum {0 | 1 | 2}
+ */
+export const ObstacleKind = Object.freeze({
+    /**
+     * An open chain of segments joining consecutive points.
+     */
+    Segments: 0, "0": "Segments",
+    /**
+     * A closed outline, convex or not, that particles stay out of.
+     */
+    Polygon: 1, "1": "Polygon",
+    /**
+     * A solid circle. Its points are the centre and the radius.
+     */
+    Circle: 2, "2": "Circle",
+});
+
+/**
+ * One particle's state. The simulation stores particles as separate
+ * attribute arrays; this record is what `get_particles_ptr` and the Rust
+ * API hand out. `id` stays with the particle from spawn to `reset`.
+ */
+export class Particle {
+    static __wrap(ptr) {
+        ptr = ptr >>> 0;
+        const obj = Object.create(Particle.prototype);
+        obj.__wbg_ptr = ptr;
+        ParticleFinalization.register(obj, obj.__wbg_ptr, obj);
+        return obj;
+    }
+    __destroy_into_raw() {
+        const ptr = this.__wbg_ptr;
+        this.__wbg_ptr = 0;
+        ParticleFinalization.unregister(this);
+        return ptr;
+    }
+    free() {
+        const ptr = this.__destroy_into_raw();
+        wasm.__wbg_particle_free(ptr, 0);
+    }
+    /**
+     * @returns {number}
+     */
+    get x() {
+        const ret = wasm.__wbg_get_particle_x(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @param {number} arg0
+     */
+    set x(arg0) {
+        wasm.__wbg_set_particle_x(this.__wbg_ptr, arg0);
+    }
+    /**
+     * @returns {number}
+     */
+    get y() {
+        const ret = wasm.__wbg_get_particle_y(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @param {number} arg0
+     */
+    set y(arg0) {
+        wasm.__wbg_set_particle_y(this.__wbg_ptr, arg0);
+    }
+    /**
+     * @returns {number}
+     */
+    get radius() {
+        const ret = wasm.__wbg_get_particle_radius(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @param {number} arg0
+     */
+    set radius(arg0) {
+        wasm.__wbg_set_particle_radius(this.__wbg_ptr, arg0);
+    }
+    /**
+     * @returns {number}
+     */
+    get mass() {
+        const ret = wasm.__wbg_get_particle_mass(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @param {number} arg0
+     */
+    set mass(arg0) {
+        wasm.__wbg_set_particle_mass(this.__wbg_ptr, arg0);
+    }
+    /**
+     * @returns {number}
+     */
+    get vx() {
+        const ret = wasm.__wbg_get_particle_vx(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @param {number} arg0
+     */
+    set vx(arg0) {
+        wasm.__wbg_set_particle_vx(this.__wbg_ptr, arg0);
+    }
+    /**
+     * @returns {number}
+     */
+    get vy() {
+        const ret = wasm.__wbg_get_particle_vy(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @param {number} arg0
+     */
+    set vy(arg0) {
+        wasm.__wbg_set_particle_vy(this.__wbg_ptr, arg0);
+    }
+    /**
+     * @returns {boolean}
+     */
+    get colliding() {
+        const ret = wasm.__wbg_get_particle_colliding(this.__wbg_ptr);
+        return ret !== 0;
+    }
+    /**
+     * @param {boolean} arg0
+     */
+    set colliding(arg0) {
+        wasm.__wbg_set_particle_colliding(this.__wbg_ptr, arg0);
+    }
+    /**
+     * @returns {number}
+     */
+    get impulse_x() {
+        const ret = wasm.__wbg_get_particle_impulse_x(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @param {number} arg0
+     */
+    set impulse_x(arg0) {
+        wasm.__wbg_set_particle_impulse_x(this.__wbg_ptr, arg0);
+    }
+    /**
+     * @returns {number}
+     */
+    get impulse_y() {
+        const ret = wasm.__wbg_get_particle_impulse_y(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @param {number} arg0
+     */
+    set impulse_y(arg0) {
+        wasm.__wbg_set_particle_impulse_y(this.__wbg_ptr, arg0);
+    }
+    /**
+     * @returns {number}
+     */
+    get color_temp() {
+        const ret = wasm.__wbg_get_particle_color_temp(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @param {number} arg0
+     */
+    set color_temp(arg0) {
+        wasm.__wbg_set_particle_color_temp(this.__wbg_ptr, arg0);
+    }
+    /**
+     * @returns {number}
+     */
+    get prev_x() {
+        const ret = wasm.__wbg_get_particle_prev_x(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @param {number} arg0
+     */
+    set prev_x(arg0) {
+        wasm.__wbg_set_particle_prev_x(this.__wbg_ptr, arg0);
+    }
+    /**
+     * @returns {number}
+     */
+    get prev_y() {
+        const ret = wasm.__wbg_get_particle_prev_y(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @param {number} arg0
+     */
+    set prev_y(arg0) {
+        wasm.__wbg_set_particle_prev_y(this.__wbg_ptr, arg0);
+    }
+    /**
+     * @returns {number}
+     */
+    get id() {
+        const ret = wasm.__wbg_get_particle_id(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @param {number} arg0
+     */
+    set id(arg0) {
+        wasm.__wbg_set_particle_id(this.__wbg_ptr, arg0);
+    }
+}
+if (Symbol.dispose) Particle.prototype[Symbol.dispose] = Particle.prototype.free;
+
+/**
+ * Plays back an exported trace. Frames are exposed through the same
+ * `get_particles_ptr` interface as `Simulation`, with `prev_x`/`prev_y`
+ * equal to `x`/`y` and velocities zeroed.
+ */
+export class Player {
+    __destroy_into_raw() {
+        const ptr = this.__wbg_ptr;
+        this.__wbg_ptr = 0;
+        PlayerFinalization.unregister(this);
+        return ptr;
+    }
+    free() {
+        const ptr = this.__destroy_into_raw();
+        wasm.__wbg_player_free(ptr, 0);
+    }
+    /**
+     * @returns {number}
+     */
+    get_height() {
+        const ret = wasm.player_get_height(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @returns {number}
+     */
+    get_capacity() {
+        const ret = wasm.player_get_capacity(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * Index of the current recorded frame.
+     * @returns {number}
+     */
+    get_position() {
+        const ret = wasm.player_get_position(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @returns {number}
+     */
+    get_frame_count() {
+        const ret = wasm.player_get_frame_count(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * Packs the current frame into `Particle` records (see
+     * `particle_layout`) and returns the buffer, exactly as
+     * `Simulation::get_particles_ptr` does.
+     * @returns {number}
+     */
+    get_particles_ptr() {
+        const ret = wasm.player_get_particles_ptr(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @returns {number}
+     */
+    get_particle_count() {
+        const ret = wasm.player_get_particle_count(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @returns {number}
+     */
+    get_buffer_generation() {
+        const ret = wasm.player_get_buffer_generation(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * Parses and checks every frame of `bytes` up front, so seeking can't fail.
+     * @param {Uint8Array} bytes
+     */
+    constructor(bytes) {
+        const ptr0 = passArray8ToWasm0(bytes, wasm.__wbindgen_malloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ret = wasm.player_new(ptr0, len0);
+        if (ret[2]) {
+            throw takeFromExternrefTable0(ret[1]);
+        }
+        this.__wbg_ptr = ret[0] >>> 0;
+        PlayerFinalization.register(this, this.__wbg_ptr, this);
+        return this;
+    }
+    /**
+     * Moves to recorded frame `index` (clamped to the recording). Stepping
+     * forward by one decodes a single frame; any other jump decodes from
+     * the nearest keyframe before `index`.
+     * @param {number} index
+     */
+    seek(index) {
+        wasm.player_seek(this.__wbg_ptr, index);
+    }
+    /**
+     * The simulation frame number the current frame was captured at.
+     * @returns {bigint}
+     */
+    get_frame() {
+        const ret = wasm.player_get_frame(this.__wbg_ptr);
+        return BigInt.asUintN(64, ret);
+    }
+    /**
+     * @returns {number}
+     */
+    get_width() {
+        const ret = wasm.player_get_width(this.__wbg_ptr);
+        return ret;
+    }
+}
+if (Symbol.dispose) Player.prototype[Symbol.dispose] = Player.prototype.free;
+
+/**
+ * What the pointer does to the particles around it.
+ * @enum {0 | 1 | 2 | 3}
+ */
+export const PointerMode = Object.freeze({
+    Off: 0, "0": "Off",
+    /**
+     * Pulls particles in, like an `Attractor`.
+     */
+    Attract: 1, "1": "Attract",
+    /**
+     * Pushes them away, like a `Repulsor`.
+     */
+    Repel: 2, "2": "Repel",
+    /**
+     * Swirls them around, like a `Vortex`.
+     */
+    Stir: 3, "3": "Stir",
+});
+
+/**
+ * Captures frames from a `Simulation` into a ring buffer of at most
+ * `max_bytes`, oldest chunks dropped first.
+ */
+export class Recorder {
+    __destroy_into_raw() {
+        const ptr = this.__wbg_ptr;
+        this.__wbg_ptr = 0;
+        RecorderFinalization.unregister(this);
+        return ptr;
+    }
+    free() {
+        const ptr = this.__destroy_into_raw();
+        wasm.__wbg_recorder_free(ptr, 0);
+    }
+    /**
+     * Encoded size of the recorded frames.
+     * @returns {number}
+     */
+    get_byte_size() {
+        const ret = wasm.recorder_get_byte_size(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @returns {number}
+     */
+    get_frame_count() {
+        const ret = wasm.recorder_get_frame_count(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @param {number} max_bytes
+     */
+    constructor(max_bytes) {
+        const ret = wasm.recorder_new(max_bytes);
+        this.__wbg_ptr = ret >>> 0;
+        RecorderFinalization.register(this, this.__wbg_ptr, this);
+        return this;
+    }
+    clear() {
+        wasm.recorder_clear(this.__wbg_ptr);
+    }
+    /**
+     * The recording as a self-contained trace file for `Player`.
+     * @returns {Uint8Array}
+     */
+    export() {
+        const ret = wasm.recorder_export(this.__wbg_ptr);
+        var v1 = getArrayU8FromWasm0(ret[0], ret[1]).slice();
+        wasm.__wbindgen_free(ret[0], ret[1] * 1, 1);
+        return v1;
+    }
+    /**
+     * Records the simulation's current frame. Call after every `update`;
+     * calls that find the same frame as last time are ignored.
+     * @param {Simulation} simulation
+     */
+    capture(simulation) {
+        _assertClass(simulation, Simulation);
+        wasm.recorder_capture(this.__wbg_ptr, simulation.__wbg_ptr);
+    }
+}
+if (Symbol.dispose) Recorder.prototype[Symbol.dispose] = Recorder.prototype.free;
+
+export class Simulation {
+    __destroy_into_raw() {
+        const ptr = this.__wbg_ptr;
+        this.__wbg_ptr = 0;
+        SimulationFinalization.unregister(this);
+        return ptr;
+    }
+    free() {
+        const ptr = this.__destroy_into_raw();
+        wasm.__wbg_simulation_free(ptr, 0);
+    }
+    /**
+     * Adds a solid circle and returns its id. Returns `undefined` unless
+     * `radius` is positive.
+     * @param {number} x
+     * @param {number} y
+     * @param {number} radius
+     * @returns {number | undefined}
+     */
+    add_circle(x, y, radius) {
+        const ret = wasm.simulation_add_circle(this.__wbg_ptr, x, y, radius);
+        return ret === 0x100000001 ? undefined : ret;
+    }
+    /**
+     * @returns {number}
+     */
+    get_height() {
+        const ret = wasm.simulation_get_height(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @returns {number}
+     */
+    get_vx_ptr() {
+        const ret = wasm.simulation_get_vx_ptr(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @returns {number}
+     */
+    get_vy_ptr() {
+        const ret = wasm.simulation_get_vy_ptr(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * Ids, ascending, of the particles touching the rectangle with corners
+     * (x0, y0) and (x1, y1).
+     * @param {number} x0
+     * @param {number} y0
+     * @param {number} x1
+     * @param {number} y1
+     * @returns {Uint32Array}
+     */
+    query_rect(x0, y0, x1, y1) {
+        const ret = wasm.simulation_query_rect(this.__wbg_ptr, x0, y0, x1, y1);
+        var v1 = getArrayU32FromWasm0(ret[0], ret[1]).slice();
+        wasm.__wbindgen_free(ret[0], ret[1] * 4, 4);
+        return v1;
+    }
+    /**
+     * Adds a force emitter and returns its id.
+     * @param {Emitter} emitter
+     * @returns {number}
+     */
+    add_emitter(emitter) {
+        _assertClass(emitter, Emitter);
+        const ret = wasm.simulation_add_emitter(this.__wbg_ptr, emitter.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * Adds a solid polygon outlined by `points`, given as flat (x, y)
+     * pairs, and returns its id. It may be concave but shouldn't cross
+     * itself. Returns `undefined` for fewer than three points.
+     * @param {Float32Array} points
+     * @returns {number | undefined}
+     */
+    add_polygon(points) {
+        const ptr0 = passArrayF32ToWasm0(points, wasm.__wbindgen_malloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ret = wasm.simulation_add_polygon(this.__wbg_ptr, ptr0, len0);
+        return ret === 0x100000001 ? undefined : ret;
+    }
+    /**
+     * Energies, momentum and speeds now, plus the collisions and energy
+     * lost during the last step (see `diagnostics.rs`). O(n).
+     * @returns {Diagnostics}
+     */
+    diagnostics() {
+        const ret = wasm.simulation_diagnostics(this.__wbg_ptr);
+        return Diagnostics.__wrap(ret);
+    }
+    /**
+     * @param {number} id
+     * @returns {Emitter | undefined}
+     */
+    get_emitter(id) {
+        const ret = wasm.simulation_get_emitter(this.__wbg_ptr, id);
+        return ret === 0 ? undefined : Emitter.__wrap(ret);
+    }
+    /**
+     * Id of the grabbed particle, or `undefined`.
+     * @returns {number | undefined}
+     */
+    get_grabbed() {
+        const ret = wasm.simulation_get_grabbed(this.__wbg_ptr);
+        return ret === 0x100000001 ? undefined : ret;
+    }
+    /**
+     * @returns {number}
+     */
+    get_gravity() {
+        const ret = wasm.simulation_get_gravity(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * Each slot's particle id (`u32`).
+     * @returns {number}
+     */
+    get_ids_ptr() {
+        const ret = wasm.simulation_get_ids_ptr(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @returns {number}
+     */
+    get_threads() {
+        const ret = wasm.simulation_get_threads(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @param {number} id
+     * @returns {boolean}
+     */
+    remove_body(id) {
+        const ret = wasm.simulation_remove_body(this.__wbg_ptr, id);
+        return ret !== 0;
+    }
+    /**
+     * Moves, reshapes or retunes emitter `id`. Returns false if there is no
+     * such emitter.
+     * @param {number} id
+     * @param {Emitter} emitter
+     * @returns {boolean}
+     */
+    set_emitter(id, emitter) {
+        _assertClass(emitter, Emitter);
+        const ret = wasm.simulation_set_emitter(this.__wbg_ptr, id, emitter.__wbg_ptr);
+        return ret !== 0;
+    }
+    /**
+     * @param {number} gravity
+     */
+    set_gravity(gravity) {
+        wasm.simulation_set_gravity(this.__wbg_ptr, gravity);
+    }
+    /**
+     * Moves the pointer to (x, y) and sets what it does there. Its force
+     * covers `get_pointer_radius` and acts after every emitter's.
+     * @param {number} x
+     * @param {number} y
+     * @param {PointerMode} mode
+     */
+    set_pointer(x, y, mode) {
+        wasm.simulation_set_pointer(this.__wbg_ptr, x, y, mode);
+    }
+    /**
+     * How many threads `detect_collisions` splits the broadphase queries
+     * across. Defaults to the number of cores in native builds with the
+     * `threads` feature and to 1 otherwise; without threads to run on, the
+     * chunks run in turn. The result never depends on the count.
+     * @param {number} threads
+     */
+    set_threads(threads) {
+        wasm.simulation_set_threads(this.__wbg_ptr, threads);
+    }
+    /**
+     * @param {number} x
+     * @param {number} y
+     */
+    add_particle(x, y) {
+        wasm.simulation_add_particle(this.__wbg_ptr, x, y);
+    }
+    /**
+     * Adds an open chain of line segments through `points`, given as flat
+     * (x, y) pairs, and returns its id. Returns `undefined` for fewer than
+     * two points.
+     * @param {Float32Array} points
+     * @returns {number | undefined}
+     */
+    add_segments(points) {
+        const ptr0 = passArrayF32ToWasm0(points, wasm.__wbindgen_malloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ret = wasm.simulation_add_segments(this.__wbg_ptr, ptr0, len0);
+        return ret === 0x100000001 ? undefined : ret;
+    }
+    clear_bodies() {
+        wasm.simulation_clear_bodies(this.__wbg_ptr);
+    }
+    /**
+     * Ids of every body, oldest first.
+     * @returns {Uint32Array}
+     */
+    get_body_ids() {
+        const ret = wasm.simulation_get_body_ids(this.__wbg_ptr);
+        var v1 = getArrayU32FromWasm0(ret[0], ret[1]).slice();
+        wasm.__wbindgen_free(ret[0], ret[1] * 4, 4);
+        return v1;
+    }
+    /**
+     * @returns {BoundaryMode}
+     */
+    get_boundary() {
+        const ret = wasm.simulation_get_boundary(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @returns {number}
+     */
+    get_capacity() {
+        const ret = wasm.simulation_get_capacity(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @returns {number}
+     */
+    get_max_size() {
+        const ret = wasm.simulation_get_max_size(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @returns {number}
+     */
+    get_min_size() {
+        const ret = wasm.simulation_get_min_size(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * The particle with this `id`, wherever it is stored.
+     * @param {number} id
+     * @returns {Particle | undefined}
+     */
+    get_particle(id) {
+        const ret = wasm.simulation_get_particle(this.__wbg_ptr, id);
+        return ret === 0 ? undefined : Particle.__wrap(ret);
+    }
+    /**
+     * Ids, ascending, of the particles touching the circle of radius `r`
+     * around (x, y).
+     * @param {number} x
+     * @param {number} y
+     * @param {number} r
+     * @returns {Uint32Array}
+     */
+    query_radius(x, y, r) {
+        const ret = wasm.simulation_query_radius(this.__wbg_ptr, x, y, r);
+        var v1 = getArrayU32FromWasm0(ret[0], ret[1]).slice();
+        wasm.__wbindgen_free(ret[0], ret[1] * 4, 4);
+        return v1;
+    }
+    /**
+     * Sets what happens at the edge of the world. Returns false, changing
+     * nothing, for `Polygon` before any `set_boundary_polygon`.
+     * @param {BoundaryMode} mode
+     * @returns {boolean}
+     */
+    set_boundary(mode) {
+        const ret = wasm.simulation_set_boundary(this.__wbg_ptr, mode);
+        return ret !== 0;
+    }
+    /**
+     * @param {number} size
+     */
+    set_max_size(size) {
+        wasm.simulation_set_max_size(this.__wbg_ptr, size);
+    }
+    /**
+     * @param {number} size
+     */
+    set_min_size(size) {
+        wasm.simulation_set_min_size(this.__wbg_ptr, size);
+    }
+    /**
+     * Overwrites particle `id`'s position, previous position, velocity,
+     * radius and colour temperature with `particle`'s; its mass follows
+     * from the radius. Returns false if there is no such particle.
+     * @param {number} id
+     * @param {Particle} particle
+     * @returns {boolean}
+     */
+    set_particle(id, particle) {
+        _assertClass(particle, Particle);
+        const ret = wasm.simulation_set_particle(this.__wbg_ptr, id, particle.__wbg_ptr);
+        return ret !== 0;
+    }
+    /**
+     * @param {number} substeps
+     */
+    set_substeps(substeps) {
+        wasm.simulation_set_substeps(this.__wbg_ptr, substeps);
+    }
+    /**
+     * @returns {number}
+     */
+    get_fan_speed() {
+        const ret = wasm.simulation_get_fan_speed(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @returns {number}
+     */
+    get_max_speed() {
+        const ret = wasm.simulation_get_max_speed(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * Slot indices (`u32`) sorted by Z-order code as of the last collision pass.
+     * @returns {number}
+     */
+    get_order_ptr() {
+        const ret = wasm.simulation_get_order_ptr(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @returns {number}
+     */
+    get_radii_ptr() {
+        const ret = wasm.simulation_get_radii_ptr(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * Sets the strength of the fan every simulation starts with (emitter
+     * 0). Does nothing to the emitters if that fan has been removed.
+     * @param {number} speed
+     */
+    set_fan_speed(speed) {
+        wasm.simulation_set_fan_speed(this.__wbg_ptr, speed);
+    }
+    /**
+     * @param {number} speed
+     */
+    set_max_speed(speed) {
+        wasm.simulation_set_max_speed(this.__wbg_ptr, speed);
+    }
+    /**
+     * Removes every emitter, including the default fan.
+     */
+    clear_emitters() {
+        wasm.simulation_clear_emitters(this.__wbg_ptr);
+    }
+    /**
+     * @returns {BroadphaseKind}
+     */
+    get_broadphase() {
+        const ret = wasm.simulation_get_broadphase(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @returns {number}
+     */
+    get_elasticity() {
+        const ret = wasm.simulation_get_elasticity(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * The collisions resolved during the last `update` or `step`, strongest
+     * first, as `get_event_count()` `CollisionEvent` records (see
+     * `collision_event_layout`). The pointer only moves when the capacity
+     * changes.
+     * @returns {number}
+     */
+    get_events_ptr() {
+        const ret = wasm.simulation_get_events_ptr(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @returns {number}
+     */
+    get_masses_ptr() {
+        const ret = wasm.simulation_get_masses_ptr(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @returns {number}
+     */
+    get_prev_x_ptr() {
+        const ret = wasm.simulation_get_prev_x_ptr(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @returns {number}
+     */
+    get_prev_y_ptr() {
+        const ret = wasm.simulation_get_prev_y_ptr(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @returns {number}
+     */
+    kinetic_energy() {
+        const ret = wasm.simulation_kinetic_energy(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @param {number} id
+     * @returns {boolean}
+     */
+    remove_emitter(id) {
+        const ret = wasm.simulation_remove_emitter(this.__wbg_ptr, id);
+        return ret !== 0;
+    }
+    /**
+     * @param {BroadphaseKind} kind
+     */
+    set_broadphase(kind) {
+        wasm.simulation_set_broadphase(this.__wbg_ptr, kind);
+    }
+    /**
+     * @param {number} elasticity
+     */
+    set_elasticity(elasticity) {
+        wasm.simulation_set_elasticity(this.__wbg_ptr, elasticity);
+    }
+    /**
+     * Adds a solid circle centred at (x, y) relative to body `id`'s
+     * position and angle. Returns false unless `radius` is positive and the
+     * body exists.
+     * @param {number} id
+     * @param {number} x
+     * @param {number} y
+     * @param {number} radius
+     * @returns {boolean}
+     */
+    add_body_circle(id, x, y, radius) {
+        const ret = wasm.simulation_add_body_circle(this.__wbg_ptr, id, x, y, radius);
+        return ret !== 0;
+    }
+    clear_obstacles() {
+        wasm.simulation_clear_obstacles(this.__wbg_ptr);
+    }
+    /**
+     * @param {number} id
+     * @returns {BodyMotion | undefined}
+     */
+    get_body_motion(id) {
+        const ret = wasm.simulation_get_body_motion(this.__wbg_ptr, id);
+        return ret === 3 ? undefined : ret;
+    }
+    /**
+     * Ids of every emitter, in the order their forces are applied.
+     * @returns {Uint32Array}
+     */
+    get_emitter_ids() {
+        const ret = wasm.simulation_get_emitter_ids(this.__wbg_ptr);
+        var v1 = getArrayU32FromWasm0(ret[0], ret[1]).slice();
+        wasm.__wbindgen_free(ret[0], ret[1] * 4, 4);
+        return v1;
+    }
+    /**
+     * @returns {number}
+     */
+    get_event_count() {
+        const ret = wasm.simulation_get_event_count(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @param {number} id
+     * @returns {boolean}
+     */
+    remove_obstacle(id) {
+        const ret = wasm.simulation_remove_obstacle(this.__wbg_ptr, id);
+        return ret !== 0;
+    }
+    /**
+     * Removes particle `id`. Returns false if there is no such particle.
+     * @param {number} id
+     * @returns {boolean}
+     */
+    remove_particle(id) {
+        const ret = wasm.simulation_remove_particle(this.__wbg_ptr, id);
+        return ret !== 0;
+    }
+    /**
+     * Particle counts in `bins` equal speed ranges from 0 to `max_speed`,
+     * with faster particles counted in the last bin.
+     * @param {number} bins
+     * @param {number} max_speed
+     * @returns {Uint32Array}
+     */
+    speed_histogram(bins, max_speed) {
+        const ret = wasm.simulation_speed_histogram(this.__wbg_ptr, bins, max_speed);
+        var v1 = getArrayU32FromWasm0(ret[0], ret[1]).slice();
+        wasm.__wbindgen_free(ret[0], ret[1] * 4, 4);
+        return v1;
+    }
+    /**
+     * Adds a solid polygon to body `id`, outlined by `points` given as flat
+     * (x, y) pairs relative to the body's position and angle. Returns false
+     * for fewer than three points or no such body.
+     * @param {number} id
+     * @param {Float32Array} points
+     * @returns {boolean}
+     */
+    add_body_polygon(id, points) {
+        const ptr0 = passArrayF32ToWasm0(points, wasm.__wbindgen_malloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ret = wasm.simulation_add_body_polygon(this.__wbg_ptr, id, ptr0, len0);
+        return ret !== 0;
+    }
+    /**
+     * Ids of every obstacle, oldest first.
+     * @returns {Uint32Array}
+     */
+    get_obstacle_ids() {
+        const ret = wasm.simulation_get_obstacle_ids(this.__wbg_ptr);
+        var v1 = getArrayU32FromWasm0(ret[0], ret[1]).slice();
+        wasm.__wbindgen_free(ret[0], ret[1] * 4, 4);
+        return v1;
+    }
+    /**
+     * @returns {PointerMode}
+     */
+    get_pointer_mode() {
+        const ret = wasm.simulation_get_pointer_mode(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @returns {number}
+     */
+    get_search_range() {
+        const ret = wasm.simulation_get_search_range(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * Milliseconds spent sorting by Z-order code during the last `step`,
+     * summed over its substeps.
+     * @returns {number}
+     */
+    get_sort_time_ms() {
+        const ret = wasm.simulation_get_sort_time_ms(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * Removes every particle touching the circle of radius `r` around
+     * (x, y) and returns their ids, ascending.
+     * @param {number} x
+     * @param {number} y
+     * @param {number} r
+     * @returns {Uint32Array}
+     */
+    remove_in_radius(x, y, r) {
+        const ret = wasm.simulation_remove_in_radius(this.__wbg_ptr, x, y, r);
+        var v1 = getArrayU32FromWasm0(ret[0], ret[1]).slice();
+        wasm.__wbindgen_free(ret[0], ret[1] * 4, 4);
+        return v1;
+    }
+    /**
+     * How many consecutive non-neighbours the Z-order broadphase steps over before
+     * jumping ahead with BIGMIN. Only affects speed, never which pairs are found.
+     * @param {number} range
+     */
+    set_search_range(range) {
+        wasm.simulation_set_search_range(this.__wbg_ptr, range);
+    }
+    /**
+     * Adds an open chain of segments to body `id`, through `points` given
+     * as flat (x, y) pairs relative to the body's position and angle.
+     * Returns false for fewer than two points or no such body.
+     * @param {number} id
+     * @param {Float32Array} points
+     * @returns {boolean}
+     */
+    add_body_segments(id, points) {
+        const ptr0 = passArrayF32ToWasm0(points, wasm.__wbindgen_malloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ret = wasm.simulation_add_body_segments(this.__wbg_ptr, id, ptr0, len0);
+        return ret !== 0;
+    }
+    /**
+     * One byte per slot, 1 while touching another particle.
+     * @returns {number}
+     */
+    get_colliding_ptr() {
+        const ret = wasm.simulation_get_colliding_ptr(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * Memory held by the history.
+     * @returns {number}
+     */
+    get_history_bytes() {
+        const ret = wasm.simulation_get_history_bytes(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * Earliest frame `rewind_to` can reach.
+     * @returns {bigint}
+     */
+    get_history_start() {
+        const ret = wasm.simulation_get_history_start(this.__wbg_ptr);
+        return BigInt.asUintN(64, ret);
+    }
+    /**
+     * @param {number} id
+     * @returns {ObstacleKind | undefined}
+     */
+    get_obstacle_kind(id) {
+        const ret = wasm.simulation_get_obstacle_kind(this.__wbg_ptr, id);
+        return ret === 3 ? undefined : ret;
+    }
+    /**
+     * Packs every particle into a `Particle` record (see `particle_layout`)
+     * in slot order and returns the buffer. Call once per frame after
+     * `update`; the pointer is stable until `get_buffer_generation` changes.
+     * @returns {number}
+     */
+    get_particles_ptr() {
+        const ret = wasm.simulation_get_particles_ptr(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * Packed `[x0, y0, x1, y1, ...]` in slot order. Refreshed by each call;
+     * read it before the next `update`.
+     * @returns {number}
+     */
+    get_positions_ptr() {
+        const ret = wasm.simulation_get_positions_ptr(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * Spins body `id` about its position at `speed` radians per reference
+     * frame, clockwise on screen for a positive speed, like a drum.
+     * Returns false if there is no such body.
+     * @param {number} id
+     * @param {number} speed
+     * @returns {boolean}
+     */
+    set_body_rotation(id, speed) {
+        const ret = wasm.simulation_set_body_rotation(this.__wbg_ptr, id, speed);
+        return ret !== 0;
+    }
+    /**
+     * @returns {number}
+     */
+    get_event_capacity() {
+        const ret = wasm.simulation_get_event_capacity(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @returns {number}
+     */
+    get_history_length() {
+        const ret = wasm.simulation_get_history_length(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @returns {number}
+     */
+    get_particle_count() {
+        const ret = wasm.simulation_get_particle_count(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @returns {number}
+     */
+    get_pointer_radius() {
+        const ret = wasm.simulation_get_pointer_radius(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * Records up to `max_events` collisions per `update` or `step` for
+     * `get_events_ptr`, keeping the strongest when there are more. 0, the
+     * default, records none.
+     * @param {number} max_events
+     */
+    set_event_capacity(max_events) {
+        wasm.simulation_set_event_capacity(this.__wbg_ptr, max_events);
+    }
+    /**
+     * @param {number} seconds
+     */
+    set_fixed_timestep(seconds) {
+        wasm.simulation_set_fixed_timestep(this.__wbg_ptr, seconds);
+    }
+    /**
+     * How many past steps `step_back` and `rewind_to` can undo. 0, the
+     * default, turns recording off; otherwise every `step` first snapshots
+     * the state, and the history holds one full snapshot, a keyframe
+     * every 30 frames and a delta of the changed bytes for the rest.
+     * @param {number} frames
+     */
+    set_history_length(frames) {
+        wasm.simulation_set_history_length(this.__wbg_ptr, frames);
+    }
+    /**
+     * Sets how many particles `reset` spawns. Below the current count, the
+     * newest particles are removed straight away.
+     * @param {number} count
+     */
+    set_particle_count(count) {
+        wasm.simulation_set_particle_count(this.__wbg_ptr, count);
+    }
+    /**
+     * @param {number} radius
+     */
+    set_pointer_radius(radius) {
+        wasm.simulation_set_pointer_radius(this.__wbg_ptr, radius);
+    }
+    /**
+     * @param {SortAlgorithm} algorithm
+     */
+    set_sort_algorithm(algorithm) {
+        wasm.simulation_set_sort_algorithm(this.__wbg_ptr, algorithm);
+    }
+    /**
+     * @param {number} id
+     * @param {number} shape
+     * @returns {ObstacleKind | undefined}
+     */
+    get_body_shape_kind(id, shape) {
+        const ret = wasm.simulation_get_body_shape_kind(this.__wbg_ptr, id, shape);
+        return ret === 3 ? undefined : ret;
+    }
+    /**
+     * The container's shape for drawing: the polygon's points as flat
+     * (x, y) pairs, the circle's centre and radius, or nothing for the
+     * other modes.
+     * @returns {Float32Array}
+     */
+    get_boundary_points() {
+        const ret = wasm.simulation_get_boundary_points(this.__wbg_ptr);
+        var v1 = getArrayF32FromWasm0(ret[0], ret[1]).slice();
+        wasm.__wbindgen_free(ret[0], ret[1] * 4, 4);
+        return v1;
+    }
+    /**
+     * Number of particles touching at least one other particle this frame.
+     * @returns {number}
+     */
+    get_collision_count() {
+        const ret = wasm.simulation_get_collision_count(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * The points obstacle `id` was added with: flat (x, y) pairs, or x, y
+     * and radius for a circle. Empty if there is no such obstacle.
+     * @param {number} id
+     * @returns {Float32Array}
+     */
+    get_obstacle_points(id) {
+        const ret = wasm.simulation_get_obstacle_points(this.__wbg_ptr, id);
+        var v1 = getArrayF32FromWasm0(ret[0], ret[1]).slice();
+        wasm.__wbindgen_free(ret[0], ret[1] * 4, 4);
+        return v1;
+    }
+    /**
+     * How many shapes body `id` has; 0 if there is no such body.
+     * @param {number} id
+     * @returns {number}
+     */
+    get_body_shape_count(id) {
+        const ret = wasm.simulation_get_body_shape_count(this.__wbg_ptr, id);
+        return ret >>> 0;
+    }
+    /**
+     * @returns {number}
+     */
+    get_pointer_strength() {
+        const ret = wasm.simulation_get_pointer_strength(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * `color_temp` per slot.
+     * @returns {number}
+     */
+    get_temperatures_ptr() {
+        const ret = wasm.simulation_get_temperatures_ptr(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * Swings body `id` back and forth by (amplitude_x, amplitude_y) either
+     * side of where it is, once every `period` reference frames, like a
+     * piston. Returns false unless `period` is positive and the body
+     * exists.
+     * @param {number} id
+     * @param {number} amplitude_x
+     * @param {number} amplitude_y
+     * @param {number} period
+     * @returns {boolean}
+     */
+    set_body_oscillation(id, amplitude_x, amplitude_y, period) {
+        const ret = wasm.simulation_set_body_oscillation(this.__wbg_ptr, id, amplitude_x, amplitude_y, period);
+        return ret !== 0;
+    }
+    /**
+     * Switches to a polygon container outlined by `points`, given as flat
+     * (x, y) pairs. Returns false, changing nothing, for fewer than three
+     * points.
+     * @param {Float32Array} points
+     * @returns {boolean}
+     */
+    set_boundary_polygon(points) {
+        const ptr0 = passArrayF32ToWasm0(points, wasm.__wbindgen_malloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ret = wasm.simulation_set_boundary_polygon(this.__wbg_ptr, ptr0, len0);
+        return ret !== 0;
+    }
+    /**
+     * Peak acceleration of the pointer's force, as for an emitter.
+     * @param {number} strength
+     */
+    set_pointer_strength(strength) {
+        wasm.simulation_set_pointer_strength(this.__wbg_ptr, strength);
+    }
+    /**
+     * Shape `shape` of body `id` where it stands now, for drawing: flat
+     * (x, y) pairs, or x, y and radius for a circle. Empty if there is no
+     * such shape.
+     * @param {number} id
+     * @param {number} shape
+     * @returns {Float32Array}
+     */
+    get_body_shape_points(id, shape) {
+        const ret = wasm.simulation_get_body_shape_points(this.__wbg_ptr, id, shape);
+        var v1 = getArrayF32FromWasm0(ret[0], ret[1]).slice();
+        wasm.__wbindgen_free(ret[0], ret[1] * 4, 4);
+        return v1;
+    }
+    /**
+     * Incremented whenever the particle arrays or the `get_particles_ptr`
+     * buffer are reallocated. Views over them must be rebuilt when this
+     * changes, and also when WASM memory grows (`memory.buffer` is replaced).
+     * @returns {number}
+     */
+    get_buffer_generation() {
+        const ret = wasm.simulation_get_buffer_generation(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * Checks the current broadphase against a brute-force pass over the
+     * current positions and returns how many overlapping pairs it missed.
+     * O(n²); meant for validation, not for every frame.
+     * @returns {number}
+     */
+    count_missed_collisions() {
+        const ret = wasm.simulation_count_missed_collisions(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * How far the banked time has progressed into the next fixed step, in
+     * [0, 1). Render at `prev + (pos - prev) * alpha` for smooth motion.
+     * @returns {number}
+     */
+    get_interpolation_alpha() {
+        const ret = wasm.simulation_get_interpolation_alpha(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * The counts `speed_histogram` would show for the same bins if the
+     * particles were in Maxwell–Boltzmann equilibrium at the current
+     * temperature. O(n × bins).
+     * @param {number} bins
+     * @param {number} max_speed
+     * @returns {Float64Array}
+     */
+    maxwell_boltzmann_histogram(bins, max_speed) {
+        const ret = wasm.simulation_maxwell_boltzmann_histogram(this.__wbg_ptr, bins, max_speed);
+        var v1 = getArrayF64FromWasm0(ret[0], ret[1]).slice();
+        wasm.__wbindgen_free(ret[0], ret[1] * 8, 8);
+        return v1;
+    }
+    /**
+     * @param {number} width
+     * @param {number} height
+     * @param {number} particle_count
+     * @param {number} min_size
+     * @param {number} max_size
+     * @param {number} max_speed
+     * @param {number} gravity
+     * @param {number} elasticity
+     * @param {number} fan_speed
+     * @param {number} search_range
+     * @param {bigint} seed
+     */
+    constructor(width, height, particle_count, min_size, max_size, max_speed, gravity, elasticity, fan_speed, search_range, seed) {
+        const ret = wasm.simulation_new(width, height, particle_count, min_size, max_size, max_speed, gravity, elasticity, fan_speed, search_range, seed);
+        this.__wbg_ptr = ret >>> 0;
+        SimulationFinalization.register(this, this.__wbg_ptr, this);
+        return this;
+    }
+    /**
+     * Holds particle `id` where it is with a stiff, critically damped
+     * spring, letting go of any other. Returns false if there is no such
+     * particle.
+     * @param {number} id
+     * @returns {boolean}
+     */
+    grab(id) {
+        const ret = wasm.simulation_grab(this.__wbg_ptr, id);
+        return ret !== 0;
+    }
+    /**
+     * Id of the particle under (x, y), or `undefined`. Where particles
+     * overlap, the one whose centre is nearest wins.
+     * @param {number} x
+     * @param {number} y
+     * @returns {number | undefined}
+     */
+    pick(x, y) {
+        const ret = wasm.simulation_pick(this.__wbg_ptr, x, y);
+        return ret === 0x100000001 ? undefined : ret;
+    }
+    /**
+     * Advances exactly one fixed timestep, split into `substeps` integration
+     * and collision passes.
+     */
+    step() {
+        wasm.simulation_step(this.__wbg_ptr);
+    }
+    /**
+     * Removes every particle. Unlike `reset`, nothing is respawned, ids keep
+     * counting up and the count `reset` spawns is left alone.
+     */
+    clear() {
+        wasm.simulation_clear(this.__wbg_ptr);
+    }
+    /**
+     * Rebuilds the particle field. The generator is rewound to the current
+     * seed first, so the same seed and parameters give the same layout.
+     */
+    reset() {
+        wasm.simulation_reset(this.__wbg_ptr);
+    }
+    /**
+     * Replaces the seed and rewinds the generator. Existing particles are
+     * left alone; call `reset` to respawn them from the new seed.
+     * @param {bigint} seed
+     */
+    reseed(seed) {
+        wasm.simulation_reseed(this.__wbg_ptr, seed);
+    }
+    /**
+     * Advances the simulation by `dt` seconds of wall-clock time. Elapsed
+     * time is banked and spent in whole fixed steps, so motion is the same at
+     * any refresh rate; the remainder is reported by `get_interpolation_alpha`.
+     * @param {number} dt
+     */
+    update(dt) {
+        wasm.simulation_update(this.__wbg_ptr, dt);
+    }
+    /**
+     * Moves the grabbed particle's spring anchor to (x, y).
+     * @param {number} x
+     * @param {number} y
+     */
+    drag_to(x, y) {
+        wasm.simulation_drag_to(this.__wbg_ptr, x, y);
+    }
+    /**
+     * Lets go of the grabbed particle, flinging it at (vx, vy) pixels per
+     * reference frame. Returns false if nothing was held.
+     * @param {number} vx
+     * @param {number} vy
+     * @returns {boolean}
+     */
+    release(vx, vy) {
+        const ret = wasm.simulation_release(this.__wbg_ptr, vx, vy);
+        return ret !== 0;
+    }
+    /**
+     * Pre-allocates room for `max_particles` so that `reset` and
+     * `add_particle` up to that count never move the particle buffer.
+     * @param {number} max_particles
+     */
+    reserve(max_particles) {
+        wasm.simulation_reserve(this.__wbg_ptr, max_particles);
+    }
+    /**
+     * Replaces the whole simulation with a `snapshot`. On error nothing is
+     * changed. Sort, broadphase, thread and SIMD choices are kept, since
+     * they never affect the result.
+     * @param {Uint8Array} bytes
+     */
+    restore(bytes) {
+        const ptr0 = passArray8ToWasm0(bytes, wasm.__wbindgen_malloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ret = wasm.simulation_restore(this.__wbg_ptr, ptr0, len0);
+        if (ret[1]) {
+            throw takeFromExternrefTable0(ret[0]);
+        }
+    }
+    /**
+     * Adds a kinematic body at (x, y), standing still with no shapes yet,
+     * and returns its id. Give it shapes with `add_body_segments`,
+     * `add_body_polygon` and `add_body_circle`, and motion with
+     * `move_body`, `set_body_oscillation` or `set_body_rotation`.
+     * @param {number} x
+     * @param {number} y
+     * @returns {number}
+     */
+    add_body(x, y) {
+        const ret = wasm.simulation_add_body(this.__wbg_ptr, x, y);
+        return ret >>> 0;
+    }
+    /**
+     * Body `id`'s pose and velocity.
+     * @param {number} id
+     * @returns {BodyState | undefined}
+     */
+    get_body(id) {
+        const ret = wasm.simulation_get_body(this.__wbg_ptr, id);
+        return ret === 0 ? undefined : BodyState.__wrap(ret);
+    }
+    /**
+     * @returns {bigint}
+     */
+    get_seed() {
+        const ret = wasm.simulation_get_seed(this.__wbg_ptr);
+        return BigInt.asUintN(64, ret);
+    }
+    /**
+     * @returns {boolean}
+     */
+    get_simd() {
+        const ret = wasm.simulation_get_simd(this.__wbg_ptr);
+        return ret !== 0;
+    }
+    /**
+     * Slot currently holding particle `id`, or `undefined` if there is no
+     * such particle.
+     * @param {number} id
+     * @returns {number | undefined}
+     */
+    get_slot(id) {
+        const ret = wasm.simulation_get_slot(this.__wbg_ptr, id);
+        return ret === 0x100000001 ? undefined : ret;
+    }
+    /**
+     * Run the integrator and the narrow-phase distance tests four particles
+     * at a time. Defaults to on in wasm builds with the `simd` feature, and
+     * to the scalar path elsewhere. Both produce identical results.
+     * @param {boolean} enabled
+     */
+    set_simd(enabled) {
+        wasm.simulation_set_simd(this.__wbg_ptr, enabled);
+    }
+    /**
+     * Serialises the world size, every constructor parameter and all
+     * particle state into a compact versioned binary format (see
+     * `snapshot.rs`). `restore` reproduces the run exactly from there.
+     * @returns {Uint8Array}
+     */
+    snapshot() {
+        const ret = wasm.simulation_snapshot(this.__wbg_ptr);
+        var v1 = getArrayU8FromWasm0(ret[0], ret[1]).slice();
+        wasm.__wbindgen_free(ret[0], ret[1] * 1, 1);
+        return v1;
+    }
+    /**
+     * Number of fixed steps taken since the last `reset`.
+     * @returns {bigint}
+     */
+    get_frame() {
+        const ret = wasm.simulation_get_frame(this.__wbg_ptr);
+        return BigInt.asUintN(64, ret);
+    }
+    /**
+     * Phase timings and pair counts for the last `update` or `step`.
+     * @returns {FrameStats}
+     */
+    get_stats() {
+        const ret = wasm.simulation_get_stats(this.__wbg_ptr);
+        return FrameStats.__wrap(ret);
+    }
+    /**
+     * @returns {number}
+     */
+    get_width() {
+        const ret = wasm.simulation_get_width(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @returns {number}
+     */
+    get_x_ptr() {
+        const ret = wasm.simulation_get_x_ptr(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @returns {number}
+     */
+    get_y_ptr() {
+        const ret = wasm.simulation_get_y_ptr(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * Ids of the `k` particles whose centres are nearest to (x, y), nearest
+     * first.
+     * @param {number} x
+     * @param {number} y
+     * @param {number} k
+     * @returns {Uint32Array}
+     */
+    k_nearest(x, y, k) {
+        const ret = wasm.simulation_k_nearest(this.__wbg_ptr, x, y, k);
+        var v1 = getArrayU32FromWasm0(ret[0], ret[1]).slice();
+        wasm.__wbindgen_free(ret[0], ret[1] * 4, 4);
+        return v1;
+    }
+    /**
+     * Sends body `id` to (x, y), turned to `angle` radians clockwise, over
+     * the next step, and stops any built-in motion. Call it every frame to
+     * script a path, such as a paddle following the mouse; particles it
+     * hits pick up its velocity along the way. Returns false if there is
+     * no such body.
+     * @param {number} id
+     * @param {number} x
+     * @param {number} y
+     * @param {number} angle
+     * @returns {boolean}
+     */
+    move_body(id, x, y, angle) {
+        const ret = wasm.simulation_move_body(this.__wbg_ptr, id, x, y, angle);
+        return ret !== 0;
+    }
+    /**
+     * Undoes steps until `get_frame` is `frame`, or as far as the history
+     * goes. Returns false when nothing was undone. The undone steps are
+     * forgotten; step forward again to recompute them.
+     * @param {bigint} frame
+     * @returns {boolean}
+     */
+    rewind_to(frame) {
+        const ret = wasm.simulation_rewind_to(this.__wbg_ptr, frame);
+        return ret !== 0;
+    }
+    /**
+     * Undoes the last step, restoring the exact state it started from.
+     * Stepping again replays it identically. Returns false when the
+     * history is empty.
+     * @returns {boolean}
+     */
+    step_back() {
+        const ret = wasm.simulation_step_back(this.__wbg_ptr);
+        return ret !== 0;
+    }
+}
+if (Symbol.dispose) Simulation.prototype[Symbol.dispose] = Simulation.prototype.free;
+
+/**
+ * How particles are ordered by Z-order code at the start of each collision
+ * pass. Both are stable and produce the same order.
+ * @enum {0 | 1}
+ */
+export const SortAlgorithm = Object.freeze({
+    Radix: 0, "0": "Radix",
+    Comparison: 1, "1": "Comparison",
+});
+
+/**
+ * The same descriptor for the `get_events_ptr` buffer.
+ * @returns {string}
+ */
+export function collision_event_layout() {
+    let deferred1_0;
+    let deferred1_1;
+    try {
+        const ret = wasm.collision_event_layout();
+        deferred1_0 = ret[0];
+        deferred1_1 = ret[1];
+        return getStringFromWasm0(ret[0], ret[1]);
+    } finally {
+        wasm.__wbindgen_free(deferred1_0, deferred1_1, 1);
+    }
+}
+
+/**
+ * The full descriptor as JSON:
+ * `{"version":2,"stride":56,"fields":[{"name":"x","offset":0,"type":"f32"},...]}`.
+ * @returns {string}
+ */
+export function particle_layout() {
+    let deferred1_0;
+    let deferred1_1;
+    try {
+        const ret = wasm.particle_layout();
+        deferred1_0 = ret[0];
+        deferred1_1 = ret[1];
+        return getStringFromWasm0(ret[0], ret[1]);
+    } finally {
+        wasm.__wbindgen_free(deferred1_0, deferred1_1, 1);
+    }
+}
+
+/**
+ * @returns {number}
+ */
+export function particle_layout_version() {
+    const ret = wasm.particle_layout_version();
+    return ret >>> 0;
+}
+
+/**
+ * Bytes from one particle to the next in the `get_particles_ptr` buffer.
+ * @returns {number}
+ */
+export function particle_stride() {
+    const ret = wasm.particle_stride();
+    return ret >>> 0;
+}
+
+const EXPECTED_RESPONSE_TYPES = new Set(['basic', 'cors', 'default']);
+
+async function __wbg_load(module, imports) {
+    if (typeof Response === 'function' && module instanceof Response) {
+        if (typeof WebAssembly.instantiateStreaming === 'function') {
+            try {
+                return await WebAssembly.instantiateStreaming(module, imports);
+            } catch (e) {
+                const validResponse = module.ok && EXPECTED_RESPONSE_TYPES.has(module.type);
+
+                if (validResponse && module.headers.get('Content-Type') !== 'application/wasm') {
+                    console.warn("`WebAssembly.instantiateStreaming` failed because your server does not serve Wasm with `application/wasm` MIME type. Falling back to `WebAssembly.instantiate` which is slower. Original error:\n", e);
+
+                } else {
+                    throw e;
+                }
+            }
+        }
 
         const bytes = await module.arrayBuffer();
         return await WebAssembly.instantiate(bytes, imports);
@@ -321,11 +2544,15 @@ async function __wbg_load(module, imports) {
 function __wbg_get_imports() {
     const imports = {};
     imports.wbg = {};
+    imports.wbg.__wbg_Error_52673b7de5a0ca89 = function(arg0, arg1) {
+        const ret = Error(getStringFromWasm0(arg0, arg1));
+        return ret;
+    };
     imports.wbg.__wbg___wbindgen_throw_dd24417ed36fc46e = function(arg0, arg1) {
         throw new Error(getStringFromWasm0(arg0, arg1));
     };
-    imports.wbg.__wbg_random_cc1f9237d866d212 = function() {
-        const ret = Math.random();
+    imports.wbg.__wbg_now_c4757667f7df12da = function() {
+        const ret = performance.now();
         return ret;
     };
     imports.wbg.__wbindgen_init_externref_table = function() {
@@ -344,6 +2571,9 @@ function __wbg_get_imports() {
 function __wbg_finalize_init(instance, module) {
     wasm = instance.exports;
     __wbg_init.__wbindgen_wasm_module = module;
+    cachedFloat32ArrayMemory0 = null;
+    cachedFloat64ArrayMemory0 = null;
+    cachedUint32ArrayMemory0 = null;
     cachedUint8ArrayMemory0 = null;
 
 
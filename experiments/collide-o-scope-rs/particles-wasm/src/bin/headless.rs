@@ -0,0 +1,247 @@
+//! Steps a `Simulation` one fixed timestep per frame without a browser and prints per-frame statistics.
+//!
+//!     cargo run --release --bin headless -- --particles 50000 --frames 600
+//!
+//! Every constructor parameter can be given as `--name value`; see `usage()`.
+
+use std::env;
+use std::process;
+use std::time::Instant;
+
+use particles_wasm::broadphase::BroadphaseKind;
+use particles_wasm::{FrameStats, Simulation, SortAlgorithm};
+
+struct Options {
+    width: f32,
+    height: f32,
+    particles: usize,
+    min_size: f32,
+    max_size: f32,
+    max_speed: f32,
+    gravity: f32,
+    elasticity: f32,
+    fan_speed: f32,
+    search_range: i32,
+    seed: u64,
+    substeps: u32,
+    sort: SortAlgorithm,
+    broadphase: BroadphaseKind,
+    /// `None` keeps the build's default (on with the `simd` feature).
+    simd: Option<bool>,
+    /// `None` keeps the build's default (every core with the `threads` feature).
+    threads: Option<usize>,
+    frames: usize,
+    validate: bool,
+    quiet: bool,
+}
+
+impl Default for Options {
+    // Mirrors the defaults in collide-o-scope-rs/index.html.
+    fn default() -> Options {
+        Options {
+            width: 1280.0,
+            height: 720.0,
+            particles: 200,
+            min_size: 4.0,
+            max_size: 12.0,
+            max_speed: 4.0,
+            gravity: 0.0,
+            elasticity: 0.7,
+            fan_speed: 0.5,
+            search_range: 3,
+            seed: 1,
+            substeps: 1,
+            sort: SortAlgorithm::Radix,
+            broadphase: BroadphaseKind::ZOrder,
+            simd: None,
+            threads: None,
+            frames: 600,
+            validate: false,
+            quiet: false,
+        }
+    }
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: headless [--width W] [--height H] [--particles N] [--min-size S] [--max-size S]
+                [--max-speed V] [--gravity G] [--elasticity E] [--fan-speed F]
+                [--search-range R] [--seed S] [--substeps N] [--sort radix|comparison]
+                [--broadphase zorder|grid|quadtree|brute] [--simd on|off] [--threads N]
+                [--frames N] [--validate] [--quiet]"
+    );
+    process::exit(2);
+}
+
+fn parse<T: std::str::FromStr>(name: &str, value: Option<String>) -> T {
+    let value = value.unwrap_or_else(|| {
+        eprintln!("missing value for {name}");
+        usage()
+    });
+    value.parse().unwrap_or_else(|_| {
+        eprintln!("invalid value for {name}: {value}");
+        usage()
+    })
+}
+
+fn parse_args() -> Options {
+    let mut options = Options::default();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--width" => options.width = parse(&arg, args.next()),
+            "--height" => options.height = parse(&arg, args.next()),
+            "--particles" => options.particles = parse(&arg, args.next()),
+            "--min-size" => options.min_size = parse(&arg, args.next()),
+            "--max-size" => options.max_size = parse(&arg, args.next()),
+            "--max-speed" => options.max_speed = parse(&arg, args.next()),
+            "--gravity" => options.gravity = parse(&arg, args.next()),
+            "--elasticity" => options.elasticity = parse(&arg, args.next()),
+            "--fan-speed" => options.fan_speed = parse(&arg, args.next()),
+            "--search-range" => options.search_range = parse(&arg, args.next()),
+            "--seed" => options.seed = parse(&arg, args.next()),
+            "--substeps" => options.substeps = parse(&arg, args.next()),
+            "--sort" => {
+                options.sort = match args.next().as_deref() {
+                    Some("radix") => SortAlgorithm::Radix,
+                    Some("comparison") => SortAlgorithm::Comparison,
+                    _ => usage(),
+                }
+            }
+            "--broadphase" => {
+                options.broadphase = match args.next().as_deref() {
+                    Some("zorder") => BroadphaseKind::ZOrder,
+                    Some("grid") => BroadphaseKind::UniformGrid,
+                    Some("quadtree") => BroadphaseKind::LooseQuadtree,
+                    Some("brute") => BroadphaseKind::BruteForce,
+                    _ => usage(),
+                }
+            }
+            "--simd" => {
+                options.simd = match args.next().as_deref() {
+                    Some("on") => Some(true),
+                    Some("off") => Some(false),
+                    _ => usage(),
+                }
+            }
+            "--threads" => options.threads = Some(parse(&arg, args.next())),
+            "--frames" => options.frames = parse(&arg, args.next()),
+            "--validate" => options.validate = true,
+            "--quiet" => options.quiet = true,
+            "--help" | "-h" => usage(),
+            _ => {
+                eprintln!("unknown argument: {arg}");
+                usage()
+            }
+        }
+    }
+    options
+}
+
+fn main() {
+    let options = parse_args();
+    let mut simulation = Simulation::new(
+        options.width,
+        options.height,
+        options.particles,
+        options.min_size,
+        options.max_size,
+        options.max_speed,
+        options.gravity,
+        options.elasticity,
+        options.fan_speed,
+        options.search_range,
+        options.seed,
+    );
+    simulation.set_substeps(options.substeps);
+    simulation.set_sort_algorithm(options.sort);
+    simulation.set_broadphase(options.broadphase);
+    if let Some(simd) = options.simd {
+        simulation.set_simd(simd);
+    }
+    if let Some(threads) = options.threads {
+        simulation.set_threads(threads);
+    }
+
+    if !options.quiet {
+        println!(
+            "frame,collisions,kinetic_energy,step_ms,sort_ms,integrate_ms,z_code_ms,broadphase_ms,narrow_phase_ms,candidates,contacts{}",
+            if options.validate { ",missed" } else { "" }
+        );
+    }
+
+    let mut total_ms = 0.0;
+    let mut total_sort_ms = 0.0;
+    let mut totals = FrameStats::default();
+    let mut min_ms = f64::INFINITY;
+    let mut max_ms: f64 = 0.0;
+    let mut total_collisions = 0;
+    let mut total_missed = 0;
+
+    for frame in 0..options.frames {
+        let start = Instant::now();
+        simulation.step();
+        let step_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+        total_ms += step_ms;
+        let sort_ms = simulation.get_sort_time_ms();
+        total_sort_ms += sort_ms;
+        min_ms = min_ms.min(step_ms);
+        max_ms = max_ms.max(step_ms);
+        let collisions = simulation.get_collision_count();
+        total_collisions += collisions;
+        let stats = simulation.get_stats();
+        totals.integrate_ms += stats.integrate_ms;
+        totals.z_code_ms += stats.z_code_ms;
+        totals.broadphase_ms += stats.broadphase_ms;
+        totals.narrow_phase_ms += stats.narrow_phase_ms;
+        totals.candidate_pairs += stats.candidate_pairs;
+        totals.contacts += stats.contacts;
+
+        // Brute-force check of the neighbour search; O(n²), so opt-in.
+        let missed = if options.validate {
+            let missed = simulation.count_missed_collisions();
+            total_missed += missed;
+            format!(",{missed}")
+        } else {
+            String::new()
+        };
+
+        if !options.quiet {
+            println!(
+                "{frame},{collisions},{:.3},{step_ms:.3},{sort_ms:.3},{:.3},{:.3},{:.3},{:.3},{},{}{missed}",
+                simulation.kinetic_energy(),
+                stats.integrate_ms,
+                stats.z_code_ms,
+                stats.broadphase_ms,
+                stats.narrow_phase_ms,
+                stats.candidate_pairs,
+                stats.contacts,
+            );
+        }
+    }
+
+    let frames = options.frames.max(1) as f64;
+    eprintln!(
+        "{} particles, {} frames: step mean {:.3} ms, min {:.3} ms, max {:.3} ms, sort mean {:.3} ms, {:.1} collisions/frame",
+        simulation.get_particle_count(),
+        options.frames,
+        total_ms / frames,
+        if options.frames == 0 { 0.0 } else { min_ms },
+        max_ms,
+        total_sort_ms / frames,
+        total_collisions as f64 / frames,
+    );
+    eprintln!(
+        "phase means: integrate {:.3} ms, z-codes {:.3} ms, broadphase {:.3} ms, narrow phase {:.3} ms; {:.0} candidate pairs/frame, {:.1}% in contact",
+        totals.integrate_ms / frames,
+        totals.z_code_ms / frames,
+        totals.broadphase_ms / frames,
+        totals.narrow_phase_ms / frames,
+        totals.candidate_pairs as f64 / frames,
+        100.0 * totals.contacts as f64 / totals.candidate_pairs.max(1) as f64,
+    );
+    if options.validate {
+        eprintln!("{total_missed} collisions missed by the neighbour search");
+    }
+}
@@ -0,0 +1,371 @@
+//! Kinematic bodies: pistons, paddles, drums and anything else built from
+//! obstacle shapes that moves on its own. A body follows a pose set from JS
+//! or one of a few built-in motions, pushes particles out of its way and
+//! is never pushed back. Its shapes are kept in its own frame, so their grid
+//! is built once, and each particle is carried into that frame to be
+//! tested. A bounce reflects the particle's velocity relative to the body's
+//! surface where they touch, which hands over momentum as if the body had
+//! infinite mass.
+
+use std::f32::consts::{PI, TAU};
+
+use wasm_bindgen::prelude::*;
+
+use crate::events::CollisionEvent;
+use crate::obstacles::{Mover, Obstacle, ObstacleKind, Obstacles};
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BodyMotion {
+    /// Stays put until `move_body` gives it a new pose, which it glides to
+    /// over the next step.
+    #[default]
+    Scripted = 0,
+    /// Swings back and forth along a line through its anchor, like a
+    /// piston.
+    Oscillate = 1,
+    /// Spins about its position at a steady rate, like a drum.
+    Rotate = 2,
+}
+
+impl BodyMotion {
+    pub(crate) fn from_u8(motion: u8) -> Option<BodyMotion> {
+        [BodyMotion::Scripted, BodyMotion::Oscillate, BodyMotion::Rotate].get(motion as usize).copied()
+    }
+}
+
+/// Where a body is and how fast it is moving. `angle` is in radians,
+/// clockwise on screen; velocities are per reference frame, and `spin` is
+/// the angular velocity about (x, y).
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BodyState {
+    pub x: f32,
+    pub y: f32,
+    pub angle: f32,
+    pub vx: f32,
+    pub vy: f32,
+    pub spin: f32,
+}
+
+/// A body's motion and what it needs to carry on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Motion {
+    /// Heading for `target`, a pose (x, y, angle) it reaches at the end of
+    /// the next step.
+    Scripted { target: Option<(f32, f32, f32)> },
+    /// At `anchor` plus `amplitude` times the sine of the phase, which
+    /// goes once round every `period` reference frames. `time` is how far
+    /// into the current period it is.
+    Oscillate { anchor: (f32, f32), amplitude: (f32, f32), period: f32, time: f32 },
+    /// Turning at `spin`.
+    Rotate,
+}
+
+impl Motion {
+    pub fn kind(&self) -> BodyMotion {
+        match self {
+            Motion::Scripted { .. } => BodyMotion::Scripted,
+            Motion::Oscillate { .. } => BodyMotion::Oscillate,
+            Motion::Rotate => BodyMotion::Rotate,
+        }
+    }
+}
+
+pub(crate) struct Body {
+    pub state: BodyState,
+    pub motion: Motion,
+    /// Shapes in the body's frame: relative to (x, y), before rotating by
+    /// `angle`.
+    shapes: Obstacles,
+    /// The shapes' bounding box, which their grid covers.
+    extent: (f32, f32, f32, f32),
+    /// How far the shapes reach from the body's origin.
+    reach: f32,
+    /// The pose at the start of the step, where particles' `prev_x` and
+    /// `prev_y` are seen from.
+    start: (f32, f32, f32),
+}
+
+impl Body {
+    /// A body at `state` made of `shapes`, given in its own frame. Returns
+    /// `None` for an oscillation that doesn't have a positive period.
+    pub fn new(state: BodyState, motion: Motion, shapes: Vec<Obstacle>) -> Option<Body> {
+        if let Motion::Oscillate { period, .. } = motion {
+            if !(period > 0.0 && period.is_finite()) {
+                return None;
+            }
+        }
+        let mut body = Body {
+            state,
+            motion,
+            shapes: Obstacles::default(),
+            extent: (0.0, 0.0, 0.0, 0.0),
+            reach: 0.0,
+            start: (state.x, state.y, state.angle),
+        };
+        for shape in shapes {
+            body.add_shape(shape);
+        }
+        Some(body)
+    }
+
+    pub fn shapes(&self) -> impl Iterator<Item = &Obstacle> {
+        self.shapes.obstacles().iter().map(|(_, shape)| shape)
+    }
+
+    pub fn add_shape(&mut self, shape: Obstacle) {
+        let (x0, y0, x1, y1) = shape.bounds();
+        let reach = match shape.kind {
+            ObstacleKind::Circle => shape.points[0].hypot(shape.points[1]) + shape.points[2],
+            _ => shape.points.chunks_exact(2).fold(0.0f32, |reach, q| reach.max(q[0].hypot(q[1]))),
+        };
+        self.extent = match self.shapes.obstacles() {
+            [] => (x0, y0, x1, y1),
+            _ => (self.extent.0.min(x0), self.extent.1.min(y0), self.extent.2.max(x1), self.extent.3.max(y1)),
+        };
+        self.reach = self.reach.max(reach);
+        self.shapes.add(shape);
+    }
+
+    /// Shape `k` as it stands in the world: its points moved to the body's
+    /// pose, and a circle's radius left alone.
+    pub fn world_shape(&self, k: usize) -> Option<(ObstacleKind, Vec<f32>)> {
+        let (_, shape) = self.shapes.obstacles().get(k)?;
+        let pose = (self.state.x, self.state.y, self.state.angle);
+        // A circle's radius is the odd one out at the end.
+        let points = shape
+            .points
+            .chunks(2)
+            .flat_map(|q| match *q {
+                [x, y] => {
+                    let (x, y) = to_world(pose, (x, y));
+                    vec![x, y]
+                }
+                _ => q.to_vec(),
+            })
+            .collect();
+        Some((shape.kind, points))
+    }
+
+    /// Heads for (x, y, angle) over the next step, turning the short way
+    /// round.
+    pub fn move_to(&mut self, x: f32, y: f32, angle: f32) {
+        self.motion = Motion::Scripted { target: Some((x, y, angle)) };
+    }
+
+    /// Starts swinging by (amplitude_x, amplitude_y) either side of where it
+    /// is now, once every `period` reference frames. False unless `period`
+    /// is positive.
+    pub fn oscillate(&mut self, amplitude_x: f32, amplitude_y: f32, period: f32) -> bool {
+        if !(period > 0.0 && period.is_finite()) {
+            return false;
+        }
+        let anchor = (self.state.x, self.state.y);
+        self.motion = Motion::Oscillate { anchor, amplitude: (amplitude_x, amplitude_y), period, time: 0.0 };
+        (self.state.vx, self.state.vy, self.state.spin) = (0.0, 0.0, 0.0);
+        true
+    }
+
+    /// Starts turning at `speed` radians per reference frame about where it
+    /// is now.
+    pub fn rotate(&mut self, speed: f32) {
+        self.motion = Motion::Rotate;
+        (self.state.vx, self.state.vy, self.state.spin) = (0.0, 0.0, speed);
+    }
+
+    /// Notes the pose at the start of a step `length` reference frames long
+    /// and, for a scripted body, the velocity that reaches its target by
+    /// the end of it.
+    fn begin_step(&mut self, length: f32) {
+        let s = &mut self.state;
+        self.start = (s.x, s.y, s.angle);
+        if let Motion::Scripted { target } = self.motion {
+            (s.vx, s.vy, s.spin) = match target {
+                Some((x, y, angle)) => {
+                    let turn = (angle - s.angle + PI).rem_euclid(TAU) - PI;
+                    ((x - s.x) / length, (y - s.y) / length, turn / length)
+                }
+                None => (0.0, 0.0, 0.0),
+            };
+        }
+    }
+
+    /// Moves the body through `h` reference frames.
+    fn advance(&mut self, h: f32) {
+        let s = &mut self.state;
+        match &mut self.motion {
+            Motion::Scripted { .. } => {
+                s.x += s.vx * h;
+                s.y += s.vy * h;
+                s.angle += s.spin * h;
+            }
+            Motion::Oscillate { anchor, amplitude, period, time } => {
+                *time = (*time + h).rem_euclid(*period);
+                let (sin, cos) = (TAU * *time / *period).sin_cos();
+                let rate = TAU / *period;
+                (s.x, s.y) = (anchor.0 + amplitude.0 * sin, anchor.1 + amplitude.1 * sin);
+                (s.vx, s.vy) = (amplitude.0 * rate * cos, amplitude.1 * rate * cos);
+            }
+            Motion::Rotate => s.angle = (s.angle + s.spin * h).rem_euclid(TAU),
+        }
+    }
+
+    /// Lands a scripted body exactly on its target, whatever rounding the
+    /// substeps picked up.
+    fn end_step(&mut self) {
+        if let Motion::Scripted { target: Some((x, y, angle)) } = self.motion {
+            (self.state.x, self.state.y, self.state.angle) = (x, y, angle);
+            self.motion = Motion::Scripted { target: None };
+        }
+    }
+
+    /// Pushes a particle out of the body and bounces it off, as
+    /// `Obstacles::collide` does but relative to the moving surface.
+    /// Returns the kinetic energy taken out, which is negative when the
+    /// body does work on the particle. The shapes must be `prepare`d.
+    fn collide(&self, m: &mut Mover, elasticity: f32, candidates: &mut Vec<u32>, events: &mut Vec<CollisionEvent>) -> f64 {
+        if self.shapes.obstacles().is_empty() {
+            return 0.0;
+        }
+        let s = self.state;
+        let pose = (s.x, s.y, s.angle);
+        let r = m.radius;
+        let from = to_local(self.start, m.from);
+        let to = to_local(pose, (m.x, m.y));
+        if distance_to_origin(from, to) >= self.reach + r {
+            return 0.0;
+        }
+        let (sin, cos) = s.angle.sin_cos();
+        let mut lost = 0.0;
+        let mut touched = false;
+        let end = self.shapes.resolve(from, to, r, candidates, |nx, ny, (x, y)| {
+            touched = true;
+            let (nx, ny) = (nx * cos - ny * sin, nx * sin + ny * cos);
+            // The surface's velocity where it touches the particle.
+            let (qx, qy) = to_world(pose, (x, y));
+            let (qx, qy) = (qx - nx * r, qy - ny * r);
+            let surface = (s.vx - s.spin * (qy - s.y), s.vy + s.spin * (qx - s.x));
+            lost += m.bounce(nx, ny, surface, (qx, qy), elasticity, events);
+        });
+        // Going out and back through the frame rounds, so only moved
+        // particles are written back.
+        if touched {
+            (m.x, m.y) = to_world(pose, end);
+            m.touched = true;
+        }
+        lost
+    }
+}
+
+/// (x, y) in the frame of a body at `pose`.
+fn to_local((bx, by, angle): (f32, f32, f32), (x, y): (f32, f32)) -> (f32, f32) {
+    let (sin, cos) = angle.sin_cos();
+    let (dx, dy) = (x - bx, y - by);
+    (dx * cos + dy * sin, dy * cos - dx * sin)
+}
+
+/// (x, y) in a body's frame, back in the world.
+fn to_world((bx, by, angle): (f32, f32, f32), (x, y): (f32, f32)) -> (f32, f32) {
+    let (sin, cos) = angle.sin_cos();
+    (bx + x * cos - y * sin, by + x * sin + y * cos)
+}
+
+/// How close the path from `a` to `b` comes to the origin.
+fn distance_to_origin(a: (f32, f32), b: (f32, f32)) -> f32 {
+    let (ex, ey) = (b.0 - a.0, b.1 - a.1);
+    let len2 = ex * ex + ey * ey;
+    let t = if len2 > 0.0 { (-(a.0 * ex + a.1 * ey) / len2).clamp(0.0, 1.0) } else { 0.0 };
+    (a.0 + t * ex).hypot(a.1 + t * ey)
+}
+
+/// The bodies, each with an id that stays valid while others are added and
+/// removed. Ids count up and are never reused.
+#[derive(Default)]
+pub(crate) struct Bodies {
+    bodies: Vec<(u32, Body)>,
+    next_id: u32,
+}
+
+impl Bodies {
+    /// Rebuilds the set from its saved parts; `bodies` must be in id order
+    /// and every id below `next_id`.
+    pub fn from_parts(next_id: u32, bodies: Vec<(u32, Body)>) -> Option<Bodies> {
+        let ordered = bodies.windows(2).all(|pair| pair[0].0 < pair[1].0);
+        if !ordered || bodies.last().is_some_and(|&(id, _)| id >= next_id) {
+            return None;
+        }
+        Some(Bodies { bodies, next_id })
+    }
+
+    pub fn next_id(&self) -> u32 {
+        self.next_id
+    }
+
+    pub fn bodies(&self) -> &[(u32, Body)] {
+        &self.bodies
+    }
+
+    pub fn add(&mut self, body: Body) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.bodies.push((id, body));
+        id
+    }
+
+    pub fn get(&self, id: u32) -> Option<&Body> {
+        self.position(id).map(|k| &self.bodies[k].1)
+    }
+
+    pub fn get_mut(&mut self, id: u32) -> Option<&mut Body> {
+        self.position(id).map(|k| &mut self.bodies[k].1)
+    }
+
+    pub fn remove(&mut self, id: u32) -> bool {
+        let Some(k) = self.position(id) else {
+            return false;
+        };
+        self.bodies.remove(k);
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.bodies.clear();
+    }
+
+    fn position(&self, id: u32) -> Option<usize> {
+        self.bodies.binary_search_by_key(&id, |&(id, _)| id).ok()
+    }
+
+    pub fn begin_step(&mut self, length: f32) {
+        for (_, body) in &mut self.bodies {
+            body.begin_step(length);
+        }
+    }
+
+    pub fn advance(&mut self, h: f32) {
+        for (_, body) in &mut self.bodies {
+            body.advance(h);
+        }
+    }
+
+    pub fn end_step(&mut self) {
+        for (_, body) in &mut self.bodies {
+            body.end_step();
+        }
+    }
+
+    /// Builds the grids of any bodies whose shapes have changed, ready for
+    /// `collide`.
+    pub fn prepare(&mut self) {
+        for (_, body) in &mut self.bodies {
+            body.shapes.prepare(body.extent);
+        }
+    }
+
+    /// Collides a particle with every body, oldest first. Returns the
+    /// kinetic energy taken out.
+    pub fn collide(&self, m: &mut Mover, elasticity: f32, candidates: &mut Vec<u32>, events: &mut Vec<CollisionEvent>) -> f64 {
+        self.bodies.iter().map(|(_, body)| body.collide(m, elasticity, candidates, events)).sum()
+    }
+}
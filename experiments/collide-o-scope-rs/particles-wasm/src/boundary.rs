@@ -0,0 +1,193 @@
+//! What happens at the edge of the world. The default walls are the box's
+//! four sides, bounced off inside the integrator. The other modes act on
+//! each particle after it moves. Periodic worlds also need the collisions
+//! across the seams, which `broadphase::Seams` finds.
+
+use wasm_bindgen::prelude::*;
+
+use crate::obstacles::{Obstacle, ObstacleKind};
+use crate::particles::Particles;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BoundaryMode {
+    /// Particles bounce off the four sides of the box.
+    #[default]
+    Walls = 0,
+    /// Leaving one side brings a particle back in at the opposite one, and
+    /// particles near opposite sides collide across the seam.
+    Periodic = 1,
+    /// Particles that leave the box entirely are removed.
+    Open = 2,
+    /// Particles bounce around inside the largest circle that fits the box.
+    Circle = 3,
+    /// Particles bounce around inside the polygon from
+    /// `set_boundary_polygon`.
+    Polygon = 4,
+}
+
+impl BoundaryMode {
+    pub(crate) fn from_u8(mode: u8) -> Option<BoundaryMode> {
+        [BoundaryMode::Walls, BoundaryMode::Periodic, BoundaryMode::Open, BoundaryMode::Circle, BoundaryMode::Polygon]
+            .get(mode as usize)
+            .copied()
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Boundary {
+    mode: BoundaryMode,
+    /// The container for `Polygon`, kept when switching to another mode.
+    polygon: Option<Obstacle>,
+}
+
+impl Boundary {
+    /// Rebuilds a boundary from its saved parts. `Polygon` needs a polygon.
+    pub fn from_parts(mode: BoundaryMode, polygon: Option<Obstacle>) -> Option<Boundary> {
+        if mode == BoundaryMode::Polygon && polygon.is_none() {
+            return None;
+        }
+        Some(Boundary { mode, polygon })
+    }
+
+    pub fn mode(&self) -> BoundaryMode {
+        self.mode
+    }
+
+    /// Switches mode; false for `Polygon` if no polygon was ever set.
+    pub fn set_mode(&mut self, mode: BoundaryMode) -> bool {
+        if mode == BoundaryMode::Polygon && self.polygon.is_none() {
+            return false;
+        }
+        self.mode = mode;
+        true
+    }
+
+    pub fn polygon(&self) -> Option<&Obstacle> {
+        self.polygon.as_ref()
+    }
+
+    pub fn set_polygon(&mut self, polygon: Obstacle) {
+        self.polygon = Some(polygon);
+        self.mode = BoundaryMode::Polygon;
+    }
+
+    /// The largest circle that fits a `width` × `height` box, as its
+    /// centre and radius.
+    pub fn circle(width: f32, height: f32) -> (f32, f32, f32) {
+        (width / 2.0, height / 2.0, width.min(height) / 2.0)
+    }
+
+    /// Whether the integrator should bounce particles off the box's sides.
+    pub fn walls(&self) -> bool {
+        self.mode == BoundaryMode::Walls
+    }
+
+    /// Whether a particle at (x, y) with radius `r` has left the box
+    /// entirely, for `Open`.
+    pub fn escaped(x: f32, y: f32, r: f32, width: f32, height: f32) -> bool {
+        x + r < 0.0 || x - r > width || y + r < 0.0 || y - r > height
+    }
+
+    /// Wraps or contains `slots` after they move, reflecting the outward
+    /// part of the velocity of any that hit a container, scaled by
+    /// `elasticity`. Returns the kinetic energy those bounces took out.
+    pub fn apply(&self, p: &mut Particles, slots: std::ops::Range<usize>, width: f32, height: f32, elasticity: f32) -> f64 {
+        let mut lost = 0.0;
+        match self.mode {
+            BoundaryMode::Walls | BoundaryMode::Open => {}
+            BoundaryMode::Periodic => {
+                for i in slots {
+                    // The start of the step moves too, so the particle's path
+                    // this step stays continuous.
+                    let dx = wrap(p.x[i], width) - p.x[i];
+                    let dy = wrap(p.y[i], height) - p.y[i];
+                    p.x[i] += dx;
+                    p.prev_x[i] += dx;
+                    p.y[i] += dy;
+                    p.prev_y[i] += dy;
+                }
+            }
+            BoundaryMode::Circle => {
+                let (cx, cy, radius) = Boundary::circle(width, height);
+                for i in slots {
+                    let (dx, dy) = (p.x[i] - cx, p.y[i] - cy);
+                    let d2 = dx * dx + dy * dy;
+                    // A particle too big for the container stays at its
+                    // centre.
+                    let limit = (radius - p.radius[i]).max(0.0);
+                    if d2 == 0.0 || d2 <= limit * limit {
+                        continue;
+                    }
+                    let d = d2.sqrt();
+                    let (nx, ny) = (dx / d, dy / d);
+                    p.x[i] = cx + nx * limit;
+                    p.y[i] = cy + ny * limit;
+                    lost += bounce(p, i, nx, ny, elasticity);
+                }
+            }
+            BoundaryMode::Polygon => {
+                let polygon = self.polygon.as_ref().expect("polygon mode has a polygon");
+                for i in slots {
+                    lost += contain(polygon, p, i, elasticity);
+                }
+            }
+        }
+        lost
+    }
+}
+
+/// `v` moved into `[0, size)`.
+fn wrap(v: f32, size: f32) -> f32 {
+    let wrapped = v.rem_euclid(size);
+    // Rounding can land a tiny negative value on `size` itself.
+    if wrapped >= size {
+        0.0
+    } else {
+        wrapped
+    }
+}
+
+/// Keeps particle `i` inside `polygon`, at least its radius from the
+/// nearest edge.
+fn contain(polygon: &Obstacle, p: &mut Particles, i: usize, elasticity: f32) -> f64 {
+    debug_assert_eq!(polygon.kind, ObstacleKind::Polygon);
+    let (x, y, r) = (p.x[i], p.y[i], p.radius[i]);
+    let points = &polygon.points;
+    let n = points.len() / 2;
+    let (mut qx, mut qy, mut best) = (x, y, f32::INFINITY);
+    for k in 0..n {
+        let j = (k + 1) % n;
+        let (x0, y0, x1, y1) = (points[2 * k], points[2 * k + 1], points[2 * j], points[2 * j + 1]);
+        let (ex, ey) = (x1 - x0, y1 - y0);
+        let len2 = ex * ex + ey * ey;
+        let t = if len2 > 0.0 { (((x - x0) * ex + (y - y0) * ey) / len2).clamp(0.0, 1.0) } else { 0.0 };
+        let (cx, cy) = (x0 + t * ex, y0 + t * ey);
+        let d2 = (x - cx) * (x - cx) + (y - cy) * (y - cy);
+        if d2 < best {
+            (qx, qy, best) = (cx, cy, d2);
+        }
+    }
+    let inside = polygon.contains(x, y);
+    if best == 0.0 || (inside && best >= r * r) {
+        return 0.0;
+    }
+    // Outward normal at the nearest point on the outline.
+    let d = best.sqrt();
+    let (nx, ny) = if inside { ((qx - x) / d, (qy - y) / d) } else { ((x - qx) / d, (y - qy) / d) };
+    p.x[i] = qx - nx * r;
+    p.y[i] = qy - ny * r;
+    bounce(p, i, nx, ny, elasticity)
+}
+
+/// Reflects particle `i`'s velocity along the outward normal (nx, ny) if it
+/// is heading out, returning the kinetic energy lost.
+fn bounce(p: &mut Particles, i: usize, nx: f32, ny: f32, elasticity: f32) -> f64 {
+    let vn = p.vx[i] * nx + p.vy[i] * ny;
+    if vn <= 0.0 {
+        return 0.0;
+    }
+    p.vx[i] -= (1.0 + elasticity) * vn * nx;
+    p.vy[i] -= (1.0 + elasticity) * vn * ny;
+    (0.5 * p.mass[i] * vn * vn * (1.0 - elasticity * elasticity)) as f64
+}
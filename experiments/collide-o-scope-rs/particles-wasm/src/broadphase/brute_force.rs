@@ -0,0 +1,22 @@
+use std::ops::Range;
+
+use super::{Broadphase, Scene};
+
+/// Tests every pair. O(n²); the reference the other broadphases are
+/// validated against.
+pub struct BruteForce;
+
+impl Broadphase for BruteForce {
+    fn query(&self, scene: &Scene, range: Range<usize>, pairs: &mut Vec<(u32, u32)>) -> usize {
+        let mut tested = 0;
+        for i in range {
+            tested += scene.len() - i - 1;
+            for j in i + 1..scene.len() {
+                if scene.overlapping(i, j) {
+                    pairs.push((i as u32, j as u32));
+                }
+            }
+        }
+        tested
+    }
+}
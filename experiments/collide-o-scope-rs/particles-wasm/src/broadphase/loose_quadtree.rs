@@ -0,0 +1,112 @@
+use std::ops::Range;
+
+use super::{Broadphase, Scene};
+
+const MAX_DEPTH: u32 = 12;
+const NONE: u32 = u32::MAX;
+
+struct Node {
+    x: f32,
+    y: f32,
+    half_size: f32,
+    depth: u32,
+    /// How far from (x, y) any circle stored in this subtree reaches. At
+    /// most twice `half_size`, the loose bound, unless a particle has been
+    /// pushed outside the world.
+    extent: f32,
+    children: [u32; 4],
+    first_item: u32,
+}
+
+/// Loose quadtree (looseness 2): each node's bounds are stretched by half its
+/// size on every side, so a particle can live in the deepest node whose cell
+/// contains its centre and is at least as wide as the particle. Nodes and item
+/// lists live in flat arrays rebuilt every frame without reallocating.
+#[derive(Default)]
+pub struct LooseQuadtree {
+    nodes: Vec<Node>,
+    next_item: Vec<u32>,
+}
+
+impl LooseQuadtree {
+    fn new_node(&mut self, x: f32, y: f32, half_size: f32, depth: u32) -> u32 {
+        self.nodes.push(Node {
+            x,
+            y,
+            half_size,
+            depth,
+            extent: 0.0,
+            children: [NONE; 4],
+            first_item: NONE,
+        });
+        (self.nodes.len() - 1) as u32
+    }
+
+    fn insert(&mut self, item: u32, x: f32, y: f32, radius: f32) {
+        let mut node = 0;
+        loop {
+            let current = &mut self.nodes[node as usize];
+            current.extent = current.extent.max((x - current.x).abs() + radius).max((y - current.y).abs() + radius);
+            let Node { x: nx, y: ny, half_size, depth, .. } = *current;
+            // A child cell is `half_size` wide; its loose bounds hold any
+            // circle of radius up to half that.
+            if depth == MAX_DEPTH || radius > half_size / 2.0 {
+                break;
+            }
+            let quadrant = (x >= nx) as usize | (((y >= ny) as usize) << 1);
+            let mut child = self.nodes[node as usize].children[quadrant];
+            if child == NONE {
+                let offset = half_size / 2.0;
+                let cx = if x >= nx { nx + offset } else { nx - offset };
+                let cy = if y >= ny { ny + offset } else { ny - offset };
+                child = self.new_node(cx, cy, offset, depth + 1);
+                self.nodes[node as usize].children[quadrant] = child;
+            }
+            node = child;
+        }
+        self.next_item[item as usize] = self.nodes[node as usize].first_item;
+        self.nodes[node as usize].first_item = item;
+    }
+}
+
+impl Broadphase for LooseQuadtree {
+    fn build(&mut self, scene: &Scene) {
+        let half_size = scene.width.max(scene.height) / 2.0;
+        self.nodes.clear();
+        self.new_node(scene.width / 2.0, scene.height / 2.0, half_size, 0);
+        self.next_item.clear();
+        self.next_item.resize(scene.len(), NONE);
+        for i in 0..scene.len() {
+            self.insert(i as u32, scene.x[i], scene.y[i], scene.radius[i]);
+        }
+    }
+
+    fn query(&self, scene: &Scene, range: Range<usize>, pairs: &mut Vec<(u32, u32)>) -> usize {
+        let mut stack = Vec::new();
+        let mut tested = 0;
+        for i in range {
+            let (x, y, radius) = (scene.x[i], scene.y[i], scene.radius[i]);
+            stack.push(0);
+            while let Some(node) = stack.pop() {
+                let node = &self.nodes[node as usize];
+                let reach = node.extent + radius;
+                if (x - node.x).abs() > reach || (y - node.y).abs() > reach {
+                    continue;
+                }
+
+                let mut item = node.first_item;
+                while item != NONE {
+                    if item as usize > i {
+                        tested += 1;
+                        if scene.overlapping(i, item as usize) {
+                            pairs.push((i as u32, item));
+                        }
+                    }
+                    item = self.next_item[item as usize];
+                }
+                stack.extend(node.children.iter().copied().filter(|&child| child != NONE));
+            }
+        }
+        tested
+    }
+}
@@ -0,0 +1,279 @@
+//! Collision broadphases. Each finds the pairs of particles whose circles
+//! overlap; the simulation then resolves them in a canonical order, so every
+//! strategy produces the same collision response.
+
+mod brute_force;
+mod loose_quadtree;
+mod seams;
+mod uniform_grid;
+mod zorder_sweep;
+
+use std::ops::Range;
+
+use wasm_bindgen::prelude::*;
+
+use crate::narrow::Run;
+use crate::particles::Particles;
+use crate::simd::{F32x4, LANES};
+
+pub use brute_force::BruteForce;
+pub use loose_quadtree::LooseQuadtree;
+pub(crate) use seams::{SeamPair, Seams};
+pub use uniform_grid::UniformGrid;
+pub use zorder_sweep::ZOrderSweep;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BroadphaseKind {
+    ZOrder = 0,
+    UniformGrid = 1,
+    LooseQuadtree = 2,
+    BruteForce = 3,
+}
+
+/// Read-only view of the world handed to a broadphase. Particles appear in
+/// Z-order, and the pairs a broadphase reports are positions in that order.
+pub struct Scene<'a> {
+    pub x: &'a [f32],
+    pub y: &'a [f32],
+    pub radius: &'a [f32],
+    /// Sorted ascending.
+    pub z_codes: &'a [u32],
+    pub width: f32,
+    pub height: f32,
+    pub max_radius: f32,
+    pub search_range: usize,
+    /// Test candidates four at a time in `push_overlapping`.
+    pub simd: bool,
+}
+
+impl Scene<'_> {
+    pub fn len(&self) -> usize {
+        self.x.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x.is_empty()
+    }
+
+    pub fn overlapping(&self, i: usize, j: usize) -> bool {
+        let dx = self.x[i] - self.x[j];
+        let dy = self.y[i] - self.y[j];
+        let radius_sum = self.radius[i] + self.radius[j];
+        dx * dx + dy * dy < radius_sum * radius_sum
+    }
+
+    /// Appends `(i, j)` for every `j` in `run` that overlaps `i`. `run`
+    /// must lie after `i`.
+    pub fn push_overlapping_run(&self, i: usize, run: Range<usize>, pairs: &mut Vec<(u32, u32)>) {
+        let mut j = run.start;
+        if self.simd {
+            let lanes = Lanes::new(self, i);
+            while j + LANES <= run.end {
+                let load = |values: &[f32]| F32x4::load(&values[j..j + LANES]);
+                let hits = lanes.overlapping(load(self.x), load(self.y), load(self.radius));
+                for k in BitIter(hits) {
+                    pairs.push((i as u32, (j + k) as u32));
+                }
+                j += LANES;
+            }
+        }
+        for j in j..run.end {
+            if self.overlapping(i, j) {
+                pairs.push((i as u32, j as u32));
+            }
+        }
+    }
+
+    /// Appends `(i, j)` for every candidate `j > i` that overlaps `i`, in
+    /// candidate order.
+    pub fn push_overlapping(&self, i: usize, candidates: &[u32], pairs: &mut Vec<(u32, u32)>) {
+        let mut rest = candidates;
+        if self.simd {
+            let lanes = Lanes::new(self, i);
+            let mut chunks = candidates.chunks_exact(LANES);
+            for chunk in &mut chunks {
+                let gather = |values: &[f32]| F32x4::from_array(std::array::from_fn(|k| values[chunk[k] as usize]));
+                let hits = lanes.overlapping(gather(self.x), gather(self.y), gather(self.radius));
+                for k in BitIter(hits) {
+                    if chunk[k] as usize > i {
+                        pairs.push((i as u32, chunk[k]));
+                    }
+                }
+            }
+            rest = chunks.remainder();
+        }
+        for &j in rest {
+            if j as usize > i && self.overlapping(i, j as usize) {
+                pairs.push((i as u32, j));
+            }
+        }
+    }
+}
+
+/// Particle `i` splatted across all lanes, for testing it against four
+/// others at once with the same arithmetic as `Scene::overlapping`.
+struct Lanes {
+    x: F32x4,
+    y: F32x4,
+    radius: F32x4,
+}
+
+impl Lanes {
+    fn new(scene: &Scene, i: usize) -> Lanes {
+        Lanes {
+            x: F32x4::splat(scene.x[i]),
+            y: F32x4::splat(scene.y[i]),
+            radius: F32x4::splat(scene.radius[i]),
+        }
+    }
+
+    /// Bit `k` is set when lane `k` overlaps.
+    fn overlapping(&self, x: F32x4, y: F32x4, radius: F32x4) -> u32 {
+        let dx = self.x - x;
+        let dy = self.y - y;
+        let radius_sum = self.radius + radius;
+        (dx * dx + dy * dy).lt(radius_sum * radius_sum).bits()
+    }
+}
+
+/// Indices of the set bits, lowest first.
+struct BitIter(u32);
+
+impl Iterator for BitIter {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.0 == 0 {
+            return None;
+        }
+        let k = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        Some(k)
+    }
+}
+
+/// Particles gathered into Z-order, so broadphases scan contiguous memory
+/// and the narrow phase can split them into runs of ranks. Impulses start
+/// at zero and no particle is colliding. Kept between frames to avoid
+/// reallocating.
+#[derive(Default)]
+pub(crate) struct SortedView {
+    x: Vec<f32>,
+    y: Vec<f32>,
+    radius: Vec<f32>,
+    z_codes: Vec<u32>,
+    max_radius: f32,
+    vx: Vec<f32>,
+    vy: Vec<f32>,
+    mass: Vec<f32>,
+    temp: Vec<f32>,
+    impulse_x: Vec<f32>,
+    impulse_y: Vec<f32>,
+    colliding: Vec<bool>,
+}
+
+impl SortedView {
+    pub fn gather(&mut self, particles: &Particles, order: &[u32]) {
+        self.x.clear();
+        self.y.clear();
+        self.radius.clear();
+        self.z_codes.clear();
+        self.vx.clear();
+        self.vy.clear();
+        self.mass.clear();
+        self.temp.clear();
+        self.max_radius = 0.0;
+        for &slot in order {
+            let slot = slot as usize;
+            self.x.push(particles.x[slot]);
+            self.y.push(particles.y[slot]);
+            self.radius.push(particles.radius[slot]);
+            self.z_codes.push(particles.z_code[slot]);
+            self.max_radius = self.max_radius.max(particles.radius[slot]);
+            self.vx.push(particles.vx[slot]);
+            self.vy.push(particles.vy[slot]);
+            self.mass.push(particles.mass[slot]);
+            self.temp.push(particles.temp[slot]);
+        }
+        let n = order.len();
+        for impulse in [&mut self.impulse_x, &mut self.impulse_y] {
+            impulse.clear();
+            impulse.resize(n, 0.0);
+        }
+        self.colliding.clear();
+        self.colliding.resize(n, false);
+    }
+
+    /// Writes what the narrow phase changed back to the slots.
+    pub fn scatter(&self, particles: &mut Particles, order: &[u32]) {
+        for (rank, &slot) in order.iter().enumerate() {
+            let slot = slot as usize;
+            particles.x[slot] = self.x[rank];
+            particles.y[slot] = self.y[rank];
+            particles.temp[slot] = self.temp[rank];
+            particles.impulse_x[slot] = self.impulse_x[rank];
+            particles.impulse_y[slot] = self.impulse_y[rank];
+            particles.colliding[slot] = self.colliding[rank];
+        }
+    }
+
+    /// Every rank, for the narrow phase.
+    pub fn run(&mut self) -> Run<'_> {
+        Run {
+            start: 0,
+            x: &mut self.x,
+            y: &mut self.y,
+            vx: &self.vx,
+            vy: &self.vy,
+            radius: &self.radius,
+            mass: &self.mass,
+            temp: &mut self.temp,
+            impulse_x: &mut self.impulse_x,
+            impulse_y: &mut self.impulse_y,
+            colliding: &mut self.colliding,
+        }
+    }
+
+    pub fn scene(&self, width: f32, height: f32, search_range: usize, simd: bool) -> Scene<'_> {
+        Scene {
+            x: &self.x,
+            y: &self.y,
+            radius: &self.radius,
+            z_codes: &self.z_codes,
+            width,
+            height,
+            max_radius: self.max_radius,
+            search_range,
+            simd,
+        }
+    }
+}
+
+pub trait Broadphase: Send + Sync {
+    /// Builds whatever structure `query` needs over `scene`. Called once per
+    /// collision pass, before any `query`.
+    fn build(&mut self, _scene: &Scene) {}
+
+    /// Appends every pair `(i, j)`, `i < j`, of overlapping particles with `i`
+    /// in `range`, in any order and without duplicates. `range` only limits
+    /// which particles are queried from; any particle can be a neighbour.
+    /// Disjoint ranges can be queried concurrently. Returns how many
+    /// candidate pairs `(i, j)`, `i < j`, were tested for overlap.
+    fn query(&self, scene: &Scene, range: Range<usize>, pairs: &mut Vec<(u32, u32)>) -> usize;
+
+    /// Every overlapping pair in the scene. Returns the candidates tested.
+    fn find_pairs(&mut self, scene: &Scene, pairs: &mut Vec<(u32, u32)>) -> usize {
+        self.build(scene);
+        self.query(scene, 0..scene.len(), pairs)
+    }
+}
+
+pub fn create(kind: BroadphaseKind) -> Box<dyn Broadphase> {
+    match kind {
+        BroadphaseKind::ZOrder => Box::new(ZOrderSweep),
+        BroadphaseKind::UniformGrid => Box::<UniformGrid>::default(),
+        BroadphaseKind::LooseQuadtree => Box::<LooseQuadtree>::default(),
+        BroadphaseKind::BruteForce => Box::new(BruteForce),
+    }
+}
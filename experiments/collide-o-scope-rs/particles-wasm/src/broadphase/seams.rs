@@ -0,0 +1,80 @@
+use std::ops::Range;
+
+use super::Scene;
+use crate::grid::CellGrid;
+
+/// A collision across a periodic seam: `a` and `b`, with `b` seen shifted
+/// by (ox, oy).
+pub(crate) type SeamPair = (u32, u32, f32, f32);
+
+/// Finds the overlaps across the seams of a periodic world, which the
+/// broadphases, searching the unwrapped box, never see. The box is tiled
+/// with whole cells at least a diameter wide, so each particle in a cell
+/// along its edge only has to check the cells across the seam from it,
+/// wrapped round to the far side.
+#[derive(Default)]
+pub(crate) struct Seams {
+    grid: CellGrid,
+}
+
+impl Seams {
+    pub fn build(&mut self, scene: &Scene) {
+        self.grid.tile(scene.width, scene.height, (scene.max_radius * 2.0).max(1.0));
+        self.grid.fill(scene.len(), |i| Some((scene.x[i], scene.y[i], scene.x[i], scene.y[i])));
+    }
+
+    /// Appends `(i, j, ox, oy)` for every `j > i` that overlaps `i` once
+    /// shifted by (ox, oy) across a seam, with `i` in `range`. As with a
+    /// broadphase query, disjoint ranges can run concurrently. Returns how
+    /// many candidates were tested.
+    pub fn query(&self, scene: &Scene, range: Range<usize>, pairs: &mut Vec<SeamPair>) -> usize {
+        let (cols, rows) = (self.grid.cols(), self.grid.rows());
+        let mut tested = 0;
+        for i in range {
+            let (col, row) = self.grid.cell_of(scene.x[i], scene.y[i]);
+            if col != 0 && col != cols - 1 && row != 0 && row != rows - 1 {
+                continue;
+            }
+            // Each offset reaches a different copy of the box, so in a box
+            // only one or two cells across, the same cell is searched again
+            // under another shift rather than twice under the same one.
+            for dr in -1..=1 {
+                let (r, oy) = across(row as isize + dr, rows, scene.height);
+                for dc in -1..=1 {
+                    let (c, ox) = across(col as isize + dc, cols, scene.width);
+                    if (ox, oy) == (0.0, 0.0) {
+                        continue;
+                    }
+                    for &j in self.grid.cell(c, r) {
+                        if j as usize > i {
+                            tested += 1;
+                            if overlapping(scene, i, j as usize, ox, oy) {
+                                pairs.push((i as u32, j, ox, oy));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        tested
+    }
+}
+
+/// The cell `k` of `n` names once wrapped, and the shift that carries a
+/// particle there across the seam.
+fn across(k: isize, n: usize, size: f32) -> (usize, f32) {
+    if k < 0 {
+        (n - 1, -size)
+    } else if k as usize >= n {
+        (0, size)
+    } else {
+        (k as usize, 0.0)
+    }
+}
+
+fn overlapping(scene: &Scene, i: usize, j: usize, ox: f32, oy: f32) -> bool {
+    let dx = scene.x[i] - (scene.x[j] + ox);
+    let dy = scene.y[i] - (scene.y[j] + oy);
+    let radius_sum = scene.radius[i] + scene.radius[j];
+    dx * dx + dy * dy < radius_sum * radius_sum
+}
@@ -0,0 +1,39 @@
+use std::ops::Range;
+
+use super::{Broadphase, Scene};
+use crate::grid::CellGrid;
+
+/// Uniform grid with cells as wide as the largest particle, so every
+/// neighbour is in the same or an adjacent cell. Like `UniformGrid` in
+/// js/spatial-partitioning.js, but stored as flat counting-sorted arrays that
+/// are reused from frame to frame.
+#[derive(Default)]
+pub struct UniformGrid {
+    grid: CellGrid,
+}
+
+impl Broadphase for UniformGrid {
+    fn build(&mut self, scene: &Scene) {
+        self.grid.cover((0.0, 0.0, scene.width, scene.height), (scene.max_radius * 2.0).max(1.0));
+        self.grid.fill(scene.len(), |i| Some((scene.x[i], scene.y[i], scene.x[i], scene.y[i])));
+    }
+
+    fn query(&self, scene: &Scene, range: Range<usize>, pairs: &mut Vec<(u32, u32)>) -> usize {
+        let (cols, rows) = (self.grid.cols(), self.grid.rows());
+        let mut tested = 0;
+        for i in range {
+            let (col, row) = self.grid.cell_of(scene.x[i], scene.y[i]);
+            for r in row.saturating_sub(1)..=(row + 1).min(rows - 1) {
+                for c in col.saturating_sub(1)..=(col + 1).min(cols - 1) {
+                    let items = self.grid.cell(c, r);
+                    // Each cell lists its particles in ascending order, and
+                    // only later ones pair with `i`.
+                    let later = &items[items.partition_point(|&j| j as usize <= i)..];
+                    tested += later.len();
+                    scene.push_overlapping(i, later, pairs);
+                }
+            }
+        }
+        tested
+    }
+}
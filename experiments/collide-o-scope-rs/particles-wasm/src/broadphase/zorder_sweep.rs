@@ -0,0 +1,25 @@
+use std::ops::Range;
+
+use super::{Broadphase, Scene};
+use crate::zorder;
+
+/// Rectangle queries over the sorted Z-order codes, jumping past runs of
+/// non-neighbours with BIGMIN.
+pub struct ZOrderSweep;
+
+impl Broadphase for ZOrderSweep {
+    fn query(&self, scene: &Scene, range: Range<usize>, pairs: &mut Vec<(u32, u32)>) -> usize {
+        let mut tested = 0;
+        // Every neighbour of p lies within p.radius + max_radius of it, and
+        // only later entries need checking since pairs are found from both ends.
+        for i in range {
+            let reach = scene.radius[i] + scene.max_radius;
+            let (zmin, zmax) = zorder::query_bounds(scene.x[i], scene.y[i], reach, scene.width, scene.height);
+            zorder::scan_rect(scene.z_codes, i + 1, zmin, zmax, scene.search_range, |run| {
+                tested += run.len();
+                scene.push_overlapping_run(i, run, pairs);
+            });
+        }
+        tested
+    }
+}
@@ -0,0 +1,92 @@
+//! Conservation and thermodynamics measurements. Energies are in the
+//! simulation's own units: mass is the particle's area and velocities are
+//! pixels per reference frame, so kinetic energy is `m v² / 2` and
+//! potential energy under `gravity` is `m g h`, with `h` measured up from the
+//! floor.
+
+use wasm_bindgen::prelude::*;
+
+use crate::particles::Particles;
+
+/// A summary of the whole simulation, from `Simulation::diagnostics`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Diagnostics {
+    pub kinetic_energy: f64,
+    pub potential_energy: f64,
+    pub momentum_x: f64,
+    pub momentum_y: f64,
+    /// Pairs whose collision was resolved during the last step, summed
+    /// over its substeps.
+    pub collisions: u32,
+    pub mean_speed: f64,
+    pub max_speed: f64,
+    /// Kinetic energy taken out by inelastic collisions and wall bounces
+    /// during the last step, less any work moving bodies did on the
+    /// particles, so it can be negative.
+    pub energy_lost: f64,
+    /// `energy_lost` summed since the last `reset`, `restore` or rewind.
+    pub total_energy_lost: f64,
+    /// Mean kinetic energy per particle. With two degrees of freedom this is
+    /// `kT`, the temperature of the fitted Maxwell–Boltzmann distribution.
+    pub temperature: f64,
+}
+
+pub(crate) fn measure(p: &Particles, gravity: f32, height: f32) -> Diagnostics {
+    let mut d = Diagnostics::default();
+    let mut speed_sum = 0.0;
+    for i in 0..p.len() {
+        let (mass, vx, vy) = (p.mass[i] as f64, p.vx[i] as f64, p.vy[i] as f64);
+        let speed_sq = vx * vx + vy * vy;
+        let speed = speed_sq.sqrt();
+        d.kinetic_energy += 0.5 * mass * speed_sq;
+        d.potential_energy += mass * gravity as f64 * (height - p.y[i]) as f64;
+        d.momentum_x += mass * vx;
+        d.momentum_y += mass * vy;
+        speed_sum += speed;
+        d.max_speed = d.max_speed.max(speed);
+    }
+    if p.len() > 0 {
+        d.mean_speed = speed_sum / p.len() as f64;
+        d.temperature = d.kinetic_energy / p.len() as f64;
+    }
+    d
+}
+
+/// Particle counts in `bins` equal speed ranges over `0..max_speed`. Faster
+/// particles are counted in the last bin.
+pub(crate) fn speed_histogram(p: &Particles, bins: usize, max_speed: f32) -> Vec<u32> {
+    let mut counts = vec![0; bins];
+    if bins == 0 || max_speed <= 0.0 {
+        return counts;
+    }
+    let scale = bins as f32 / max_speed;
+    for i in 0..p.len() {
+        let speed = (p.vx[i] * p.vx[i] + p.vy[i] * p.vy[i]).sqrt();
+        counts[((speed * scale) as usize).min(bins - 1)] += 1;
+    }
+    counts
+}
+
+/// Expected counts in the bins of `speed_histogram` if the particles were in
+/// equilibrium at `temperature`. In 2D each particle's speed then follows a
+/// Rayleigh distribution with `P(speed < v) = 1 - exp(-m v² / 2kT)`; the
+/// expectation sums that over every particle's own mass.
+pub(crate) fn maxwell_boltzmann(p: &Particles, bins: usize, max_speed: f32, temperature: f64) -> Vec<f64> {
+    let mut expected = vec![0.0; bins];
+    if bins == 0 || max_speed <= 0.0 || temperature <= 0.0 {
+        return expected;
+    }
+    let width = max_speed as f64 / bins as f64;
+    for &mass in &p.mass {
+        let k = mass as f64 / (2.0 * temperature);
+        let mut below = 0.0;
+        for (bin, expected) in expected.iter_mut().enumerate() {
+            let upper = if bin + 1 == bins { f64::INFINITY } else { (bin + 1) as f64 * width };
+            let cdf = 1.0 - (-k * upper * upper).exp();
+            *expected += cdf - below;
+            below = cdf;
+        }
+    }
+    expected
+}
@@ -0,0 +1,84 @@
+//! Contact events for sound and effects. Every resolved collision of the
+//! last `update` (or `step`) can be recorded, up to a cap; past the cap the
+//! strongest impacts are kept.
+
+use std::cmp::Ordering;
+
+use wasm_bindgen::prelude::*;
+
+/// What `b` holds for a particle's contact with an obstacle or a body.
+pub const NO_PARTICLE: u32 = u32::MAX;
+
+/// One resolved collision. `a` and `b` are particle ids, `(x, y)` is the
+/// contact point on `a`'s surface and `(nx, ny)` the unit normal pointing
+/// from `b` to `a`. `impulse` is the magnitude of the impulse exchanged. A
+/// bounce off an obstacle or a body has `b` set to `NO_PARTICLE`, with the
+/// normal pointing out of the surface.
+#[wasm_bindgen]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CollisionEvent {
+    pub a: u32,
+    pub b: u32,
+    pub x: f32,
+    pub y: f32,
+    pub nx: f32,
+    pub ny: f32,
+    pub impulse: f32,
+}
+
+#[derive(Default)]
+pub(crate) struct EventBuffer {
+    capacity: usize,
+    events: Vec<CollisionEvent>,
+}
+
+impl EventBuffer {
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.events.clear();
+        // Room for twice the cap between prunes, so the pointer handed to JS
+        // only moves when the cap changes.
+        self.events.shrink_to(capacity * 2);
+        self.events.reserve_exact(capacity * 2);
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    pub fn push(&mut self, event: CollisionEvent) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.events.len() == self.capacity * 2 {
+            self.keep_strongest();
+        }
+        self.events.push(event);
+    }
+
+    /// Trims to the cap and orders strongest first, breaking ties by pair.
+    pub fn finish(&mut self) {
+        self.keep_strongest();
+        self.events.sort_unstable_by(strongest_first);
+    }
+
+    pub fn events(&self) -> &[CollisionEvent] {
+        &self.events
+    }
+
+    fn keep_strongest(&mut self) {
+        if self.events.len() > self.capacity {
+            self.events.select_nth_unstable_by(self.capacity, strongest_first);
+            self.events.truncate(self.capacity);
+        }
+    }
+}
+
+fn strongest_first(e: &CollisionEvent, f: &CollisionEvent) -> Ordering {
+    f.impulse.total_cmp(&e.impulse).then((e.a, e.b).cmp(&(f.a, f.b)))
+}
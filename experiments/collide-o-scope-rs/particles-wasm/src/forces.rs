@@ -0,0 +1,277 @@
+//! Force emitters: fans, point attractors and repulsors, vortices and wind
+//! zones. Each accelerates the particles inside its reach, in
+//! pixels per reference frame per reference frame like `gravity`, and the
+//! accelerations of overlapping emitters add up in the order they were added.
+
+use wasm_bindgen::prelude::*;
+
+use crate::particles::Particles;
+use crate::simd::F32x4;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EmitterKind {
+    /// Blows along `angle` across a `width`-wide band, hardest at the
+    /// middle of its base and fading to nothing at `reach` and at the edges.
+    Fan = 0,
+    /// Pulls towards (x, y), fading linearly to nothing at `reach`.
+    Attractor = 1,
+    /// Pushes away from (x, y), fading linearly to nothing at `reach`.
+    Repulsor = 2,
+    /// Spins particles around (x, y), clockwise on screen for a positive
+    /// strength, fading linearly to nothing at `reach`.
+    Vortex = 3,
+    /// Blows along `angle` with the same strength everywhere in its band.
+    Wind = 4,
+}
+
+impl EmitterKind {
+    pub(crate) fn from_u8(kind: u8) -> Option<EmitterKind> {
+        [EmitterKind::Fan, EmitterKind::Attractor, EmitterKind::Repulsor, EmitterKind::Vortex, EmitterKind::Wind]
+            .get(kind as usize)
+            .copied()
+    }
+}
+
+/// One emitter. Fans and wind cover the rectangle that starts at (x, y),
+/// runs `reach` along `angle` (radians, clockwise on screen from +x) and is
+/// `width` across. The other kinds cover the circle of radius `reach` around
+/// (x, y) and ignore `angle` and `width`. `strength` is the peak
+/// acceleration.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Emitter {
+    pub kind: EmitterKind,
+    pub x: f32,
+    pub y: f32,
+    pub angle: f32,
+    pub width: f32,
+    pub reach: f32,
+    pub strength: f32,
+}
+
+#[wasm_bindgen]
+impl Emitter {
+    #[wasm_bindgen(constructor)]
+    pub fn new(kind: EmitterKind, x: f32, y: f32, angle: f32, width: f32, reach: f32, strength: f32) -> Emitter {
+        Emitter { kind, x, y, angle, width, reach, strength }
+    }
+}
+
+/// The fan `fan_speed` controls: blowing up from the middle of the floor to
+/// half the height, four average particle sizes wide.
+pub(crate) fn default_fan(width: f32, height: f32, min_size: f32, max_size: f32, fan_speed: f32) -> Emitter {
+    let avg_size = (min_size + max_size) / 2.0;
+    let up = -std::f32::consts::FRAC_PI_2;
+    Emitter::new(EmitterKind::Fan, width / 2.0, height, up, avg_size * 4.0, height / 2.0, fan_strength(fan_speed))
+}
+
+/// Peak acceleration of the default fan at `fan_speed`.
+pub(crate) fn fan_strength(fan_speed: f32) -> f32 {
+    1.5 * fan_speed
+}
+
+/// An emitter with the constants the integrator needs worked out.
+#[derive(Clone, Copy)]
+struct Field {
+    kind: EmitterKind,
+    x: f32,
+    y: f32,
+    /// Unit vector along `angle`.
+    dx: f32,
+    dy: f32,
+    half_width: f32,
+    inv_half_width: f32,
+    reach: f32,
+    inv_reach: f32,
+    strength: f32,
+}
+
+impl Field {
+    fn new(e: &Emitter) -> Field {
+        let (dy, dx) = e.angle.sin_cos();
+        Field {
+            kind: e.kind,
+            x: e.x,
+            y: e.y,
+            dx,
+            dy,
+            half_width: e.width / 2.0,
+            inv_half_width: 2.0 / e.width,
+            reach: e.reach,
+            inv_reach: 1.0 / e.reach,
+            strength: e.strength,
+        }
+    }
+
+    /// Whether the field can affect anything; the rest divide by its size.
+    fn active(&self) -> bool {
+        let banded = matches!(self.kind, EmitterKind::Fan | EmitterKind::Wind);
+        self.strength != 0.0 && self.reach > 0.0 && (!banded || self.half_width > 0.0)
+    }
+}
+
+/// The emitters, each with an id that stays valid while others are added
+/// and removed. Ids count up and are never reused. The pointer's force, if
+/// any, acts last and isn't one of them.
+#[derive(Default)]
+pub(crate) struct ForceField {
+    emitters: Vec<(u32, Emitter)>,
+    pointer: Option<Emitter>,
+    fields: Vec<Field>,
+    next_id: u32,
+}
+
+impl ForceField {
+    /// Rebuilds a field from its saved parts; `emitters` must be in id order
+    /// and every id below `next_id`.
+    pub fn from_parts(next_id: u32, emitters: Vec<(u32, Emitter)>) -> Option<ForceField> {
+        let ordered = emitters.windows(2).all(|pair| pair[0].0 < pair[1].0);
+        if !ordered || emitters.last().is_some_and(|&(id, _)| id >= next_id) {
+            return None;
+        }
+        let mut field = ForceField { emitters, pointer: None, fields: Vec::new(), next_id };
+        field.compile();
+        Some(field)
+    }
+
+    pub fn next_id(&self) -> u32 {
+        self.next_id
+    }
+
+    pub fn emitters(&self) -> &[(u32, Emitter)] {
+        &self.emitters
+    }
+
+    pub fn add(&mut self, emitter: Emitter) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.emitters.push((id, emitter));
+        self.compile();
+        id
+    }
+
+    pub fn get(&self, id: u32) -> Option<Emitter> {
+        self.position(id).map(|k| self.emitters[k].1)
+    }
+
+    pub fn set(&mut self, id: u32, emitter: Emitter) -> bool {
+        let Some(k) = self.position(id) else {
+            return false;
+        };
+        self.emitters[k].1 = emitter;
+        self.compile();
+        true
+    }
+
+    pub fn remove(&mut self, id: u32) -> bool {
+        let Some(k) = self.position(id) else {
+            return false;
+        };
+        self.emitters.remove(k);
+        self.compile();
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.emitters.clear();
+        self.compile();
+    }
+
+    pub fn set_pointer(&mut self, pointer: Option<Emitter>) {
+        self.pointer = pointer;
+        self.compile();
+    }
+
+    fn position(&self, id: u32) -> Option<usize> {
+        self.emitters.binary_search_by_key(&id, |&(id, _)| id).ok()
+    }
+
+    fn compile(&mut self) {
+        self.fields.clear();
+        let emitters = self.emitters.iter().map(|(_, e)| e).chain(&self.pointer);
+        self.fields.extend(emitters.map(Field::new).filter(Field::active));
+    }
+
+    /// Accelerates particle `i` for a substep of `h` reference frames.
+    pub fn apply(&self, p: &mut Particles, i: usize, h: f32) {
+        for f in &self.fields {
+            let (rx, ry) = (p.x[i] - f.x, p.y[i] - f.y);
+            match f.kind {
+                EmitterKind::Fan | EmitterKind::Wind => {
+                    let along = rx * f.dx + ry * f.dy;
+                    let across = (ry * f.dx - rx * f.dy).abs();
+                    if along > 0.0 && along < f.reach && across < f.half_width {
+                        let mut strength = f.strength;
+                        if f.kind == EmitterKind::Fan {
+                            strength = strength * (1.0 - along * f.inv_reach) * (1.0 - across * f.inv_half_width);
+                        }
+                        p.vx[i] += f.dx * strength * h;
+                        p.vy[i] += f.dy * strength * h;
+                    }
+                }
+                EmitterKind::Attractor | EmitterKind::Repulsor | EmitterKind::Vortex => {
+                    let d2 = rx * rx + ry * ry;
+                    if d2 > 0.0 && d2 < f.reach * f.reach {
+                        let d = d2.sqrt();
+                        // Strength over distance, so scaling (rx, ry) gives a vector of that length.
+                        let scale = f.strength * (1.0 - d * f.inv_reach) / d;
+                        let minus = 0.0 - scale;
+                        let (ax, ay) = match f.kind {
+                            EmitterKind::Attractor => (rx * minus, ry * minus),
+                            EmitterKind::Repulsor => (rx * scale, ry * scale),
+                            _ => (ry * minus, rx * scale),
+                        };
+                        p.vx[i] += ax * h;
+                        p.vy[i] += ay * h;
+                    }
+                }
+            }
+        }
+    }
+
+    /// `apply` for four particles at once, returning their new velocities.
+    pub fn apply_lanes(&self, x: F32x4, y: F32x4, mut vx: F32x4, mut vy: F32x4, h: F32x4) -> (F32x4, F32x4) {
+        let splat = F32x4::splat;
+        let zero = splat(0.0);
+        for f in &self.fields {
+            let (rx, ry) = (x - splat(f.x), y - splat(f.y));
+            match f.kind {
+                EmitterKind::Fan | EmitterKind::Wind => {
+                    let (dx, dy) = (splat(f.dx), splat(f.dy));
+                    let along = rx * dx + ry * dy;
+                    let across = (ry * dx - rx * dy).abs();
+                    let inside = along.gt(zero) & along.lt(splat(f.reach)) & across.lt(splat(f.half_width));
+                    if inside.bits() == 0 {
+                        continue;
+                    }
+                    let mut strength = splat(f.strength);
+                    if f.kind == EmitterKind::Fan {
+                        let one = splat(1.0);
+                        strength = strength * (one - along * splat(f.inv_reach)) * (one - across * splat(f.inv_half_width));
+                    }
+                    vx = inside.select(vx + dx * strength * h, vx);
+                    vy = inside.select(vy + dy * strength * h, vy);
+                }
+                EmitterKind::Attractor | EmitterKind::Repulsor | EmitterKind::Vortex => {
+                    let d2 = rx * rx + ry * ry;
+                    let inside = d2.gt(zero) & d2.lt(splat(f.reach * f.reach));
+                    if inside.bits() == 0 {
+                        continue;
+                    }
+                    let d = d2.sqrt();
+                    let scale = splat(f.strength) * (splat(1.0) - d * splat(f.inv_reach)) / d;
+                    let minus = zero - scale;
+                    let (ax, ay) = match f.kind {
+                        EmitterKind::Attractor => (rx * minus, ry * minus),
+                        EmitterKind::Repulsor => (rx * scale, ry * scale),
+                        _ => (ry * minus, rx * scale),
+                    };
+                    vx = inside.select(vx + ax * h, vx);
+                    vy = inside.select(vy + ay * h, vy);
+                }
+            }
+        }
+        (vx, vy)
+    }
+}
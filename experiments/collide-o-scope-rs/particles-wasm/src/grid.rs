@@ -0,0 +1,121 @@
+//! A uniform grid of cells over a rectangle, with items binned into the
+//! cells their bounding boxes touch. It is stored as flat counting-sorted
+//! arrays that are reused from build to build. The uniform-grid broadphase,
+//! the periodic seam search and the obstacles all bin into one of these.
+
+/// Cap on the number of cells, so tiny items in a huge area don't allocate
+/// an enormous grid.
+const MAX_CELLS: usize = 1 << 20;
+
+#[derive(Default)]
+pub(crate) struct CellGrid {
+    x0: f32,
+    y0: f32,
+    cell_width: f32,
+    cell_height: f32,
+    cols: usize,
+    rows: usize,
+    /// Cell `c`'s items are `items[start[c]..start[c + 1]]`.
+    start: Vec<u32>,
+    fill: Vec<u32>,
+    items: Vec<u32>,
+}
+
+impl CellGrid {
+    /// Lays the grid over `bounds`, (x0, y0, x1, y1), in square cells at
+    /// least `min_size` wide. The last row and column may overhang.
+    pub fn cover(&mut self, bounds: (f32, f32, f32, f32), min_size: f32) {
+        let (width, height) = (bounds.2 - bounds.0, bounds.3 - bounds.1);
+        let mut size = min_size;
+        while ((width / size).ceil() * (height / size).ceil()) as usize > MAX_CELLS {
+            size *= 2.0;
+        }
+        let cols = ((width / size).ceil() as usize).max(1);
+        let rows = ((height / size).ceil() as usize).max(1);
+        (self.x0, self.y0, self.cell_width, self.cell_height, self.cols, self.rows) = (bounds.0, bounds.1, size, size, cols, rows);
+    }
+
+    /// Splits the box from the origin to (width, height) into whole cells at
+    /// least `min_size` on a side, stretched to fit it exactly, so cells on
+    /// opposite edges line up. A box narrower than `min_size` is one cell
+    /// across.
+    pub fn tile(&mut self, width: f32, height: f32, min_size: f32) {
+        let mut size = min_size;
+        while ((width / size).floor() * (height / size).floor()) as usize > MAX_CELLS {
+            size *= 2.0;
+        }
+        let cols = ((width / size) as usize).max(1);
+        let rows = ((height / size) as usize).max(1);
+        (self.x0, self.y0, self.cell_width, self.cell_height) = (0.0, 0.0, width / cols as f32, height / rows as f32);
+        (self.cols, self.rows) = (cols, rows);
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// The cell holding (x, y), or the nearest one if it is off the grid.
+    pub fn cell_of(&self, x: f32, y: f32) -> (usize, usize) {
+        let col = (((x - self.x0) / self.cell_width).max(0.0) as usize).min(self.cols - 1);
+        let row = (((y - self.y0) / self.cell_height).max(0.0) as usize).min(self.rows - 1);
+        (col, row)
+    }
+
+    /// Bins items `0..n` by counting sort. Item `k` goes in every cell its
+    /// bounding box, (x0, y0, x1, y1), touches, or nowhere if `bounds`
+    /// gives `None`. Each cell lists its items in ascending order.
+    pub fn fill(&mut self, n: usize, bounds: impl Fn(usize) -> Option<(f32, f32, f32, f32)>) {
+        let (cols, rows) = (self.cols, self.rows);
+        let (mut start, mut fill, mut items) = (std::mem::take(&mut self.start), std::mem::take(&mut self.fill), std::mem::take(&mut self.items));
+        let block = |k| {
+            let (x0, y0, x1, y1) = bounds(k)?;
+            Some((self.cell_of(x0, y0), self.cell_of(x1, y1)))
+        };
+        start.clear();
+        start.resize(cols * rows + 1, 0);
+        for k in 0..n {
+            if let Some(((c0, r0), (c1, r1))) = block(k) {
+                for row in r0..=r1 {
+                    for col in c0..=c1 {
+                        start[row * cols + col + 1] += 1;
+                    }
+                }
+            }
+        }
+        for cell in 0..cols * rows {
+            start[cell + 1] += start[cell];
+        }
+        fill.clear();
+        fill.extend_from_slice(&start);
+        items.clear();
+        items.resize(*start.last().unwrap() as usize, 0);
+        for k in 0..n {
+            if let Some(((c0, r0), (c1, r1))) = block(k) {
+                for row in r0..=r1 {
+                    for col in c0..=c1 {
+                        let slot = &mut fill[row * cols + col];
+                        items[*slot as usize] = k as u32;
+                        *slot += 1;
+                    }
+                }
+            }
+        }
+        (self.start, self.fill, self.items) = (start, fill, items);
+    }
+
+    /// The items in one cell.
+    pub fn cell(&self, col: usize, row: usize) -> &[u32] {
+        self.cells(row, col, col)
+    }
+
+    /// The items in cells `c0..=c1` of `row`, which are stored back to back.
+    /// An item spanning several of them appears once for each.
+    pub fn cells(&self, row: usize, c0: usize, c1: usize) -> &[u32] {
+        let (first, last) = (row * self.cols + c0, row * self.cols + c1);
+        &self.items[self.start[first] as usize..self.start[last + 1] as usize]
+    }
+}
@@ -0,0 +1,232 @@
+//! Bounded undo history behind `Simulation::step_back` and `rewind_to`.
+//!
+//! Every step first serialises the current state with the snapshot encoder
+//! and makes it the new `top`. The state it displaces is kept as an XOR
+//! delta against the new top that stores only the changed bytes (see
+//! `encode_delta`). XORing the delta into the top again recovers the older
+//! state, so stepping back one frame costs one delta.
+//! Every `KEYFRAME_INTERVAL`th frame, and any frame whose snapshot differs
+//! in size from the next one (particles were added), is kept whole instead,
+//! so a long rewind starts from the nearest keyframe above its target rather
+//! than undoing every delta from the top.
+
+use std::collections::VecDeque;
+
+use crate::trace::write_varint;
+
+const KEYFRAME_INTERVAL: u64 = 30;
+
+enum Data {
+    Keyframe(Vec<u8>),
+    Delta(Vec<u8>),
+}
+
+impl Data {
+    fn len(&self) -> usize {
+        match self {
+            Data::Keyframe(bytes) | Data::Delta(bytes) => bytes.len(),
+        }
+    }
+}
+
+/// The running diagnostics at a frame. They aren't part of the snapshot,
+/// so they are kept alongside it and restored with it.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct Counters {
+    pub collisions: u32,
+    pub energy_lost: f64,
+    pub total_energy_lost: f64,
+}
+
+struct Entry {
+    frame: u64,
+    counters: Counters,
+    data: Data,
+}
+
+#[derive(Default)]
+pub(crate) struct History {
+    length: usize,
+    /// Oldest first. Each delta turns the state of the entry after it (or
+    /// `top`, for the last one) into the state at its own frame.
+    entries: VecDeque<Entry>,
+    top: Option<(u64, Counters, Vec<u8>)>,
+    entry_bytes: usize,
+    spare: Vec<u8>,
+}
+
+impl History {
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// Keeps at most `length` past states, dropping the oldest first.
+    pub fn set_length(&mut self, length: usize) {
+        self.length = length;
+        self.trim();
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.top = None;
+        self.entry_bytes = 0;
+    }
+
+    /// Frame of the oldest state that can be restored.
+    pub fn start(&self) -> Option<u64> {
+        self.entries.front().map(|entry| entry.frame).or(self.top.as_ref().map(|(frame, _, _)| *frame))
+    }
+
+    pub fn byte_size(&self) -> usize {
+        self.entry_bytes + self.top.as_ref().map_or(0, |(_, _, bytes)| bytes.len())
+    }
+
+    /// A recycled buffer for the next snapshot passed to `push`.
+    pub fn take_buffer(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.spare)
+    }
+
+    /// The most recently pushed or restored state and its counters.
+    pub fn top(&self) -> (Counters, &[u8]) {
+        self.top.as_ref().map_or((Counters::default(), &[]), |(_, counters, bytes)| (*counters, bytes))
+    }
+
+    /// Records `bytes`, the snapshot of the state at `frame` about to be
+    /// stepped from, and the counters at that point.
+    pub fn push(&mut self, frame: u64, counters: Counters, bytes: Vec<u8>) {
+        match self.top.take() {
+            // Stepping again from a state that was just restored. If it was
+            // edited since, the delta below it no longer leads anywhere, so
+            // that state is rebuilt and kept whole.
+            Some((top_frame, _, top)) if top_frame == frame => {
+                if top != bytes {
+                    if let Some(entry) = self.entries.back_mut() {
+                        if let Data::Delta(delta) = &entry.data {
+                            let mut older = top.clone();
+                            apply_delta(&mut older, delta);
+                            self.entry_bytes = self.entry_bytes - delta.len() + older.len();
+                            entry.data = Data::Keyframe(older);
+                        }
+                    }
+                }
+                self.spare = top;
+            }
+            Some((top_frame, top_counters, top)) => {
+                let data = if top.len() != bytes.len() || top_frame % KEYFRAME_INTERVAL == 0 {
+                    Data::Keyframe(top)
+                } else {
+                    let mut delta = Vec::new();
+                    encode_delta(&top, &bytes, &mut delta);
+                    self.spare = top;
+                    Data::Delta(delta)
+                };
+                self.entry_bytes += data.len();
+                self.entries.push_back(Entry { frame: top_frame, counters: top_counters, data });
+            }
+            None => {}
+        }
+        self.top = Some((frame, counters, bytes));
+        self.trim();
+    }
+
+    /// Moves `top` to the state one step before the simulation's current
+    /// `frame`. Returns false when there is none.
+    pub fn step_back(&mut self, frame: u64) -> bool {
+        match &self.top {
+            Some((top_frame, _, _)) if *top_frame < frame => true,
+            Some(_) => self.pop(),
+            None => false,
+        }
+    }
+
+    /// Moves `top` to the state at `target`, or the oldest one kept if that
+    /// is later. Returns false when there is nothing before `frame`.
+    pub fn rewind(&mut self, frame: u64, target: u64) -> bool {
+        if target >= frame || !self.step_back(frame) {
+            return false;
+        }
+        // Skip straight to the nearest keyframe at or above the target.
+        let first = self.entries.partition_point(|entry| entry.frame < target);
+        if let Some(k) = (first..self.entries.len()).find(|&k| matches!(self.entries[k].data, Data::Keyframe(_))) {
+            for entry in self.entries.drain(k + 1..) {
+                self.entry_bytes -= entry.data.len();
+            }
+            self.pop();
+        }
+        while self.top.as_ref().is_some_and(|(frame, _, _)| *frame > target) && self.pop() {}
+        true
+    }
+
+    fn pop(&mut self) -> bool {
+        let (Some(entry), Some((frame, counters, top))) = (self.entries.pop_back(), self.top.as_mut()) else {
+            return false;
+        };
+        self.entry_bytes -= entry.data.len();
+        *frame = entry.frame;
+        *counters = entry.counters;
+        match entry.data {
+            Data::Keyframe(bytes) => self.spare = std::mem::replace(top, bytes),
+            Data::Delta(delta) => apply_delta(top, &delta),
+        }
+        true
+    }
+
+    fn trim(&mut self) {
+        if self.length == 0 {
+            self.clear();
+            return;
+        }
+        while self.entries.len() >= self.length {
+            let entry = self.entries.pop_front().unwrap();
+            self.entry_bytes -= entry.data.len();
+        }
+    }
+}
+
+/// Writes `old ^ new` (equal lengths) in groups of 8 bytes: the number of
+/// unchanged groups skipped as a varint, then a mask of the next group's
+/// changed bytes followed by those bytes XORed. Float fields that moved
+/// slightly keep their sign and exponent, so their high bytes drop out.
+fn encode_delta(old: &[u8], new: &[u8], out: &mut Vec<u8>) {
+    let mut skipped = 0;
+    for (old, new) in old.chunks(8).zip(new.chunks(8)) {
+        let mask = (0..old.len()).filter(|&k| old[k] != new[k]).fold(0u8, |mask, k| mask | 1 << k);
+        if mask == 0 {
+            skipped += 1;
+            continue;
+        }
+        write_varint(out, skipped);
+        skipped = 0;
+        out.push(mask);
+        out.extend((0..old.len()).filter(|&k| mask & 1 << k != 0).map(|k| old[k] ^ new[k]));
+    }
+}
+
+fn apply_delta(state: &mut [u8], mut delta: &[u8]) {
+    let mut group = 0;
+    while !delta.is_empty() {
+        group += read_varint(&mut delta);
+        let mask = delta[0];
+        delta = &delta[1..];
+        for k in (0..8).filter(|&k| mask & 1 << k != 0) {
+            state[group * 8 + k] ^= delta[0];
+            delta = &delta[1..];
+        }
+        group += 1;
+    }
+}
+
+/// Deltas are only ever produced by `encode_delta`, so unlike trace input
+/// they need no validation.
+fn read_varint(bytes: &mut &[u8]) -> usize {
+    let mut value = 0;
+    for shift in (0..).step_by(7) {
+        let byte = bytes[0];
+        *bytes = &bytes[1..];
+        value |= ((byte & 0x7f) as usize) << shift;
+        if byte & 0x80 == 0 {
+            break;
+        }
+    }
+    value
+}
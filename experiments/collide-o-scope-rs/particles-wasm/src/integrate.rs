@@ -0,0 +1,115 @@
+use crate::forces::ForceField;
+use crate::particles::Particles;
+use crate::simd::{self, F32x4, LANES};
+
+/// Per-substep constants for advancing particles: gravity, force emitters,
+/// cooling, motion and, if `walls` is set, bounces off the box's sides.
+pub(crate) struct Integrator<'a> {
+    pub gravity: f32,
+    pub elasticity: f32,
+    pub width: f32,
+    pub height: f32,
+    pub walls: bool,
+    pub forces: &'a ForceField,
+    pub cooling_rate: f32,
+    pub h: f32,
+}
+
+impl Integrator<'_> {
+    /// Advances `slots` and returns the kinetic energy the wall bounces
+    /// took out, summed in slot order so both paths agree exactly.
+    pub fn run(&self, p: &mut Particles, slots: std::ops::Range<usize>, simd: bool) -> f64 {
+        let mut lost = 0.0;
+        let mut i = slots.start;
+        if simd {
+            while i + LANES <= slots.end {
+                let mut lanes = [0.0; LANES];
+                self.lanes(p, i).store(&mut lanes);
+                lost += lanes.iter().map(|&v| v as f64).sum::<f64>();
+                i += LANES;
+            }
+        }
+        for i in i..slots.end {
+            lost += self.scalar(p, i) as f64;
+        }
+        lost
+    }
+
+    fn scalar(&self, p: &mut Particles, i: usize) -> f32 {
+        let h = self.h;
+        p.vy[i] += self.gravity * h;
+        self.forces.apply(p, i, h);
+
+        if p.temp[i] > 0.0 {
+            p.temp[i] = simd::max(p.temp[i] - self.cooling_rate * h, 0.0);
+        }
+
+        p.x[i] += p.vx[i] * h;
+        p.y[i] += p.vy[i] * h;
+
+        if !self.walls {
+            return 0.0;
+        }
+        let radius = p.radius[i];
+        let mut bounced = 0.0;
+        if p.x[i] - radius < 0.0 || p.x[i] + radius > self.width {
+            bounced += p.vx[i] * p.vx[i];
+            p.vx[i] *= -self.elasticity;
+            p.x[i] = simd::min(simd::max(p.x[i], radius), self.width - radius);
+        }
+        if p.y[i] - radius < 0.0 || p.y[i] + radius > self.height {
+            bounced += p.vy[i] * p.vy[i];
+            p.vy[i] *= -self.elasticity;
+            p.y[i] = simd::min(simd::max(p.y[i], radius), self.height - radius);
+        }
+        bounced * 0.5 * p.mass[i] * (1.0 - self.elasticity * self.elasticity)
+    }
+
+    /// `scalar` for slots `i..i + LANES`, with each branch turned into a
+    /// lane select over the same arithmetic.
+    fn lanes(&self, p: &mut Particles, i: usize) -> F32x4 {
+        let splat = F32x4::splat;
+        let h = splat(self.h);
+        let zero = splat(0.0);
+        let range = i..i + LANES;
+
+        let mut x = F32x4::load(&p.x[range.clone()]);
+        let mut y = F32x4::load(&p.y[range.clone()]);
+        let mut vx = F32x4::load(&p.vx[range.clone()]);
+        let mut vy = F32x4::load(&p.vy[range.clone()]);
+        let temp = F32x4::load(&p.temp[range.clone()]);
+        let radius = F32x4::load(&p.radius[range.clone()]);
+
+        vy = vy + splat(self.gravity) * h;
+        (vx, vy) = self.forces.apply_lanes(x, y, vx, vy, h);
+
+        let cooled = (temp - splat(self.cooling_rate) * h).max(zero);
+        temp.gt(zero).select(cooled, temp).store(&mut p.temp[range.clone()]);
+
+        x = x + vx * h;
+        y = y + vy * h;
+        if !self.walls {
+            x.store(&mut p.x[range.clone()]);
+            y.store(&mut p.y[range.clone()]);
+            vx.store(&mut p.vx[range.clone()]);
+            vy.store(&mut p.vy[range]);
+            return zero;
+        }
+
+        let bounce = splat(-self.elasticity);
+        let hit_x = (x - radius).lt(zero) | (x + radius).gt(splat(self.width));
+        let hit_y = (y - radius).lt(zero) | (y + radius).gt(splat(self.height));
+        let bounced = zero + hit_x.select(vx * vx, zero) + hit_y.select(vy * vy, zero);
+        vx = hit_x.select(vx * bounce, vx);
+        x = hit_x.select(x.max(radius).min(splat(self.width) - radius), x);
+        vy = hit_y.select(vy * bounce, vy);
+        y = hit_y.select(y.max(radius).min(splat(self.height) - radius), y);
+
+        x.store(&mut p.x[range.clone()]);
+        y.store(&mut p.y[range.clone()]);
+        vx.store(&mut p.vx[range.clone()]);
+        vy.store(&mut p.vy[range.clone()]);
+        let mass = F32x4::load(&p.mass[range]);
+        bounced * splat(0.5) * mass * splat(1.0 - self.elasticity * self.elasticity)
+    }
+}
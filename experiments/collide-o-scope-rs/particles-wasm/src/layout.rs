@@ -0,0 +1,85 @@
+//! Describes the memory layout of `Particle` and `CollisionEvent` so JS can
+//! build its typed-array views from the crate instead of hardcoding offsets.
+
+use std::mem::{offset_of, size_of};
+
+use wasm_bindgen::prelude::*;
+
+use crate::{CollisionEvent, Particle};
+
+/// Bump whenever a `Particle` field is added, removed, reordered or retyped.
+pub const PARTICLE_LAYOUT_VERSION: u32 = 2;
+
+/// Bump whenever a `CollisionEvent` field is added, removed, reordered or
+/// retyped.
+pub const EVENT_LAYOUT_VERSION: u32 = 1;
+
+// Float32Array views need every record to start on a 4-byte boundary.
+const _: () = assert!(size_of::<Particle>().is_multiple_of(4));
+const _: () = assert!(size_of::<CollisionEvent>().is_multiple_of(4));
+
+pub struct FieldLayout {
+    pub name: &'static str,
+    pub offset: usize,
+    /// "f32", "u32" or "u8" (`bool` is stored as a single byte).
+    pub ty: &'static str,
+}
+
+pub const PARTICLE_FIELDS: [FieldLayout; 14] = [
+    FieldLayout { name: "x", offset: offset_of!(Particle, x), ty: "f32" },
+    FieldLayout { name: "y", offset: offset_of!(Particle, y), ty: "f32" },
+    FieldLayout { name: "radius", offset: offset_of!(Particle, radius), ty: "f32" },
+    FieldLayout { name: "mass", offset: offset_of!(Particle, mass), ty: "f32" },
+    FieldLayout { name: "vx", offset: offset_of!(Particle, vx), ty: "f32" },
+    FieldLayout { name: "vy", offset: offset_of!(Particle, vy), ty: "f32" },
+    FieldLayout { name: "colliding", offset: offset_of!(Particle, colliding), ty: "u8" },
+    FieldLayout { name: "z_code", offset: offset_of!(Particle, z_code), ty: "u32" },
+    FieldLayout { name: "impulse_x", offset: offset_of!(Particle, impulse_x), ty: "f32" },
+    FieldLayout { name: "impulse_y", offset: offset_of!(Particle, impulse_y), ty: "f32" },
+    FieldLayout { name: "color_temp", offset: offset_of!(Particle, color_temp), ty: "f32" },
+    FieldLayout { name: "prev_x", offset: offset_of!(Particle, prev_x), ty: "f32" },
+    FieldLayout { name: "prev_y", offset: offset_of!(Particle, prev_y), ty: "f32" },
+    FieldLayout { name: "id", offset: offset_of!(Particle, id), ty: "u32" },
+];
+
+pub const EVENT_FIELDS: [FieldLayout; 7] = [
+    FieldLayout { name: "a", offset: offset_of!(CollisionEvent, a), ty: "u32" },
+    FieldLayout { name: "b", offset: offset_of!(CollisionEvent, b), ty: "u32" },
+    FieldLayout { name: "x", offset: offset_of!(CollisionEvent, x), ty: "f32" },
+    FieldLayout { name: "y", offset: offset_of!(CollisionEvent, y), ty: "f32" },
+    FieldLayout { name: "nx", offset: offset_of!(CollisionEvent, nx), ty: "f32" },
+    FieldLayout { name: "ny", offset: offset_of!(CollisionEvent, ny), ty: "f32" },
+    FieldLayout { name: "impulse", offset: offset_of!(CollisionEvent, impulse), ty: "f32" },
+];
+
+#[wasm_bindgen]
+pub fn particle_layout_version() -> u32 {
+    PARTICLE_LAYOUT_VERSION
+}
+
+/// Bytes from one particle to the next in the `get_particles_ptr` buffer.
+#[wasm_bindgen]
+pub fn particle_stride() -> usize {
+    size_of::<Particle>()
+}
+
+/// The full descriptor as JSON:
+/// `{"version":2,"stride":56,"fields":[{"name":"x","offset":0,"type":"f32"},...]}`.
+#[wasm_bindgen]
+pub fn particle_layout() -> String {
+    describe(PARTICLE_LAYOUT_VERSION, particle_stride(), &PARTICLE_FIELDS)
+}
+
+/// The same descriptor for the `get_events_ptr` buffer.
+#[wasm_bindgen]
+pub fn collision_event_layout() -> String {
+    describe(EVENT_LAYOUT_VERSION, size_of::<CollisionEvent>(), &EVENT_FIELDS)
+}
+
+fn describe(version: u32, stride: usize, fields: &[FieldLayout]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|f| format!(r#"{{"name":"{}","offset":{},"type":"{}"}}"#, f.name, f.offset, f.ty))
+        .collect();
+    format!(r#"{{"version":{},"stride":{},"fields":[{}]}}"#, version, stride, fields.join(","))
+}
@@ -1,29 +1,76 @@
 use wasm_bindgen::prelude::*;
-use js_sys::Math;
+
+mod bodies;
+mod boundary;
+pub mod broadphase;
+mod clock;
+mod diagnostics;
+mod events;
+mod forces;
+mod grid;
+mod history;
+mod integrate;
+pub mod layout;
+mod narrow;
+mod obstacles;
+mod particles;
+mod pointer;
+mod query;
+mod rng;
+pub mod simd;
+mod snapshot;
+pub mod sort;
+mod stats;
+mod threads;
+mod trace;
+pub mod zorder;
+
+use bodies::{Bodies, Body, Motion};
+use boundary::Boundary;
+use broadphase::{Broadphase, BroadphaseKind, Scene, SeamPair, Seams, SortedView};
+use events::EventBuffer;
+use forces::ForceField;
+use history::{Counters, History};
+use integrate::Integrator;
+use narrow::NarrowPhase;
+use obstacles::{Mover, Obstacle, Obstacles};
+use particles::Particles;
+use pointer::{Grab, Pointer};
+use query::SpatialIndex;
+use sort::RadixSorter;
+pub use bodies::{BodyMotion, BodyState};
+pub use boundary::BoundaryMode;
+pub use diagnostics::Diagnostics;
+pub use events::{CollisionEvent, NO_PARTICLE};
+pub use forces::{Emitter, EmitterKind};
+pub use obstacles::ObstacleKind;
+pub use pointer::PointerMode;
+pub use rng::Rng;
+pub use snapshot::{SnapshotError, SNAPSHOT_VERSION};
+pub use stats::FrameStats;
+pub use trace::{Player, Recorder, TraceError, TRACE_VERSION};
+pub use zorder::ZOrder;
+
+/// Velocities, gravity and the fan are expressed per reference frame, which
+/// is what the simulation originally advanced by on every `update` call.
+const REFERENCE_DT: f32 = 1.0 / 60.0;
+/// Longest wall-clock gap `update` will try to catch up on, so a stalled tab
+/// doesn't come back to a burst of hundreds of steps.
+const MAX_FRAME_TIME: f64 = 0.25;
+/// Id of the fan `fan_speed` controls, which every simulation starts with.
+const DEFAULT_FAN: u32 = 0;
 
 // Set up the global allocator
 #[cfg(feature = "wee_alloc")]
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
-pub struct ZOrder;
-
-impl ZOrder {
-    fn interleave(n: u32) -> u32 {
-        let n = (n | (n << 8)) & 0x00FF00FF;
-        let n = (n | (n << 4)) & 0x0F0F0F0F;
-        let n = (n | (n << 2)) & 0x33333333;
-        (n | (n << 1)) & 0x55555555
-    }
-
-    pub fn encode(x: u32, y: u32) -> u32 {
-        ZOrder::interleave(x) | (ZOrder::interleave(y) << 1)
-    }
-}
-
+/// One particle's state. The simulation stores particles as separate
+/// attribute arrays; this record is what `get_particles_ptr` and the Rust
+/// API hand out. `id` stays with the particle from spawn to `reset`.
 #[wasm_bindgen]
 #[repr(C)]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Particle {
     pub x: f32,
     pub y: f32,
@@ -36,14 +83,49 @@ pub struct Particle {
     pub impulse_x: f32,
     pub impulse_y: f32,
     pub color_temp: f32,
+    pub prev_x: f32,
+    pub prev_y: f32,
+    pub id: u32,
 }
 
+/// How particles are ordered by Z-order code at the start of each collision
+/// pass. Both are stable and produce the same order.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortAlgorithm {
+    Radix = 0,
+    Comparison = 1,
+}
+
+/// What one thread's share of the collision search found.
+#[derive(Default)]
+struct ChunkPairs {
+    pairs: Vec<(u32, u32)>,
+    seam_pairs: Vec<SeamPair>,
+    /// How many candidate pairs it tested.
+    tested: usize,
+}
+
+/// What one thread's share of the obstacle and body contacts found.
+#[derive(Default)]
+struct ChunkContacts {
+    /// Each particle that touched something, by slot, where it ended up,
+    /// and the kinetic energy its bounces took out.
+    moved: Vec<(u32, Mover, f64)>,
+    events: Vec<CollisionEvent>,
+    candidates: Vec<u32>,
+}
 
 #[wasm_bindgen]
 pub struct Simulation {
     width: f32,
     height: f32,
-    particles: Vec<Particle>,
+    particles: Particles,
+    /// Particle slots sorted by Z-order code.
+    order: Vec<u32>,
+    /// Id the next particle gets. Ids only count up, and removal keeps slot
+    /// order, so `particles.id` is always ascending.
+    next_id: u32,
     particle_count: usize,
     min_size: f32,
     max_size: f32,
@@ -51,17 +133,58 @@ pub struct Simulation {
     gravity: f32,
     elasticity: f32,
     fan_speed: f32,
+    forces: ForceField,
+    obstacles: Obstacles,
+    bodies: Bodies,
+    boundary: Boundary,
+    pointer: Pointer,
+    grab: Option<Grab>,
     search_range: i32,
+    seed: u64,
+    rng: Rng,
+    fixed_dt: f32,
+    substeps: u32,
+    accumulator: f64,
+    frame: u64,
+    history: History,
+    collisions: u32,
+    energy_lost: f64,
+    total_energy_lost: f64,
+    sort_algorithm: SortAlgorithm,
+    sorter: RadixSorter,
+    sort_time_ms: f64,
+    stats: FrameStats,
+    events: EventBuffer,
+    sorted: SortedView,
+    index: SpatialIndex,
+    broadphase_kind: BroadphaseKind,
+    broadphase: Box<dyn Broadphase>,
+    pairs: Vec<(u32, u32)>,
+    seams: Seams,
+    /// Ranks meeting across a periodic seam, lower rank first.
+    seam_pairs: Vec<SeamPair>,
+    /// What each thread found, merged in chunk order.
+    chunk_pairs: Vec<ChunkPairs>,
+    narrow: NarrowPhase,
+    chunk_contacts: Vec<ChunkContacts>,
+    threads: usize,
+    simd: bool,
+    export: Vec<Particle>,
+    positions: Vec<f32>,
+    buffer_generation: u32,
 }
 
 #[wasm_bindgen]
 impl Simulation {
     #[wasm_bindgen(constructor)]
-    pub fn new(width: f32, height: f32, particle_count: usize, min_size: f32, max_size: f32, max_speed: f32, gravity: f32, elasticity: f32, fan_speed: f32, search_range: i32) -> Simulation {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(width: f32, height: f32, particle_count: usize, min_size: f32, max_size: f32, max_speed: f32, gravity: f32, elasticity: f32, fan_speed: f32, search_range: i32, seed: u64) -> Simulation {
         let mut simulation = Simulation {
             width,
             height,
-            particles: Vec::new(),
+            particles: Particles::default(),
+            order: Vec::new(),
+            next_id: 0,
             particle_count,
             min_size,
             max_size,
@@ -69,178 +192,709 @@ impl Simulation {
             gravity,
             elasticity,
             fan_speed,
+            forces: ForceField::default(),
+            obstacles: Obstacles::default(),
+            bodies: Bodies::default(),
+            boundary: Boundary::default(),
+            pointer: Pointer::default(),
+            grab: None,
             search_range,
+            seed,
+            rng: Rng::new(seed),
+            fixed_dt: REFERENCE_DT,
+            substeps: 1,
+            accumulator: 0.0,
+            frame: 0,
+            history: History::default(),
+            collisions: 0,
+            energy_lost: 0.0,
+            total_energy_lost: 0.0,
+            sort_algorithm: SortAlgorithm::Radix,
+            sorter: RadixSorter::default(),
+            sort_time_ms: 0.0,
+            stats: FrameStats::default(),
+            events: EventBuffer::default(),
+            sorted: SortedView::default(),
+            index: SpatialIndex::default(),
+            broadphase_kind: BroadphaseKind::ZOrder,
+            broadphase: broadphase::create(BroadphaseKind::ZOrder),
+            pairs: Vec::new(),
+            seams: Seams::default(),
+            seam_pairs: Vec::new(),
+            chunk_pairs: Vec::new(),
+            narrow: NarrowPhase::default(),
+            chunk_contacts: Vec::new(),
+            threads: threads::default_threads(),
+            simd: cfg!(all(feature = "simd", target_arch = "wasm32", target_feature = "simd128")),
+            export: Vec::new(),
+            positions: Vec::new(),
+            buffer_generation: 0,
         };
+        simulation.forces.add(forces::default_fan(width, height, min_size, max_size, fan_speed));
         simulation.reset();
         simulation
     }
 
+    /// Rebuilds the particle field. The generator is rewound to the current
+    /// seed first, so the same seed and parameters give the same layout.
     pub fn reset(&mut self) {
-        self.particles = Vec::new();
+        self.rng = Rng::new(self.seed);
+        self.particles.clear();
+        self.order.clear();
+        self.next_id = 0;
+        self.index.invalidate();
+        self.grab = None;
+        self.accumulator = 0.0;
+        self.frame = 0;
+        self.history.clear();
+        self.collisions = 0;
+        self.energy_lost = 0.0;
+        self.total_energy_lost = 0.0;
+        let h = self.substep_scale();
         for _ in 0..self.particle_count {
-            let radius = self.min_size + (Math::random() as f32) * (self.max_size - self.min_size);
-            let mut p = Particle {
-                x: (Math::random() as f32) * self.width,
-                y: (Math::random() as f32) * self.height,
+            let radius = self.min_size + self.rng.next_f32() * (self.max_size - self.min_size);
+            let p = Particle {
+                x: self.rng.next_f32() * self.width,
+                y: self.rng.next_f32() * self.height,
                 radius,
                 mass: std::f32::consts::PI * radius * radius,
-                vx: ((Math::random() as f32) - 0.5) * self.max_speed,
-                vy: ((Math::random() as f32) - 0.5) * self.max_speed,
+                vx: (self.rng.next_f32() - 0.5) * self.max_speed,
+                vy: (self.rng.next_f32() - 0.5) * self.max_speed,
                 colliding: false,
                 z_code: 0,
                 impulse_x: 0.0,
                 impulse_y: 0.0,
-                color_temp: Math::random() as f32,
+                color_temp: self.rng.next_f32(),
+                prev_x: 0.0,
+                prev_y: 0.0,
+                id: 0,
             };
-            self.update_particle(&mut p);
-            self.particles.push(p);
+            self.spawn(p, h);
         }
     }
 
-    pub fn update(&mut self) {
-        for i in 0..self.particles.len() {
-            let mut p = self.particles[i];
-            self.update_particle(&mut p);
-            self.particles[i] = p;
+    /// Advances the simulation by `dt` seconds of wall-clock time. Elapsed
+    /// time is banked and spent in whole fixed steps, so motion is the same at
+    /// any refresh rate; the remainder is reported by `get_interpolation_alpha`.
+    pub fn update(&mut self, dt: f32) {
+        self.stats = FrameStats::default();
+        self.events.clear();
+        let fixed_dt = self.fixed_dt as f64;
+        // A NaN would stick in the accumulator and stop every later update.
+        let dt = if dt.is_finite() { dt as f64 } else { 0.0 };
+        self.accumulator += dt.clamp(0.0, MAX_FRAME_TIME);
+        // Tolerate rounding in the banked time so e.g. 144 calls of 1/144 s
+        // run exactly 60 steps of 1/60 s.
+        let epsilon = fixed_dt * 1e-4;
+        while self.accumulator + epsilon >= fixed_dt {
+            self.advance();
+            self.accumulator = (self.accumulator - fixed_dt).max(0.0);
         }
+        self.events.finish();
+    }
 
-        self.detect_collisions();
+    /// Advances exactly one fixed timestep, split into `substeps` integration
+    /// and collision passes.
+    pub fn step(&mut self) {
+        self.stats = FrameStats::default();
+        self.events.clear();
+        self.advance();
+        self.events.finish();
     }
 
-    pub fn get_particles_ptr(&self) -> *const Particle {
-        self.particles.as_ptr()
+    /// Records up to `max_events` collisions per `update` or `step` for
+    /// `get_events_ptr`, keeping the strongest when there are more. 0, the
+    /// default, records none.
+    pub fn set_event_capacity(&mut self, max_events: usize) {
+        self.events.set_capacity(max_events);
+    }
+
+    pub fn get_event_capacity(&self) -> usize {
+        self.events.capacity()
+    }
+
+    /// The collisions resolved during the last `update` or `step`, strongest
+    /// first, as `get_event_count()` `CollisionEvent` records (see
+    /// `collision_event_layout`). The pointer only moves when the capacity
+    /// changes.
+    pub fn get_events_ptr(&self) -> *const CollisionEvent {
+        self.events.events().as_ptr()
+    }
+
+    pub fn get_event_count(&self) -> usize {
+        self.events.events().len()
+    }
+
+    /// Phase timings and pair counts for the last `update` or `step`.
+    pub fn get_stats(&self) -> FrameStats {
+        self.stats
+    }
+
+    fn advance(&mut self) {
+        if self.history.length() > 0 {
+            let mut bytes = self.history.take_buffer();
+            snapshot::write_into(self, &mut bytes);
+            self.history.push(self.frame, self.counters(), bytes);
+        }
+        self.particles.prev_x.copy_from_slice(&self.particles.x);
+        self.particles.prev_y.copy_from_slice(&self.particles.y);
+
+        self.sort_time_ms = 0.0;
+        self.collisions = 0;
+        self.energy_lost = 0.0;
+        let h = self.substep_scale();
+        self.bodies.begin_step(self.fixed_dt / REFERENCE_DT);
+        for _ in 0..self.substeps {
+            self.bodies.advance(h);
+            self.integrate(0..self.particles.len(), h);
+            self.remove_escaped();
+            self.detect_collisions();
+            self.collide_obstacles();
+        }
+        self.bodies.end_step();
+        self.total_energy_lost += self.energy_lost;
+        self.frame += 1;
+        self.stats.steps += 1;
+    }
+
+    /// How far the banked time has progressed into the next fixed step, in
+    /// [0, 1). Render at `prev + (pos - prev) * alpha` for smooth motion.
+    pub fn get_interpolation_alpha(&self) -> f32 {
+        (self.accumulator / self.fixed_dt as f64).min(1.0) as f32
+    }
+
+    /// Number of fixed steps taken since the last `reset`.
+    pub fn get_frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn set_fixed_timestep(&mut self, seconds: f32) {
+        if seconds > 0.0 {
+            self.fixed_dt = seconds;
+        }
+    }
+
+    pub fn set_substeps(&mut self, substeps: u32) {
+        self.substeps = substeps.max(1);
+    }
+
+    pub fn set_sort_algorithm(&mut self, algorithm: SortAlgorithm) {
+        self.sort_algorithm = algorithm;
+    }
+
+    /// How many threads `detect_collisions` splits the broadphase queries
+    /// across. Defaults to the number of cores in native builds with the
+    /// `threads` feature and to 1 otherwise; without threads to run on, the
+    /// chunks run in turn. The result never depends on the count.
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    pub fn get_threads(&self) -> usize {
+        self.threads
+    }
+
+    /// Run the integrator and the narrow-phase distance tests four particles
+    /// at a time. Defaults to on in wasm builds with the `simd` feature, and
+    /// to the scalar path elsewhere. Both produce identical results.
+    pub fn set_simd(&mut self, enabled: bool) {
+        self.simd = enabled;
+    }
+
+    pub fn get_simd(&self) -> bool {
+        self.simd
+    }
+
+    /// Milliseconds spent sorting by Z-order code during the last `step`,
+    /// summed over its substeps.
+    pub fn get_sort_time_ms(&self) -> f64 {
+        self.sort_time_ms
+    }
+
+    /// Packs every particle into a `Particle` record (see `particle_layout`)
+    /// in slot order and returns the buffer. Call once per frame after
+    /// `update`; the pointer is stable until `get_buffer_generation` changes.
+    pub fn get_particles_ptr(&mut self) -> *const Particle {
+        if self.export.capacity() < self.particles.len() {
+            let buffers = self.buffer_addresses();
+            self.export.reserve_exact(self.particles.capacity() - self.export.len());
+            self.note_moved_buffers(buffers);
+        }
+        self.export.clear();
+        self.export.extend((0..self.particles.len()).map(|i| self.particles.get(i)));
+        self.export.as_ptr()
     }
     
     pub fn get_particle_count(&self) -> usize {
         self.particles.len()
     }
 
-    fn update_particle(&self, p: &mut Particle) {
-        p.vy += self.gravity;
+    /// Slot currently holding particle `id`, or `undefined` if there is no
+    /// such particle.
+    pub fn get_slot(&self, id: u32) -> Option<usize> {
+        self.particles.id.binary_search(&id).ok()
+    }
 
-        if self.fan_speed > 0.0 {
-            let avg_size = (self.min_size + self.max_size) / 2.0;
-            let fan_width = avg_size * 4.0;
-            let fan_center_x = self.width / 2.0;
-            let fan_bottom = self.height;
-            let fan_top = self.height / 2.0;
+    /// The particle with this `id`, wherever it is stored.
+    pub fn get_particle(&self, id: u32) -> Option<Particle> {
+        self.get_slot(id).map(|slot| self.particles.get(slot))
+    }
 
-            let dx = p.x - fan_center_x;
-            if dx.abs() < fan_width / 2.0 {
-                if p.y > fan_top {
-                    let distance_from_bottom = (p.y - fan_top) / (fan_bottom - fan_top);
-                    let base_fan_force = 1.5 * distance_from_bottom;
-                    let horizontal_factor = 1.0 - dx.abs() / (fan_width / 2.0);
-                    p.vy -= base_fan_force * horizontal_factor * self.fan_speed;
-                }
-            }
+    /// Overwrites particle `id`'s position, previous position, velocity,
+    /// radius and colour temperature with `particle`'s; its mass follows
+    /// from the radius. Returns false if there is no such particle.
+    pub fn set_particle(&mut self, id: u32, particle: &Particle) -> bool {
+        let Some(slot) = self.get_slot(id) else {
+            return false;
+        };
+        let p = &mut self.particles;
+        p.x[slot] = particle.x;
+        p.y[slot] = particle.y;
+        p.prev_x[slot] = particle.prev_x;
+        p.prev_y[slot] = particle.prev_y;
+        p.vx[slot] = particle.vx;
+        p.vy[slot] = particle.vy;
+        p.radius[slot] = particle.radius;
+        p.mass[slot] = std::f32::consts::PI * particle.radius * particle.radius;
+        p.temp[slot] = particle.color_temp;
+        p.z_code[slot] = zorder::code_at(particle.x, particle.y, self.width, self.height);
+        self.index.invalidate();
+        true
+    }
+
+    /// Id of the particle under (x, y), or `undefined`. Where particles
+    /// overlap, the one whose centre is nearest wins.
+    pub fn pick(&mut self, x: f32, y: f32) -> Option<u32> {
+        self.refresh_index();
+        self.index.pick(&self.particles, x, y, self.search_range.max(0) as usize)
+    }
+
+    /// Ids, ascending, of the particles touching the circle of radius `r`
+    /// around (x, y).
+    pub fn query_radius(&mut self, x: f32, y: f32, r: f32) -> Vec<u32> {
+        self.refresh_index();
+        self.index.within_radius(&self.particles, x, y, r, self.search_range.max(0) as usize)
+    }
+
+    /// Ids, ascending, of the particles touching the rectangle with corners
+    /// (x0, y0) and (x1, y1).
+    pub fn query_rect(&mut self, x0: f32, y0: f32, x1: f32, y1: f32) -> Vec<u32> {
+        self.refresh_index();
+        self.index.in_rect(&self.particles, x0, y0, x1, y1, self.search_range.max(0) as usize)
+    }
+
+    /// Ids of the `k` particles whose centres are nearest to (x, y), nearest
+    /// first.
+    pub fn k_nearest(&mut self, x: f32, y: f32, k: usize) -> Vec<u32> {
+        self.refresh_index();
+        self.index.nearest(&self.particles, x, y, k, self.search_range.max(0) as usize)
+    }
+
+    /// Packed `[x0, y0, x1, y1, ...]` in slot order. Refreshed by each call;
+    /// read it before the next `update`.
+    pub fn get_positions_ptr(&mut self) -> *const f32 {
+        self.positions.clear();
+        self.positions.extend(self.particles.x.iter().zip(&self.particles.y).flat_map(|(&x, &y)| [x, y]));
+        self.positions.as_ptr()
+    }
+
+    // Attribute arrays, one entry per particle slot. These are the live
+    // simulation state, not copies, and stay valid until
+    // `get_buffer_generation` changes.
+
+    pub fn get_x_ptr(&self) -> *const f32 {
+        self.particles.x.as_ptr()
+    }
+
+    pub fn get_y_ptr(&self) -> *const f32 {
+        self.particles.y.as_ptr()
+    }
+
+    pub fn get_prev_x_ptr(&self) -> *const f32 {
+        self.particles.prev_x.as_ptr()
+    }
+
+    pub fn get_prev_y_ptr(&self) -> *const f32 {
+        self.particles.prev_y.as_ptr()
+    }
+
+    pub fn get_vx_ptr(&self) -> *const f32 {
+        self.particles.vx.as_ptr()
+    }
+
+    pub fn get_vy_ptr(&self) -> *const f32 {
+        self.particles.vy.as_ptr()
+    }
+
+    pub fn get_radii_ptr(&self) -> *const f32 {
+        self.particles.radius.as_ptr()
+    }
+
+    pub fn get_masses_ptr(&self) -> *const f32 {
+        self.particles.mass.as_ptr()
+    }
+
+    /// `color_temp` per slot.
+    pub fn get_temperatures_ptr(&self) -> *const f32 {
+        self.particles.temp.as_ptr()
+    }
+
+    /// One byte per slot, 1 while touching another particle.
+    pub fn get_colliding_ptr(&self) -> *const u8 {
+        self.particles.colliding.as_ptr() as *const u8
+    }
+
+    /// Each slot's particle id (`u32`).
+    pub fn get_ids_ptr(&self) -> *const u32 {
+        self.particles.id.as_ptr()
+    }
+
+    /// Slot indices (`u32`) sorted by Z-order code as of the last collision pass.
+    pub fn get_order_ptr(&self) -> *const u32 {
+        self.order.as_ptr()
+    }
+
+    /// Number of particles touching at least one other particle this frame.
+    pub fn get_collision_count(&self) -> usize {
+        self.particles.colliding.iter().filter(|&&colliding| colliding).count()
+    }
+
+    pub fn kinetic_energy(&self) -> f32 {
+        let p = &self.particles;
+        (0..p.len()).map(|i| 0.5 * p.mass[i] * (p.vx[i] * p.vx[i] + p.vy[i] * p.vy[i])).sum()
+    }
+
+    /// Energies, momentum and speeds now, plus the collisions and energy
+    /// lost during the last step (see `diagnostics.rs`). O(n).
+    pub fn diagnostics(&self) -> Diagnostics {
+        Diagnostics {
+            collisions: self.collisions,
+            energy_lost: self.energy_lost,
+            total_energy_lost: self.total_energy_lost,
+            ..diagnostics::measure(&self.particles, self.gravity, self.height)
+        }
+    }
+
+    /// Particle counts in `bins` equal speed ranges from 0 to `max_speed`,
+    /// with faster particles counted in the last bin.
+    pub fn speed_histogram(&self, bins: usize, max_speed: f32) -> Vec<u32> {
+        diagnostics::speed_histogram(&self.particles, bins, max_speed)
+    }
+
+    /// The counts `speed_histogram` would show for the same bins if the
+    /// particles were in Maxwell–Boltzmann equilibrium at the current
+    /// temperature. O(n × bins).
+    pub fn maxwell_boltzmann_histogram(&self, bins: usize, max_speed: f32) -> Vec<f64> {
+        let temperature = diagnostics::measure(&self.particles, self.gravity, self.height).temperature;
+        diagnostics::maxwell_boltzmann(&self.particles, bins, max_speed, temperature)
+    }
+
+    pub fn set_broadphase(&mut self, kind: BroadphaseKind) {
+        if kind != self.broadphase_kind {
+            self.broadphase_kind = kind;
+            self.broadphase = broadphase::create(kind);
         }
-        
-        let cooling_rate = 0.01; 
-        if p.color_temp > 0.0 {
-            p.color_temp = (p.color_temp - cooling_rate).max(0.0);
+    }
+
+    pub fn get_broadphase(&self) -> BroadphaseKind {
+        self.broadphase_kind
+    }
+
+    /// Checks the current broadphase against a brute-force pass over the
+    /// current positions and returns how many overlapping pairs it missed.
+    /// O(n²); meant for validation, not for every frame.
+    pub fn count_missed_collisions(&mut self) -> usize {
+        let p = &self.particles;
+        let mut order: Vec<(u32, usize)> = (0..p.len())
+            .map(|i| (zorder::code_at(p.x[i], p.y[i], self.width, self.height), i))
+            .collect();
+        order.sort_unstable();
+        let x: Vec<f32> = order.iter().map(|&(_, i)| p.x[i]).collect();
+        let y: Vec<f32> = order.iter().map(|&(_, i)| p.y[i]).collect();
+        let radius: Vec<f32> = order.iter().map(|&(_, i)| p.radius[i]).collect();
+        let z_codes: Vec<u32> = order.iter().map(|&(z, _)| z).collect();
+        let scene = Scene {
+            x: &x,
+            y: &y,
+            radius: &radius,
+            z_codes: &z_codes,
+            width: self.width,
+            height: self.height,
+            max_radius: radius.iter().fold(0.0f32, |max, &r| max.max(r)),
+            search_range: self.search_range.max(0) as usize,
+            simd: self.simd,
+        };
+
+        let mut found = Vec::new();
+        self.broadphase.find_pairs(&scene, &mut found);
+        let mut expected = Vec::new();
+        broadphase::BruteForce.find_pairs(&scene, &mut expected);
+
+        found.sort_unstable();
+        expected.iter().filter(|pair| found.binary_search(pair).is_err()).count()
+    }
+
+    /// Serialises the world size, every constructor parameter and all
+    /// particle state into a compact versioned binary format (see
+    /// `snapshot.rs`). `restore` reproduces the run exactly from there.
+    pub fn snapshot(&self) -> Vec<u8> {
+        snapshot::write(self)
+    }
+
+    /// Replaces the whole simulation with a `snapshot`. On error nothing is
+    /// changed. Sort, broadphase, thread and SIMD choices are kept, since
+    /// they never affect the result.
+    pub fn restore(&mut self, bytes: &[u8]) -> Result<(), SnapshotError> {
+        snapshot::read(self, bytes)?;
+        self.history.clear();
+        Ok(())
+    }
+
+    /// How many past steps `step_back` and `rewind_to` can undo. 0, the
+    /// default, turns recording off; otherwise every `step` first snapshots
+    /// the state, and the history holds one full snapshot, a keyframe
+    /// every 30 frames and a delta of the changed bytes for the rest.
+    pub fn set_history_length(&mut self, frames: usize) {
+        self.history.set_length(frames);
+    }
+
+    pub fn get_history_length(&self) -> usize {
+        self.history.length()
+    }
+
+    /// Earliest frame `rewind_to` can reach.
+    pub fn get_history_start(&self) -> u64 {
+        self.history.start().unwrap_or(self.frame)
+    }
+
+    /// Memory held by the history.
+    pub fn get_history_bytes(&self) -> usize {
+        self.history.byte_size()
+    }
+
+    /// Undoes the last step, restoring the exact state it started from.
+    /// Stepping again replays it identically. Returns false when the
+    /// history is empty.
+    pub fn step_back(&mut self) -> bool {
+        let frame = self.frame;
+        self.restore_from_history(|history| history.step_back(frame))
+    }
+
+    /// Undoes steps until `get_frame` is `frame`, or as far as the history
+    /// goes. Returns false when nothing was undone. The undone steps are
+    /// forgotten; step forward again to recompute them.
+    pub fn rewind_to(&mut self, frame: u64) -> bool {
+        let current = self.frame;
+        self.restore_from_history(|history| history.rewind(current, frame))
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Replaces the seed and rewinds the generator. Existing particles are
+    /// left alone; call `reset` to respawn them from the new seed.
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = Rng::new(seed);
+    }
+
+    fn refresh_index(&mut self) {
+        self.index.refresh(&self.particles, &self.order, self.width, self.height);
+    }
+
+    fn restore_from_history(&mut self, seek: impl FnOnce(&mut History) -> bool) -> bool {
+        let mut history = std::mem::take(&mut self.history);
+        let moved = seek(&mut history);
+        if moved {
+            let (counters, bytes) = history.top();
+            snapshot::read(self, bytes).expect("history holds valid snapshots");
+            // Unlike a loaded file, a rewind stays on the same run.
+            self.collisions = counters.collisions;
+            self.energy_lost = counters.energy_lost;
+            self.total_energy_lost = counters.total_energy_lost;
         }
+        self.history = history;
+        moved
+    }
 
-        p.x += p.vx;
-        p.y += p.vy;
+    fn counters(&self) -> Counters {
+        Counters { collisions: self.collisions, energy_lost: self.energy_lost, total_energy_lost: self.total_energy_lost }
+    }
+
+    /// Fraction of a reference frame covered by one substep.
+    fn substep_scale(&self) -> f32 {
+        self.fixed_dt / REFERENCE_DT / self.substeps as f32
+    }
 
-        if p.x - p.radius < 0.0 || p.x + p.radius > self.width {
-            p.vx *= -self.elasticity;
-            p.x = p.x.max(p.radius).min(self.width - p.radius);
+    fn integrate(&mut self, slots: std::ops::Range<usize>, h: f32) {
+        let grabbed = self.grab.as_ref().and_then(|grab| self.get_slot(grab.id));
+        if let (Some(grab), Some(slot)) = (&self.grab, grabbed.filter(|slot| slots.contains(slot))) {
+            grab.pull(&mut self.particles, slot, h);
         }
-        if p.y - p.radius < 0.0 || p.y + p.radius > self.height {
-            p.vy *= -self.elasticity;
-            p.y = p.y.max(p.radius).min(self.height - p.radius);
+        let integrator = Integrator {
+            gravity: self.gravity,
+            elasticity: self.elasticity,
+            width: self.width,
+            height: self.height,
+            walls: self.boundary.walls(),
+            forces: &self.forces,
+            cooling_rate: 0.01,
+            h,
+        };
+        let start = clock::now_ms();
+        self.energy_lost += integrator.run(&mut self.particles, slots.clone(), self.simd);
+        self.energy_lost += self.boundary.apply(&mut self.particles, slots.clone(), self.width, self.height, self.elasticity);
+        let integrated = clock::now_ms();
+        self.stats.integrate_ms += integrated - start;
+
+        let p = &mut self.particles;
+        for i in slots {
+            p.z_code[i] = zorder::code_at(p.x[i], p.y[i], self.width, self.height);
         }
+        self.stats.z_code_ms += clock::now_ms() - integrated;
+    }
 
-        let grid_x = ((p.x / self.width) * 0xFFFF as f32) as u32 & 0xFFFF;
-        let grid_y = ((p.y / self.height) * 0xFFFF as f32) as u32 & 0xFFFF;
-        p.z_code = ZOrder::encode(grid_x, grid_y);
+    fn sort_particles(&mut self) {
+        let start = clock::now_ms();
+        // Slots added since the last sort go on the end; the sort places them.
+        let sorted = self.order.len() as u32;
+        self.order.extend(sorted..self.particles.len() as u32);
+        let keys = &self.particles.z_code;
+        match self.sort_algorithm {
+            SortAlgorithm::Radix => self.sorter.sort_indices(&mut self.order, keys),
+            SortAlgorithm::Comparison => self.order.sort_by_key(|&slot| keys[slot as usize]),
+        }
+        let elapsed = clock::now_ms() - start;
+        self.sort_time_ms += elapsed;
+        self.stats.sort_ms += elapsed;
     }
-    
+
     fn detect_collisions(&mut self) {
-        self.particles.sort_by(|a, b| a.z_code.cmp(&b.z_code));
+        self.sort_particles();
+        self.index.invalidate();
+        let start = clock::now_ms();
+        self.sorted.gather(&self.particles, &self.order);
 
-        for p in self.particles.iter_mut() {
-            p.colliding = false;
-            p.impulse_x = 0.0;
-            p.impulse_y = 0.0;
+        let scene = self.sorted.scene(self.width, self.height, self.search_range.max(0) as usize, self.simd);
+        self.broadphase.build(&scene);
+        let periodic = self.boundary.mode() == BoundaryMode::Periodic;
+        if periodic {
+            self.seams.build(&scene);
         }
-
-        let avg_size = (self.min_size + self.max_size) / 2.0;
-        let search_dist = self.search_range as f32 * avg_size * 2.0;
-        let z_threshold = ((search_dist * search_dist) / (self.width * self.height) * 0xFFFFFFFF_u32 as f32) as u32;
-
-        for i in 0..self.particles.len() {
-            for j in i + 1..self.particles.len() {
-                // Unsafe block to get two mutable references to particles
-                unsafe {
-                    let p1_ptr = &mut self.particles[i] as *mut Particle;
-                    let p2_ptr = &mut self.particles[j] as *mut Particle;
-                    let p1 = &mut *p1_ptr;
-                    let p2 = &mut *p2_ptr;
-
-                    let z_diff = p2.z_code - p1.z_code;
-                    if z_diff > z_threshold {
-                        break;
-                    }
-
-                    let dx = p1.x - p2.x;
-                    let dy = p1.y - p2.y;
-                    let dist_sq = dx * dx + dy * dy;
-                    let radius_sum = p1.radius + p2.radius;
-
-                    if dist_sq < radius_sum * radius_sum {
-                        p1.colliding = true;
-                        p2.colliding = true;
-                        p1.color_temp = 1.0;
-                        p2.color_temp = 1.0;
-
-                        let dist = dist_sq.sqrt();
-                        if dist == 0.0 { continue; }
-
-                        let nx = dx / dist;
-                        let ny = dy / dist;
-
-                        let dvx = p1.vx - p2.vx;
-                        let dvy = p1.vy - p2.vy;
-                        
-                        let dvn = dvx * nx + dvy * ny;
-
-                        if dvn <= 0.0 {
-                            let impulse_val = -(1.0 + self.elasticity) * dvn / (1.0 / p1.mass + 1.0 / p2.mass);
-
-                            p1.impulse_x += (impulse_val * nx) / p1.mass;
-                            p1.impulse_y += (impulse_val * ny) / p1.mass;
-                            p2.impulse_x -= (impulse_val * nx) / p2.mass;
-                            p2.impulse_y -= (impulse_val * ny) / p2.mass;
-
-                            let overlap = (p1.radius + p2.radius) - dist;
-                            if overlap > 0.0 {
-                                let total_mass = p1.mass + p2.mass;
-                                let p1_factor = p2.mass / total_mass;
-                                let p2_factor = p1.mass / total_mass;
-                                let separation = overlap + 0.1;
-
-                                p1.x += nx * separation * p1_factor;
-                                p1.y += ny * separation * p1_factor;
-                                p2.x -= nx * separation * p2_factor;
-                                p2.y -= ny * separation * p2_factor;
-                            }
-                        }
-                    }
-                }
+        // Each thread queries from a contiguous run of Z-order ranks, reading
+        // neighbours on either side of it; every pair is found from its lower
+        // rank, so each turns up in exactly one chunk. The same goes for the
+        // pairs across a periodic seam.
+        let (broadphase, seams) = (&*self.broadphase, &self.seams);
+        self.chunk_pairs.resize_with(self.threads, Default::default);
+        threads::run_chunks(scene.len(), &mut self.chunk_pairs, |range, chunk| {
+            chunk.pairs.clear();
+            chunk.seam_pairs.clear();
+            chunk.tested = broadphase.query(&scene, range.clone(), &mut chunk.pairs);
+            if periodic {
+                chunk.tested += seams.query(&scene, range, &mut chunk.seam_pairs);
             }
+        });
+        self.pairs.clear();
+        self.seam_pairs.clear();
+        for chunk in &self.chunk_pairs {
+            self.pairs.extend_from_slice(&chunk.pairs);
+            self.seam_pairs.extend_from_slice(&chunk.seam_pairs);
+            self.stats.candidate_pairs += chunk.tested as u64;
+        }
+        self.stats.contacts += (self.pairs.len() + self.seam_pairs.len()) as u64;
+        let searched = clock::now_ms();
+        self.stats.broadphase_ms += searched - start;
+
+        // Resolve in a canonical order so every broadphase gives the same
+        // result. Pairs across a periodic seam go after the rest; in a world
+        // under three diameters across, two particles can meet across more
+        // than one seam, and the stable sort keeps those in the order found.
+        self.pairs.sort_unstable();
+        self.seam_pairs.sort_by_key(|&(i, j, _, _)| (i, j));
+        let events = self.narrow.resolve(self.sorted.run(), &self.pairs, &self.seam_pairs, self.elasticity, self.threads);
+        self.collisions += events.len() as u32;
+        for &event in events {
+            let id = |rank: u32| self.particles.id[self.order[rank as usize] as usize];
+            self.events.push(CollisionEvent { a: id(event.a), b: id(event.b), ..event });
         }
+        self.sorted.scatter(&mut self.particles, &self.order);
 
-        for p in self.particles.iter_mut() {
-            p.vx += p.impulse_x;
-            p.vy += p.impulse_y;
+        let p = &mut self.particles;
+        let mut lost = 0.0;
+        for i in 0..p.len() {
+            let before = p.vx[i] * p.vx[i] + p.vy[i] * p.vy[i];
+            p.vx[i] += p.impulse_x[i];
+            p.vy[i] += p.impulse_y[i];
+            let after = p.vx[i] * p.vx[i] + p.vy[i] * p.vy[i];
+            lost += (0.5 * p.mass[i] * (before - after)) as f64;
         }
+        self.energy_lost += lost;
+        self.stats.narrow_phase_ms += clock::now_ms() - searched;
     }
 
+    /// With open boundaries, removes the particles that have left the box.
+    fn remove_escaped(&mut self) {
+        if self.boundary.mode() != BoundaryMode::Open {
+            return;
+        }
+        let p = &self.particles;
+        let (width, height) = (self.width, self.height);
+        let keep: Vec<bool> = (0..p.len()).map(|i| !Boundary::escaped(p.x[i], p.y[i], p.radius[i], width, height)).collect();
+        if keep.contains(&false) {
+            self.retain(&keep);
+        }
+    }
+
+    fn collide_obstacles(&mut self) {
+        if self.obstacles.obstacles().is_empty() && self.bodies.bodies().is_empty() {
+            return;
+        }
+        let start = clock::now_ms();
+        self.obstacles.prepare((0.0, 0.0, self.width, self.height));
+        self.bodies.prepare();
+        // Each particle meets the obstacles and then the bodies on its own,
+        // so the slots split into chunks like the broadphase's ranks. The
+        // results go back in slot order, whichever thread found them.
+        let (obstacles, bodies, p, elasticity) = (&self.obstacles, &self.bodies, &self.particles, self.elasticity);
+        self.chunk_contacts.resize_with(self.threads, Default::default);
+        threads::run_chunks(p.len(), &mut self.chunk_contacts, |range, chunk| {
+            chunk.moved.clear();
+            chunk.events.clear();
+            for i in range {
+                let mut m = Mover::new(p, i);
+                let mut lost = obstacles.collide(&mut m, elasticity, &mut chunk.candidates, &mut chunk.events);
+                lost += bodies.collide(&mut m, elasticity, &mut chunk.candidates, &mut chunk.events);
+                if m.touched {
+                    chunk.moved.push((i as u32, m, lost));
+                }
+            }
+        });
+        let p = &mut self.particles;
+        for chunk in &self.chunk_contacts {
+            for &(i, m, lost) in &chunk.moved {
+                let i = i as usize;
+                (p.x[i], p.y[i], p.vx[i], p.vy[i]) = (m.x, m.y, m.vx, m.vy);
+                self.energy_lost += lost;
+            }
+            for &event in &chunk.events {
+                self.events.push(event);
+            }
+        }
+        self.stats.narrow_phase_ms += clock::now_ms() - start;
+    }
+
+    /// Sets how many particles `reset` spawns. Below the current count, the
+    /// newest particles are removed straight away.
     pub fn set_particle_count(&mut self, count: usize) {
         self.particle_count = count;
+        if count < self.particles.len() {
+            // Removal keeps slot order, and spawns append, so slots run
+            // oldest to newest.
+            let keep: Vec<bool> = (0..self.particles.len()).map(|slot| slot < count).collect();
+            self.retain(&keep);
+        }
     }
 
     pub fn set_min_size(&mut self, size: f32) {
@@ -263,17 +917,332 @@ impl Simulation {
         self.elasticity = elasticity;
     }
 
+    /// Sets the strength of the fan every simulation starts with (emitter
+    /// 0). Does nothing to the emitters if that fan has been removed.
     pub fn set_fan_speed(&mut self, speed: f32) {
         self.fan_speed = speed;
+        if let Some(fan) = self.forces.get(DEFAULT_FAN) {
+            self.forces.set(DEFAULT_FAN, Emitter { strength: forces::fan_strength(speed), ..fan });
+        }
+    }
+
+    /// Adds a force emitter and returns its id.
+    pub fn add_emitter(&mut self, emitter: &Emitter) -> u32 {
+        self.forces.add(*emitter)
+    }
+
+    pub fn get_emitter(&self, id: u32) -> Option<Emitter> {
+        self.forces.get(id)
+    }
+
+    /// Moves, reshapes or retunes emitter `id`. Returns false if there is no
+    /// such emitter.
+    pub fn set_emitter(&mut self, id: u32, emitter: &Emitter) -> bool {
+        self.forces.set(id, *emitter)
+    }
+
+    pub fn remove_emitter(&mut self, id: u32) -> bool {
+        self.forces.remove(id)
+    }
+
+    /// Ids of every emitter, in the order their forces are applied.
+    pub fn get_emitter_ids(&self) -> Vec<u32> {
+        self.forces.emitters().iter().map(|&(id, _)| id).collect()
+    }
+
+    /// Removes every emitter, including the default fan.
+    pub fn clear_emitters(&mut self) {
+        self.forces.clear();
+    }
+
+    /// Adds an open chain of line segments through `points`, given as flat
+    /// (x, y) pairs, and returns its id. Returns `undefined` for fewer than
+    /// two points.
+    pub fn add_segments(&mut self, points: &[f32]) -> Option<u32> {
+        self.add_obstacle(ObstacleKind::Segments, points)
+    }
+
+    /// Adds a solid polygon outlined by `points`, given as flat (x, y)
+    /// pairs, and returns its id. It may be concave but shouldn't cross
+    /// itself. Returns `undefined` for fewer than three points.
+    pub fn add_polygon(&mut self, points: &[f32]) -> Option<u32> {
+        self.add_obstacle(ObstacleKind::Polygon, points)
+    }
+
+    /// Adds a solid circle and returns its id. Returns `undefined` unless
+    /// `radius` is positive.
+    pub fn add_circle(&mut self, x: f32, y: f32, radius: f32) -> Option<u32> {
+        self.add_obstacle(ObstacleKind::Circle, &[x, y, radius])
+    }
+
+    pub fn get_obstacle_kind(&self, id: u32) -> Option<ObstacleKind> {
+        self.obstacles.get(id).map(|obstacle| obstacle.kind)
+    }
+
+    /// The points obstacle `id` was added with: flat (x, y) pairs, or x, y
+    /// and radius for a circle. Empty if there is no such obstacle.
+    pub fn get_obstacle_points(&self, id: u32) -> Vec<f32> {
+        self.obstacles.get(id).map(|obstacle| obstacle.points.clone()).unwrap_or_default()
+    }
+
+    pub fn remove_obstacle(&mut self, id: u32) -> bool {
+        self.obstacles.remove(id)
+    }
+
+    /// Ids of every obstacle, oldest first.
+    pub fn get_obstacle_ids(&self) -> Vec<u32> {
+        self.obstacles.obstacles().iter().map(|&(id, _)| id).collect()
+    }
+
+    pub fn clear_obstacles(&mut self) {
+        self.obstacles.clear();
+    }
+
+    /// Adds a kinematic body at (x, y), standing still with no shapes yet,
+    /// and returns its id. Give it shapes with `add_body_segments`,
+    /// `add_body_polygon` and `add_body_circle`, and motion with
+    /// `move_body`, `set_body_oscillation` or `set_body_rotation`.
+    pub fn add_body(&mut self, x: f32, y: f32) -> u32 {
+        let state = BodyState { x, y, ..BodyState::default() };
+        self.bodies.add(Body::new(state, Motion::Scripted { target: None }, Vec::new()).expect("scripted bodies are valid"))
+    }
+
+    /// Adds an open chain of segments to body `id`, through `points` given
+    /// as flat (x, y) pairs relative to the body's position and angle.
+    /// Returns false for fewer than two points or no such body.
+    pub fn add_body_segments(&mut self, id: u32, points: &[f32]) -> bool {
+        self.add_body_shape(id, ObstacleKind::Segments, points)
+    }
+
+    /// Adds a solid polygon to body `id`, outlined by `points` given as flat
+    /// (x, y) pairs relative to the body's position and angle. Returns false
+    /// for fewer than three points or no such body.
+    pub fn add_body_polygon(&mut self, id: u32, points: &[f32]) -> bool {
+        self.add_body_shape(id, ObstacleKind::Polygon, points)
+    }
+
+    /// Adds a solid circle centred at (x, y) relative to body `id`'s
+    /// position and angle. Returns false unless `radius` is positive and the
+    /// body exists.
+    pub fn add_body_circle(&mut self, id: u32, x: f32, y: f32, radius: f32) -> bool {
+        self.add_body_shape(id, ObstacleKind::Circle, &[x, y, radius])
+    }
+
+    /// Sends body `id` to (x, y), turned to `angle` radians clockwise, over
+    /// the next step, and stops any built-in motion. Call it every frame to
+    /// script a path, such as a paddle following the mouse; particles it
+    /// hits pick up its velocity along the way. Returns false if there is
+    /// no such body.
+    pub fn move_body(&mut self, id: u32, x: f32, y: f32, angle: f32) -> bool {
+        let Some(body) = self.bodies.get_mut(id) else {
+            return false;
+        };
+        body.move_to(x, y, angle);
+        true
+    }
+
+    /// Swings body `id` back and forth by (amplitude_x, amplitude_y) either
+    /// side of where it is, once every `period` reference frames, like a
+    /// piston. Returns false unless `period` is positive and the body
+    /// exists.
+    pub fn set_body_oscillation(&mut self, id: u32, amplitude_x: f32, amplitude_y: f32, period: f32) -> bool {
+        self.bodies.get_mut(id).is_some_and(|body| body.oscillate(amplitude_x, amplitude_y, period))
+    }
+
+    /// Spins body `id` about its position at `speed` radians per reference
+    /// frame, clockwise on screen for a positive speed, like a drum.
+    /// Returns false if there is no such body.
+    pub fn set_body_rotation(&mut self, id: u32, speed: f32) -> bool {
+        let Some(body) = self.bodies.get_mut(id) else {
+            return false;
+        };
+        body.rotate(speed);
+        true
+    }
+
+    pub fn get_body_motion(&self, id: u32) -> Option<BodyMotion> {
+        self.bodies.get(id).map(|body| body.motion.kind())
+    }
+
+    /// Body `id`'s pose and velocity.
+    pub fn get_body(&self, id: u32) -> Option<BodyState> {
+        self.bodies.get(id).map(|body| body.state)
+    }
+
+    /// How many shapes body `id` has; 0 if there is no such body.
+    pub fn get_body_shape_count(&self, id: u32) -> usize {
+        self.bodies.get(id).map_or(0, |body| body.shapes().count())
+    }
+
+    pub fn get_body_shape_kind(&self, id: u32, shape: usize) -> Option<ObstacleKind> {
+        self.bodies.get(id).and_then(|body| body.world_shape(shape)).map(|(kind, _)| kind)
+    }
+
+    /// Shape `shape` of body `id` where it stands now, for drawing: flat
+    /// (x, y) pairs, or x, y and radius for a circle. Empty if there is no
+    /// such shape.
+    pub fn get_body_shape_points(&self, id: u32, shape: usize) -> Vec<f32> {
+        self.bodies.get(id).and_then(|body| body.world_shape(shape)).map(|(_, points)| points).unwrap_or_default()
+    }
+
+    pub fn remove_body(&mut self, id: u32) -> bool {
+        self.bodies.remove(id)
+    }
+
+    /// Ids of every body, oldest first.
+    pub fn get_body_ids(&self) -> Vec<u32> {
+        self.bodies.bodies().iter().map(|&(id, _)| id).collect()
+    }
+
+    pub fn clear_bodies(&mut self) {
+        self.bodies.clear();
+    }
+
+    /// Sets what happens at the edge of the world. Returns false, changing
+    /// nothing, for `Polygon` before any `set_boundary_polygon`.
+    pub fn set_boundary(&mut self, mode: BoundaryMode) -> bool {
+        self.boundary.set_mode(mode)
+    }
+
+    pub fn get_boundary(&self) -> BoundaryMode {
+        self.boundary.mode()
+    }
+
+    /// Switches to a polygon container outlined by `points`, given as flat
+    /// (x, y) pairs. Returns false, changing nothing, for fewer than three
+    /// points.
+    pub fn set_boundary_polygon(&mut self, points: &[f32]) -> bool {
+        let Some(polygon) = Obstacle::new(ObstacleKind::Polygon, points) else {
+            return false;
+        };
+        self.boundary.set_polygon(polygon);
+        true
+    }
+
+    /// The container's shape for drawing: the polygon's points as flat
+    /// (x, y) pairs, the circle's centre and radius, or nothing for the
+    /// other modes.
+    pub fn get_boundary_points(&self) -> Vec<f32> {
+        match self.boundary.mode() {
+            BoundaryMode::Circle => {
+                let (x, y, radius) = Boundary::circle(self.width, self.height);
+                vec![x, y, radius]
+            }
+            BoundaryMode::Polygon => self.boundary.polygon().map(|polygon| polygon.points.clone()).unwrap_or_default(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Moves the pointer to (x, y) and sets what it does there. Its force
+    /// covers `get_pointer_radius` and acts after every emitter's.
+    pub fn set_pointer(&mut self, x: f32, y: f32, mode: PointerMode) {
+        (self.pointer.x, self.pointer.y, self.pointer.mode) = (x, y, mode);
+        self.forces.set_pointer(self.pointer.emitter());
+    }
+
+    pub fn get_pointer_mode(&self) -> PointerMode {
+        self.pointer.mode
+    }
+
+    pub fn set_pointer_radius(&mut self, radius: f32) {
+        self.pointer.radius = radius;
+        self.forces.set_pointer(self.pointer.emitter());
+    }
+
+    pub fn get_pointer_radius(&self) -> f32 {
+        self.pointer.radius
+    }
+
+    /// Peak acceleration of the pointer's force, as for an emitter.
+    pub fn set_pointer_strength(&mut self, strength: f32) {
+        self.pointer.strength = strength;
+        self.forces.set_pointer(self.pointer.emitter());
+    }
+
+    pub fn get_pointer_strength(&self) -> f32 {
+        self.pointer.strength
+    }
+
+    /// Holds particle `id` where it is with a stiff, critically damped
+    /// spring, letting go of any other. Returns false if there is no such
+    /// particle.
+    pub fn grab(&mut self, id: u32) -> bool {
+        let Some(slot) = self.get_slot(id) else {
+            return false;
+        };
+        let (x, y) = (self.particles.x[slot], self.particles.y[slot]);
+        self.grab = Some(Grab { id, x, y });
+        true
     }
 
+    /// Id of the grabbed particle, or `undefined`.
+    pub fn get_grabbed(&self) -> Option<u32> {
+        self.grab.as_ref().map(|grab| grab.id).filter(|&id| self.get_slot(id).is_some())
+    }
+
+    /// Moves the grabbed particle's spring anchor to (x, y).
+    pub fn drag_to(&mut self, x: f32, y: f32) {
+        if let Some(grab) = &mut self.grab {
+            (grab.x, grab.y) = (x, y);
+        }
+    }
+
+    /// Lets go of the grabbed particle, flinging it at (vx, vy) pixels per
+    /// reference frame. Returns false if nothing was held.
+    pub fn release(&mut self, vx: f32, vy: f32) -> bool {
+        let Some(slot) = self.grab.take().and_then(|grab| self.get_slot(grab.id)) else {
+            return false;
+        };
+        self.particles.vx[slot] = vx;
+        self.particles.vy[slot] = vy;
+        true
+    }
+
+    /// How many consecutive non-neighbours the Z-order broadphase steps over before
+    /// jumping ahead with BIGMIN. Only affects speed, never which pairs are found.
     pub fn set_search_range(&mut self, range: i32) {
         self.search_range = range;
     }
 
+    pub fn get_width(&self) -> f32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> f32 {
+        self.height
+    }
+
+    pub fn get_min_size(&self) -> f32 {
+        self.min_size
+    }
+
+    pub fn get_max_size(&self) -> f32 {
+        self.max_size
+    }
+
+    pub fn get_max_speed(&self) -> f32 {
+        self.max_speed
+    }
+
+    pub fn get_gravity(&self) -> f32 {
+        self.gravity
+    }
+
+    pub fn get_elasticity(&self) -> f32 {
+        self.elasticity
+    }
+
+    pub fn get_fan_speed(&self) -> f32 {
+        self.fan_speed
+    }
+
+    pub fn get_search_range(&self) -> i32 {
+        self.search_range
+    }
+
     pub fn add_particle(&mut self, x: f32, y: f32) {
-        let radius = self.min_size + (Math::random() as f32) * (self.max_size - self.min_size);
-        let mut p = Particle {
+        let radius = self.min_size + self.rng.next_f32() * (self.max_size - self.min_size);
+        let p = Particle {
             x,
             y,
             radius,
@@ -284,9 +1253,152 @@ impl Simulation {
             z_code: 0,
             impulse_x: 0.0,
             impulse_y: 0.0,
-            color_temp: Math::random() as f32,
+            color_temp: self.rng.next_f32(),
+            prev_x: 0.0,
+            prev_y: 0.0,
+            id: 0,
+        };
+        self.spawn(p, self.substep_scale());
+    }
+
+    /// Removes particle `id`. Returns false if there is no such particle.
+    pub fn remove_particle(&mut self, id: u32) -> bool {
+        self.remove_ids(&[id]) == 1
+    }
+
+    /// Removes every particle touching the circle of radius `r` around
+    /// (x, y) and returns their ids, ascending.
+    pub fn remove_in_radius(&mut self, x: f32, y: f32, r: f32) -> Vec<u32> {
+        let ids = self.query_radius(x, y, r);
+        self.remove_ids(&ids);
+        ids
+    }
+
+    /// Removes every particle. Unlike `reset`, nothing is respawned, ids keep
+    /// counting up and the count `reset` spawns is left alone.
+    pub fn clear(&mut self) {
+        self.retain(&vec![false; self.particles.len()]);
+    }
+
+    fn remove_ids(&mut self, ids: &[u32]) -> usize {
+        let mut keep = vec![true; self.particles.len()];
+        let mut removed = 0;
+        for &id in ids {
+            if let Some(slot) = self.get_slot(id) {
+                removed += keep[slot] as usize;
+                keep[slot] = false;
+            }
+        }
+        if removed > 0 {
+            self.retain(&keep);
+        }
+        removed
+    }
+
+    /// Drops the particles not marked in `keep`. The rest move down to fill
+    /// the gaps in slot order, so `order` stays sorted and only needs its
+    /// slots renumbered. Ids keep their order too, so they stay ascending.
+    fn retain(&mut self, keep: &[bool]) {
+        // Each kept slot moves down past the dropped ones before it.
+        let mut new_slots = Vec::with_capacity(keep.len());
+        let mut next = 0;
+        for &kept in keep {
+            new_slots.push(next);
+            next += kept as u32;
+        }
+        self.order.retain(|&slot| keep[slot as usize]);
+        for slot in &mut self.order {
+            *slot = new_slots[*slot as usize];
+        }
+        self.particles.retain(keep);
+        self.index.invalidate();
+    }
+
+    fn add_obstacle(&mut self, kind: ObstacleKind, points: &[f32]) -> Option<u32> {
+        Obstacle::new(kind, points).map(|obstacle| self.obstacles.add(obstacle))
+    }
+
+    fn add_body_shape(&mut self, id: u32, kind: ObstacleKind, points: &[f32]) -> bool {
+        let (Some(body), Some(shape)) = (self.bodies.get_mut(id), Obstacle::new(kind, points)) else {
+            return false;
         };
-        self.update_particle(&mut p);
-        self.particles.push(p);
+        body.add_shape(shape);
+        true
+    }
+
+    /// Appends `p` and runs it through one integration substep, which settles
+    /// it inside the walls and computes its Z-order code.
+    fn spawn(&mut self, p: Particle, h: f32) {
+        let buffers = self.buffer_addresses();
+        let slot = self.particles.len();
+        self.particles.push(Particle { id: self.next_id, ..p });
+        self.next_id += 1;
+        self.index.invalidate();
+        self.reserve_order();
+        self.note_moved_buffers(buffers);
+
+        self.integrate(slot..slot + 1, h);
+        self.particles.prev_x[slot] = self.particles.x[slot];
+        self.particles.prev_y[slot] = self.particles.y[slot];
+    }
+
+    /// Pre-allocates room for `max_particles` so that `reset` and
+    /// `add_particle` up to that count never move the particle buffer.
+    pub fn reserve(&mut self, max_particles: usize) {
+        let buffers = self.buffer_addresses();
+        self.particles.reserve_exact(max_particles.saturating_sub(self.particles.len()));
+        self.reserve_order();
+        self.export.reserve_exact(max_particles.saturating_sub(self.export.len()));
+        self.positions.reserve_exact((max_particles * 2).saturating_sub(self.positions.len()));
+        self.note_moved_buffers(buffers);
+    }
+
+    pub fn get_capacity(&self) -> usize {
+        self.particles.capacity()
+    }
+
+    /// Where every buffer behind a `get_*_ptr` that stays valid until the
+    /// generation changes starts.
+    pub(crate) fn buffer_addresses(&self) -> [usize; 16] {
+        let mut addresses = [0; 16];
+        addresses[..14].copy_from_slice(&self.particles.addresses());
+        addresses[14] = self.order.as_ptr() as usize;
+        addresses[15] = self.export.as_ptr() as usize;
+        addresses
+    }
+
+    /// Bumps the generation if any buffer has moved since `before`.
+    pub(crate) fn note_moved_buffers(&mut self, before: [usize; 16]) {
+        if self.buffer_addresses() != before {
+            self.buffer_generation += 1;
+        }
+    }
+
+    /// Gives `order` room for every particle the arrays can hold, so the
+    /// sort appending new slots never moves it.
+    pub(crate) fn reserve_order(&mut self) {
+        let capacity = self.particles.capacity();
+        if self.order.capacity() < capacity {
+            self.order.reserve_exact(capacity - self.order.len());
+        }
+    }
+
+    /// Incremented whenever the particle arrays or the `get_particles_ptr`
+    /// buffer are reallocated. Views over them must be rebuilt when this
+    /// changes, and also when WASM memory grows (`memory.buffer` is replaced).
+    pub fn get_buffer_generation(&self) -> u32 {
+        self.buffer_generation
+    }
+}
+
+impl Simulation {
+    /// Every particle, in slot order.
+    pub fn particles(&self) -> Vec<Particle> {
+        (0..self.particles.len()).map(|i| self.particles.get(i)).collect()
     }
-}
\ No newline at end of file
+
+    /// The events behind `get_events_ptr`.
+    pub fn collision_events(&self) -> &[CollisionEvent] {
+        self.events.events()
+    }
+}
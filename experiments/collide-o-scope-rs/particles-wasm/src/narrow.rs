@@ -0,0 +1,206 @@
+//! Collision response, on the particles gathered into Z-order ranks. The
+//! ranks are cut into fixed blocks of `BLOCK`. A pair with both ends in one
+//! block only touches that block, so the blocks resolve their own pairs in
+//! parallel; the pairs that reach across a block boundary (the halo) and
+//! across a periodic seam follow one at a time. Blocks never depend on the
+//! thread count, so neither does the result.
+
+use crate::broadphase::SeamPair;
+use crate::threads;
+use crate::CollisionEvent;
+
+/// Ranks per block. Big enough that most pairs stay inside one.
+const BLOCK: usize = 512;
+
+/// The attributes collision response reads and writes, for ranks
+/// `start..start + x.len()`.
+pub(crate) struct Run<'a> {
+    pub start: usize,
+    pub x: &'a mut [f32],
+    pub y: &'a mut [f32],
+    pub vx: &'a [f32],
+    pub vy: &'a [f32],
+    pub radius: &'a [f32],
+    pub mass: &'a [f32],
+    pub temp: &'a mut [f32],
+    pub impulse_x: &'a mut [f32],
+    pub impulse_y: &'a mut [f32],
+    pub colliding: &'a mut [bool],
+}
+
+impl<'a> Run<'a> {
+    /// The same ranks, borrowed for a shorter while.
+    fn reborrow(&mut self) -> Run<'_> {
+        Run {
+            start: self.start,
+            x: self.x,
+            y: self.y,
+            vx: self.vx,
+            vy: self.vy,
+            radius: self.radius,
+            mass: self.mass,
+            temp: self.temp,
+            impulse_x: self.impulse_x,
+            impulse_y: self.impulse_y,
+            colliding: self.colliding,
+        }
+    }
+
+    /// The first `mid` ranks and the rest.
+    fn split_at(self, mid: usize) -> (Run<'a>, Run<'a>) {
+        let (x, x_rest) = self.x.split_at_mut(mid);
+        let (y, y_rest) = self.y.split_at_mut(mid);
+        let (vx, vx_rest) = self.vx.split_at(mid);
+        let (vy, vy_rest) = self.vy.split_at(mid);
+        let (radius, radius_rest) = self.radius.split_at(mid);
+        let (mass, mass_rest) = self.mass.split_at(mid);
+        let (temp, temp_rest) = self.temp.split_at_mut(mid);
+        let (impulse_x, impulse_x_rest) = self.impulse_x.split_at_mut(mid);
+        let (impulse_y, impulse_y_rest) = self.impulse_y.split_at_mut(mid);
+        let (colliding, colliding_rest) = self.colliding.split_at_mut(mid);
+        let head = Run { start: self.start, x, y, vx, vy, radius, mass, temp, impulse_x, impulse_y, colliding };
+        let rest = Run {
+            start: self.start + mid,
+            x: x_rest,
+            y: y_rest,
+            vx: vx_rest,
+            vy: vy_rest,
+            radius: radius_rest,
+            mass: mass_rest,
+            temp: temp_rest,
+            impulse_x: impulse_x_rest,
+            impulse_y: impulse_y_rest,
+            colliding: colliding_rest,
+        };
+        (head, rest)
+    }
+
+    /// Resolves a collision between ranks `a` and `b`, with `b` seen shifted
+    /// by `offset`. Both must lie in the run. Returns the event, with ranks
+    /// for `a` and `b`, if they were approaching.
+    fn resolve(&mut self, a: usize, b: usize, (ox, oy): (f32, f32), elasticity: f32) -> Option<CollisionEvent> {
+        let event = CollisionEvent { a: a as u32, b: b as u32, x: 0.0, y: 0.0, nx: 0.0, ny: 0.0, impulse: 0.0 };
+        let (a, b) = (a - self.start, b - self.start);
+        let dx = self.x[a] - (self.x[b] + ox);
+        let dy = self.y[a] - (self.y[b] + oy);
+        let dist_sq = dx * dx + dy * dy;
+        let radius_sum = self.radius[a] + self.radius[b];
+
+        if dist_sq < radius_sum * radius_sum {
+            self.colliding[a] = true;
+            self.colliding[b] = true;
+            self.temp[a] = 1.0;
+            self.temp[b] = 1.0;
+
+            let dist = dist_sq.sqrt();
+            if dist == 0.0 { return None; }
+
+            let nx = dx / dist;
+            let ny = dy / dist;
+
+            let dvx = self.vx[a] - self.vx[b];
+            let dvy = self.vy[a] - self.vy[b];
+
+            let dvn = dvx * nx + dvy * ny;
+
+            if dvn <= 0.0 {
+                let (mass_a, mass_b) = (self.mass[a], self.mass[b]);
+                let impulse_val = -(1.0 + elasticity) * dvn / (1.0 / mass_a + 1.0 / mass_b);
+                let event = CollisionEvent {
+                    x: self.x[a] - nx * self.radius[a],
+                    y: self.y[a] - ny * self.radius[a],
+                    nx,
+                    ny,
+                    impulse: impulse_val,
+                    ..event
+                };
+
+                self.impulse_x[a] += (impulse_val * nx) / mass_a;
+                self.impulse_y[a] += (impulse_val * ny) / mass_a;
+                self.impulse_x[b] -= (impulse_val * nx) / mass_b;
+                self.impulse_y[b] -= (impulse_val * ny) / mass_b;
+
+                let overlap = radius_sum - dist;
+                if overlap > 0.0 {
+                    let total_mass = mass_a + mass_b;
+                    let a_factor = mass_b / total_mass;
+                    let b_factor = mass_a / total_mass;
+                    let separation = overlap + 0.1;
+
+                    self.x[a] += nx * separation * a_factor;
+                    self.y[a] += ny * separation * a_factor;
+                    self.x[b] -= nx * separation * b_factor;
+                    self.y[b] -= ny * separation * b_factor;
+                }
+                return Some(event);
+            }
+        }
+        None
+    }
+}
+
+#[derive(Default)]
+struct Block {
+    pairs: Vec<(u32, u32)>,
+    events: Vec<CollisionEvent>,
+}
+
+/// Scratch space for resolving, kept between passes.
+#[derive(Default)]
+pub(crate) struct NarrowPhase {
+    blocks: Vec<Block>,
+    halo: Vec<(u32, u32)>,
+    events: Vec<CollisionEvent>,
+}
+
+impl NarrowPhase {
+    /// Resolves `pairs` and then `seam_pairs`, both sorted by rank, using
+    /// up to `threads` threads. Returns the events, with ranks for `a` and
+    /// `b`, in the order they were resolved: block by block, then the halo,
+    /// then the seams.
+    pub fn resolve(&mut self, mut run: Run, pairs: &[(u32, u32)], seam_pairs: &[SeamPair], elasticity: f32, threads: usize) -> &[CollisionEvent] {
+        let blocks = run.x.len().div_ceil(BLOCK);
+        self.blocks.resize_with(blocks, Block::default);
+        for block in &mut self.blocks {
+            block.pairs.clear();
+        }
+        self.halo.clear();
+        for &(i, j) in pairs {
+            let block = i as usize / BLOCK;
+            if j as usize / BLOCK == block {
+                self.blocks[block].pairs.push((i, j));
+            } else {
+                self.halo.push((i, j));
+            }
+        }
+
+        {
+            let mut tasks = Vec::with_capacity(blocks);
+            let mut rest = run.reborrow();
+            for block in &mut self.blocks {
+                let mid = BLOCK.min(rest.x.len());
+                let (head, tail) = rest.split_at(mid);
+                tasks.push((head, block));
+                rest = tail;
+            }
+            threads::run_each(&mut tasks, threads, |_, (run, block)| {
+                block.events.clear();
+                for &(i, j) in &block.pairs {
+                    block.events.extend(run.resolve(i as usize, j as usize, (0.0, 0.0), elasticity));
+                }
+            });
+        }
+
+        self.events.clear();
+        for block in &self.blocks {
+            self.events.extend_from_slice(&block.events);
+        }
+        for &(i, j) in &self.halo {
+            self.events.extend(run.resolve(i as usize, j as usize, (0.0, 0.0), elasticity));
+        }
+        for &(i, j, ox, oy) in seam_pairs {
+            self.events.extend(run.resolve(i as usize, j as usize, (ox, oy), elasticity));
+        }
+        &self.events
+    }
+}
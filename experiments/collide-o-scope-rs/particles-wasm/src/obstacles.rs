@@ -0,0 +1,348 @@
+//! Static obstacles: chains of line segments, solid polygons and fixed
+//! circles. Particles bounce off them with the simulation's elasticity, as if
+//! off an immovable wall. Their edges and circles are binned into a uniform
+//! grid whenever the set changes, so each particle only tests the pieces
+//! near it. Kinematic bodies keep their shapes in one of these sets too,
+//! laid out in the body's own frame. Particles only read the grid, so the
+//! pass runs one particle at a time, split over threads like the
+//! broadphase.
+
+use wasm_bindgen::prelude::*;
+
+use crate::events::{CollisionEvent, NO_PARTICLE};
+use crate::grid::CellGrid;
+use crate::particles::Particles;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ObstacleKind {
+    /// An open chain of segments joining consecutive points.
+    Segments = 0,
+    /// A closed outline, convex or not, that particles stay out of.
+    Polygon = 1,
+    /// A solid circle. Its points are the centre and the radius.
+    Circle = 2,
+}
+
+impl ObstacleKind {
+    pub(crate) fn from_u8(kind: u8) -> Option<ObstacleKind> {
+        [ObstacleKind::Segments, ObstacleKind::Polygon, ObstacleKind::Circle].get(kind as usize).copied()
+    }
+}
+
+/// Side of the grid cells, in pixels.
+const CELL_SIZE: f32 = 32.0;
+
+/// One obstacle: its kind and its points as flat (x, y) pairs, or
+/// (x, y, radius) for a circle.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Obstacle {
+    pub kind: ObstacleKind,
+    pub points: Vec<f32>,
+}
+
+impl Obstacle {
+    /// The smallest box holding the shape, as (x0, y0, x1, y1).
+    pub fn bounds(&self) -> (f32, f32, f32, f32) {
+        let p = &self.points;
+        if self.kind == ObstacleKind::Circle {
+            return (p[0] - p[2], p[1] - p[2], p[0] + p[2], p[1] + p[2]);
+        }
+        p.chunks_exact(2).fold((f32::INFINITY, f32::INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY), |(x0, y0, x1, y1), q| {
+            (x0.min(q[0]), y0.min(q[1]), x1.max(q[0]), y1.max(q[1]))
+        })
+    }
+
+    /// Checks the shape: at least two points for segments, three for a
+    /// polygon, and a positive radius for a circle, all finite.
+    pub fn new(kind: ObstacleKind, points: &[f32]) -> Option<Obstacle> {
+        let pairs = points.len().is_multiple_of(2);
+        let valid = points.iter().all(|v| v.is_finite())
+            && match kind {
+                ObstacleKind::Segments => pairs && points.len() >= 4,
+                ObstacleKind::Polygon => pairs && points.len() >= 6,
+                ObstacleKind::Circle => points.len() == 3 && points[2] > 0.0,
+            };
+        valid.then(|| Obstacle { kind, points: points.to_vec() })
+    }
+
+    /// Whether (x, y) is inside the polygon, by the even-odd rule.
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        let p = &self.points;
+        let n = p.len() / 2;
+        let mut inside = false;
+        let mut j = n - 1;
+        for i in 0..n {
+            let (xi, yi, xj, yj) = (p[2 * i], p[2 * i + 1], p[2 * j], p[2 * j + 1]);
+            if (yi > y) != (yj > y) && x < xi + (y - yi) / (yj - yi) * (xj - xi) {
+                inside = !inside;
+            }
+            j = i;
+        }
+        inside
+    }
+}
+
+/// One particle on its way through the obstacles and bodies: where its
+/// centre was at the start of the step, and where it is and how fast it is
+/// going now.
+#[derive(Clone, Copy)]
+pub(crate) struct Mover {
+    pub id: u32,
+    pub from: (f32, f32),
+    pub x: f32,
+    pub y: f32,
+    pub vx: f32,
+    pub vy: f32,
+    pub radius: f32,
+    pub mass: f32,
+    /// Whether any contact has moved it.
+    pub touched: bool,
+}
+
+impl Mover {
+    pub fn new(p: &Particles, i: usize) -> Mover {
+        Mover {
+            id: p.id[i],
+            from: (p.prev_x[i], p.prev_y[i]),
+            x: p.x[i],
+            y: p.y[i],
+            vx: p.vx[i],
+            vy: p.vy[i],
+            radius: p.radius[i],
+            mass: p.mass[i],
+            touched: false,
+        }
+    }
+
+    /// Reflects the velocity, relative to a surface moving at `surface`,
+    /// along the outward unit normal (nx, ny) if the particle is heading
+    /// into it, scaled by `elasticity`. The contact at `point` goes in
+    /// `events` with `b` set to `NO_PARTICLE`. Returns the kinetic energy
+    /// taken out.
+    pub fn bounce(&mut self, nx: f32, ny: f32, surface: (f32, f32), point: (f32, f32), elasticity: f32, events: &mut Vec<CollisionEvent>) -> f64 {
+        let vn = (self.vx - surface.0) * nx + (self.vy - surface.1) * ny;
+        if vn >= 0.0 {
+            return 0.0;
+        }
+        let before = self.vx * self.vx + self.vy * self.vy;
+        self.vx -= (1.0 + elasticity) * vn * nx;
+        self.vy -= (1.0 + elasticity) * vn * ny;
+        let impulse = -self.mass * (1.0 + elasticity) * vn;
+        events.push(CollisionEvent { a: self.id, b: NO_PARTICLE, x: point.0, y: point.1, nx, ny, impulse });
+        (0.5 * self.mass * (before - (self.vx * self.vx + self.vy * self.vy))) as f64
+    }
+}
+
+/// An edge or circle of an obstacle, the unit the grid bins.
+#[derive(Clone, Copy)]
+enum Piece {
+    /// From (x0, y0) to (x1, y1). `polygon` is the index of the polygon it
+    /// bounds, if any, so a particle inside can be pushed back out.
+    Edge { x0: f32, y0: f32, x1: f32, y1: f32, polygon: Option<usize> },
+    Disc { x: f32, y: f32, r: f32 },
+}
+
+impl Piece {
+    fn bounds(&self) -> (f32, f32, f32, f32) {
+        match *self {
+            Piece::Edge { x0, y0, x1, y1, .. } => (x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1)),
+            Piece::Disc { x, y, r } => (x - r, y - r, x + r, y + r),
+        }
+    }
+}
+
+/// The obstacles, each with an id that stays valid while others are added
+/// and removed. Ids count up and are never reused.
+#[derive(Default)]
+pub(crate) struct Obstacles {
+    obstacles: Vec<(u32, Obstacle)>,
+    next_id: u32,
+    pieces: Vec<Piece>,
+    /// Area the grid was built over, as (x0, y0, x1, y1), or `None` if it
+    /// needs rebuilding.
+    built: Option<(f32, f32, f32, f32)>,
+    grid: CellGrid,
+}
+
+impl Obstacles {
+    /// Rebuilds the set from its saved parts; `obstacles` must be in id order
+    /// and every id below `next_id`.
+    pub fn from_parts(next_id: u32, obstacles: Vec<(u32, Obstacle)>) -> Option<Obstacles> {
+        let ordered = obstacles.windows(2).all(|pair| pair[0].0 < pair[1].0);
+        if !ordered || obstacles.last().is_some_and(|&(id, _)| id >= next_id) {
+            return None;
+        }
+        Some(Obstacles { obstacles, next_id, ..Obstacles::default() })
+    }
+
+    pub fn next_id(&self) -> u32 {
+        self.next_id
+    }
+
+    pub fn obstacles(&self) -> &[(u32, Obstacle)] {
+        &self.obstacles
+    }
+
+    pub fn add(&mut self, obstacle: Obstacle) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.obstacles.push((id, obstacle));
+        self.built = None;
+        id
+    }
+
+    pub fn get(&self, id: u32) -> Option<&Obstacle> {
+        self.position(id).map(|k| &self.obstacles[k].1)
+    }
+
+    pub fn remove(&mut self, id: u32) -> bool {
+        let Some(k) = self.position(id) else {
+            return false;
+        };
+        self.obstacles.remove(k);
+        self.built = None;
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.obstacles.clear();
+        self.built = None;
+    }
+
+    fn position(&self, id: u32) -> Option<usize> {
+        self.obstacles.binary_search_by_key(&id, |&(id, _)| id).ok()
+    }
+
+    /// Builds the grid over `bounds`, (x0, y0, x1, y1), unless it is
+    /// already up to date. `collide` and `resolve` need it.
+    pub fn prepare(&mut self, bounds: (f32, f32, f32, f32)) {
+        if self.built != Some(bounds) {
+            self.build(bounds);
+        }
+    }
+
+    /// Splits the obstacles into pieces and bins each into every cell its
+    /// bounding box touches. Pieces outside `bounds` go in the edge cells.
+    fn build(&mut self, bounds: (f32, f32, f32, f32)) {
+        self.pieces.clear();
+        for (k, (_, obstacle)) in self.obstacles.iter().enumerate() {
+            let p = &obstacle.points;
+            match obstacle.kind {
+                ObstacleKind::Circle => self.pieces.push(Piece::Disc { x: p[0], y: p[1], r: p[2] }),
+                ObstacleKind::Segments | ObstacleKind::Polygon => {
+                    let n = p.len() / 2;
+                    let (edges, polygon) = match obstacle.kind {
+                        ObstacleKind::Polygon => (n, Some(k)),
+                        _ => (n - 1, None),
+                    };
+                    for i in 0..edges {
+                        let j = (i + 1) % n;
+                        let (x0, y0, x1, y1) = (p[2 * i], p[2 * i + 1], p[2 * j], p[2 * j + 1]);
+                        self.pieces.push(Piece::Edge { x0, y0, x1, y1, polygon });
+                    }
+                }
+            }
+        }
+
+        self.grid.cover(bounds, CELL_SIZE);
+        self.grid.fill(self.pieces.len(), |k| Some(self.pieces[k].bounds()));
+        self.built = Some(bounds);
+    }
+
+    /// Pushes a particle out of the obstacles it overlaps, or back across any
+    /// edge its centre has crossed since the start of the step, and reflects
+    /// the normal part of its velocity, scaled by `elasticity`. Each bounce
+    /// goes in `events`; `candidates` is scratch space. Returns the kinetic
+    /// energy the bounces took out. The grid must be `prepare`d.
+    pub fn collide(&self, m: &mut Mover, elasticity: f32, candidates: &mut Vec<u32>, events: &mut Vec<CollisionEvent>) -> f64 {
+        if self.obstacles.is_empty() {
+            return 0.0;
+        }
+        let mut lost = 0.0;
+        let (from, to, r) = (m.from, (m.x, m.y), m.radius);
+        let (x, y) = self.resolve(from, to, r, candidates, |nx, ny, (x, y)| {
+            m.touched = true;
+            lost += m.bounce(nx, ny, (0.0, 0.0), (x - nx * r, y - ny * r), elasticity, events);
+        });
+        (m.x, m.y) = (x, y);
+        lost
+    }
+
+    /// Moves a particle of radius `r` that has gone from `from` to `to` out
+    /// of every piece it overlaps or crossed, one piece at a time in a fixed
+    /// order, and returns where it ends up. `contact` is called after each
+    /// push with the outward unit normal and the particle's new position.
+    /// `candidates` is scratch space. The grid must be `prepare`d.
+    pub fn resolve(&self, from: (f32, f32), to: (f32, f32), r: f32, candidates: &mut Vec<u32>, mut contact: impl FnMut(f32, f32, (f32, f32))) -> (f32, f32) {
+        let (mut x, mut y) = to;
+        let (c0, r0) = self.grid.cell_of(x.min(from.0) - r, y.min(from.1) - r);
+        let (c1, r1) = self.grid.cell_of(x.max(from.0) + r, y.max(from.1) + r);
+        candidates.clear();
+        for row in r0..=r1 {
+            candidates.extend_from_slice(self.grid.cells(row, c0, c1));
+        }
+        // Pieces spanning several cells turn up once per cell; test each
+        // once, in a fixed order.
+        candidates.sort_unstable();
+        candidates.dedup();
+        for &k in candidates.iter() {
+            let Some((nx, ny, depth)) = self.contact(k as usize, from, (x, y), r) else {
+                continue;
+            };
+            x += nx * depth;
+            y += ny * depth;
+            contact(nx, ny, (x, y));
+        }
+        (x, y)
+    }
+
+    /// The unit normal pointing out of piece `k` towards a particle of radius
+    /// `r` that has moved from `from` to (x, y), and how far the particle
+    /// must move along it to stop overlapping, if it does.
+    fn contact(&self, k: usize, from: (f32, f32), (x, y): (f32, f32), r: f32) -> Option<(f32, f32, f32)> {
+        match self.pieces[k] {
+            Piece::Disc { x: cx, y: cy, r: cr } => {
+                let (dx, dy) = (x - cx, y - cy);
+                let d2 = dx * dx + dy * dy;
+                let reach = r + cr;
+                if d2 == 0.0 || d2 >= reach * reach {
+                    return None;
+                }
+                let d = d2.sqrt();
+                Some((dx / d, dy / d, reach - d))
+            }
+            Piece::Edge { x0, y0, x1, y1, polygon } => {
+                let (ex, ey) = (x1 - x0, y1 - y0);
+                let len2 = ex * ex + ey * ey;
+                // A centre that moved onto or through the edge, more than a
+                // radius in one step, goes back to the side it came from.
+                // Leaving a polygon is always allowed.
+                let side = |(px, py): (f32, f32)| ex * (py - y0) - ey * (px - x0);
+                let (before, after) = (side(from), side((x, y)));
+                if before != 0.0 && before * after <= 0.0 && !polygon.is_some_and(|k| self.obstacles[k].1.contains(from.0, from.1)) {
+                    let t = before / (before - after);
+                    let (cx, cy) = (from.0 + t * (x - from.0), from.1 + t * (y - from.1));
+                    let u = ((cx - x0) * ex + (cy - y0) * ey) / len2;
+                    if (0.0..=1.0).contains(&u) {
+                        let len = len2.sqrt();
+                        let sign = before.signum();
+                        return Some((-ey / len * sign, ex / len * sign, r + after.abs() / len));
+                    }
+                }
+                let t = if len2 > 0.0 { (((x - x0) * ex + (y - y0) * ey) / len2).clamp(0.0, 1.0) } else { 0.0 };
+                let (dx, dy) = (x - (x0 + t * ex), y - (y0 + t * ey));
+                let d2 = dx * dx + dy * dy;
+                if d2 == 0.0 || d2 >= r * r {
+                    return None;
+                }
+                let d = d2.sqrt();
+                // A centre inside a polygon goes back out through this edge.
+                if polygon.is_some_and(|k| self.obstacles[k].1.contains(x, y)) {
+                    return Some((-dx / d, -dy / d, r + d));
+                }
+                Some((dx / d, dy / d, r - d))
+            }
+        }
+    }
+}
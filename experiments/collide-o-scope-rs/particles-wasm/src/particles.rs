@@ -0,0 +1,158 @@
+use crate::Particle;
+
+/// Structure-of-arrays particle storage. Each attribute is its own
+/// contiguous array, so the integrator and narrow phase stream through
+/// memory and JS can view a single attribute without copying. Slots never
+/// move when the simulation sorts; `Simulation::order` lists them in Z-order
+/// instead. `id` follows a particle for its whole life, whatever its slot.
+#[derive(Default)]
+pub struct Particles {
+    pub x: Vec<f32>,
+    pub y: Vec<f32>,
+    pub vx: Vec<f32>,
+    pub vy: Vec<f32>,
+    pub radius: Vec<f32>,
+    pub mass: Vec<f32>,
+    pub temp: Vec<f32>,
+    pub prev_x: Vec<f32>,
+    pub prev_y: Vec<f32>,
+    pub impulse_x: Vec<f32>,
+    pub impulse_y: Vec<f32>,
+    pub colliding: Vec<bool>,
+    pub z_code: Vec<u32>,
+    pub id: Vec<u32>,
+}
+
+/// Calls `$method($args)` on every attribute array, or runs `$body` with
+/// `$array` bound to each in turn.
+macro_rules! for_each_array {
+    ($particles:expr, $method:ident($($arg:expr),*)) => {
+        for_each_array!($particles, |array| array.$method($($arg),*))
+    };
+    ($particles:expr, |$array:ident| $body:expr) => {{
+        let particles = $particles;
+        { let $array = &mut particles.x; $body; }
+        { let $array = &mut particles.y; $body; }
+        { let $array = &mut particles.vx; $body; }
+        { let $array = &mut particles.vy; $body; }
+        { let $array = &mut particles.radius; $body; }
+        { let $array = &mut particles.mass; $body; }
+        { let $array = &mut particles.temp; $body; }
+        { let $array = &mut particles.prev_x; $body; }
+        { let $array = &mut particles.prev_y; $body; }
+        { let $array = &mut particles.impulse_x; $body; }
+        { let $array = &mut particles.impulse_y; $body; }
+        { let $array = &mut particles.colliding; $body; }
+        { let $array = &mut particles.z_code; $body; }
+        { let $array = &mut particles.id; $body; }
+    }};
+}
+
+impl Particles {
+    pub fn len(&self) -> usize {
+        self.x.len()
+    }
+
+    /// How many particles fit before some array has to grow. The arrays'
+    /// element sizes differ, so the allocator may round their capacities
+    /// differently; this is the smallest.
+    pub fn capacity(&self) -> usize {
+        [
+            self.x.capacity(),
+            self.y.capacity(),
+            self.vx.capacity(),
+            self.vy.capacity(),
+            self.radius.capacity(),
+            self.mass.capacity(),
+            self.temp.capacity(),
+            self.prev_x.capacity(),
+            self.prev_y.capacity(),
+            self.impulse_x.capacity(),
+            self.impulse_y.capacity(),
+            self.colliding.capacity(),
+            self.z_code.capacity(),
+            self.id.capacity(),
+        ]
+        .into_iter()
+        .min()
+        .unwrap()
+    }
+
+    /// Where each array's storage starts. An array that grows past its
+    /// capacity moves, which changes its entry.
+    pub fn addresses(&self) -> [usize; 14] {
+        [
+            self.x.as_ptr() as usize,
+            self.y.as_ptr() as usize,
+            self.vx.as_ptr() as usize,
+            self.vy.as_ptr() as usize,
+            self.radius.as_ptr() as usize,
+            self.mass.as_ptr() as usize,
+            self.temp.as_ptr() as usize,
+            self.prev_x.as_ptr() as usize,
+            self.prev_y.as_ptr() as usize,
+            self.impulse_x.as_ptr() as usize,
+            self.impulse_y.as_ptr() as usize,
+            self.colliding.as_ptr() as usize,
+            self.z_code.as_ptr() as usize,
+            self.id.as_ptr() as usize,
+        ]
+    }
+
+    pub fn clear(&mut self) {
+        for_each_array!(self, clear());
+    }
+
+    pub fn reserve_exact(&mut self, additional: usize) {
+        for_each_array!(self, reserve_exact(additional));
+    }
+
+    /// Keeps the particles whose slot is marked in `keep`, in slot order.
+    pub fn retain(&mut self, keep: &[bool]) {
+        for_each_array!(self, |array| {
+            let mut keep = keep.iter();
+            array.retain(|_| *keep.next().unwrap());
+        });
+    }
+
+    /// Appends `p`. When any array is full, all of them double together,
+    /// so they move at the same pushes.
+    pub fn push(&mut self, p: Particle) {
+        if self.len() == self.capacity() {
+            self.reserve_exact(self.len().max(4));
+        }
+        self.x.push(p.x);
+        self.y.push(p.y);
+        self.vx.push(p.vx);
+        self.vy.push(p.vy);
+        self.radius.push(p.radius);
+        self.mass.push(p.mass);
+        self.temp.push(p.color_temp);
+        self.prev_x.push(p.prev_x);
+        self.prev_y.push(p.prev_y);
+        self.impulse_x.push(p.impulse_x);
+        self.impulse_y.push(p.impulse_y);
+        self.colliding.push(p.colliding);
+        self.z_code.push(p.z_code);
+        self.id.push(p.id);
+    }
+
+    pub fn get(&self, i: usize) -> Particle {
+        Particle {
+            x: self.x[i],
+            y: self.y[i],
+            radius: self.radius[i],
+            mass: self.mass[i],
+            vx: self.vx[i],
+            vy: self.vy[i],
+            colliding: self.colliding[i],
+            z_code: self.z_code[i],
+            impulse_x: self.impulse_x[i],
+            impulse_y: self.impulse_y[i],
+            color_temp: self.temp[i],
+            prev_x: self.prev_x[i],
+            prev_y: self.prev_y[i],
+            id: self.id[i],
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! Direct manipulation: a force around the pointer, and a spring that drags
+//! one particle towards it.
+
+use wasm_bindgen::prelude::*;
+
+use crate::forces::{Emitter, EmitterKind};
+use crate::particles::Particles;
+
+/// What the pointer does to the particles around it.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PointerMode {
+    Off = 0,
+    /// Pulls particles in, like an `Attractor`.
+    Attract = 1,
+    /// Pushes them away, like a `Repulsor`.
+    Repel = 2,
+    /// Swirls them around, like a `Vortex`.
+    Stir = 3,
+}
+
+pub(crate) struct Pointer {
+    pub mode: PointerMode,
+    pub x: f32,
+    pub y: f32,
+    pub radius: f32,
+    pub strength: f32,
+}
+
+impl Default for Pointer {
+    fn default() -> Pointer {
+        Pointer { mode: PointerMode::Off, x: 0.0, y: 0.0, radius: 100.0, strength: 0.5 }
+    }
+}
+
+impl Pointer {
+    /// The emitter standing in for the pointer's force, if it has one.
+    pub fn emitter(&self) -> Option<Emitter> {
+        let kind = match self.mode {
+            PointerMode::Off => return None,
+            PointerMode::Attract => EmitterKind::Attractor,
+            PointerMode::Repel => EmitterKind::Repulsor,
+            PointerMode::Stir => EmitterKind::Vortex,
+        };
+        Some(Emitter::new(kind, self.x, self.y, 0.0, 0.0, self.radius, self.strength))
+    }
+}
+
+/// Angular frequency, in radians per reference frame, of the spring holding a
+/// grabbed particle. The explicit substep stays stable while `h` times this
+/// is below one.
+const GRAB_FREQUENCY: f32 = 0.5;
+
+/// A particle held at (x, y) by a critically damped spring.
+pub(crate) struct Grab {
+    pub id: u32,
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Grab {
+    /// Accelerates `slot` towards the target for a substep of `h` reference
+    /// frames.
+    pub fn pull(&self, p: &mut Particles, slot: usize, h: f32) {
+        let stiffness = GRAB_FREQUENCY * GRAB_FREQUENCY;
+        let damping = 2.0 * GRAB_FREQUENCY;
+        p.vx[slot] += (stiffness * (self.x - p.x[slot]) - damping * p.vx[slot]) * h;
+        p.vy[slot] += (stiffness * (self.y - p.y[slot]) - damping * p.vy[slot]) * h;
+    }
+}
@@ -0,0 +1,158 @@
+//! Spatial queries for picking and selection tools. They scan the particles
+//! in the Z-order the collision pass sorts them into, so a query only visits
+//! the codes inside its bounding box. Results are particle ids.
+
+use std::cmp::Ordering;
+
+use crate::particles::Particles;
+use crate::zorder;
+
+/// Slots sorted by Z-order code, with the codes alongside. Rebuilt from the
+/// collision pass's order on the first query after anything moves.
+#[derive(Default)]
+pub(crate) struct SpatialIndex {
+    slots: Vec<u32>,
+    codes: Vec<u32>,
+    /// Each slot's code, indexed by slot.
+    slot_codes: Vec<u32>,
+    max_radius: f32,
+    width: f32,
+    height: f32,
+    valid: bool,
+}
+
+impl SpatialIndex {
+    pub fn invalidate(&mut self) {
+        self.valid = false;
+    }
+
+    /// Brings the index up to date with `p`. Codes are taken afresh, since
+    /// collisions push particles apart after the sort, but `order` is still
+    /// nearly sorted by them.
+    pub fn refresh(&mut self, p: &Particles, order: &[u32], width: f32, height: f32) {
+        if self.valid {
+            return;
+        }
+        self.slot_codes.clear();
+        self.slot_codes.extend((0..p.len()).map(|i| zorder::code_at(p.x[i], p.y[i], width, height)));
+        self.slots.clear();
+        self.slots.extend_from_slice(order);
+        self.slots.extend(order.len() as u32..p.len() as u32);
+        let code = |&slot: &u32| self.slot_codes[slot as usize];
+        if !self.slots.is_sorted_by_key(code) {
+            self.slots.sort_by_key(code);
+        }
+        self.codes.clear();
+        self.codes.extend(self.slots.iter().map(code));
+        self.max_radius = self.slots.iter().map(|&slot| p.radius[slot as usize]).fold(0.0, f32::max);
+        self.width = width;
+        self.height = height;
+        self.valid = true;
+    }
+
+    /// The particle covering (x, y) whose centre is nearest to it.
+    pub fn pick(&self, p: &Particles, x: f32, y: f32, tolerance: usize) -> Option<u32> {
+        let mut best: Option<(f32, u32)> = None;
+        let reach = self.max_radius;
+        self.scan(x - reach, y - reach, x + reach, y + reach, tolerance, |slot| {
+            let d2 = distance_sq(p, slot, x, y);
+            if d2 <= p.radius[slot] * p.radius[slot] {
+                let candidate = (d2, p.id[slot]);
+                if best.is_none_or(|best| nearest_first(&candidate, &best).is_lt()) {
+                    best = Some(candidate);
+                }
+            }
+        });
+        best.map(|(_, id)| id)
+    }
+
+    /// Ids, ascending, of the particles overlapping the circle of radius `r`
+    /// around (x, y).
+    pub fn within_radius(&self, p: &Particles, x: f32, y: f32, r: f32, tolerance: usize) -> Vec<u32> {
+        let r = r.max(0.0);
+        let mut ids = Vec::new();
+        let reach = r + self.max_radius;
+        self.scan(x - reach, y - reach, x + reach, y + reach, tolerance, |slot| {
+            let reach = r + p.radius[slot];
+            if distance_sq(p, slot, x, y) <= reach * reach {
+                ids.push(p.id[slot]);
+            }
+        });
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Ids, ascending, of the particles overlapping the rectangle with
+    /// corners (x0, y0) and (x1, y1).
+    pub fn in_rect(&self, p: &Particles, x0: f32, y0: f32, x1: f32, y1: f32, tolerance: usize) -> Vec<u32> {
+        let (x0, x1) = (x0.min(x1), x0.max(x1));
+        let (y0, y1) = (y0.min(y1), y0.max(y1));
+        let mut ids = Vec::new();
+        let reach = self.max_radius;
+        self.scan(x0 - reach, y0 - reach, x1 + reach, y1 + reach, tolerance, |slot| {
+            let (x, y) = (p.x[slot].clamp(x0, x1), p.y[slot].clamp(y0, y1));
+            if distance_sq(p, slot, x, y) <= p.radius[slot] * p.radius[slot] {
+                ids.push(p.id[slot]);
+            }
+        });
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Ids of the `k` particles whose centres are nearest to (x, y), nearest
+    /// first. Searches a box around the point, doubling it until the `k`th
+    /// nearest found lies within it, or until it is as wide as the world and
+    /// checking every particle is no slower. Nothing is nearest to a point
+    /// that is not finite, or in a world with no area.
+    pub fn nearest(&self, p: &Particles, x: f32, y: f32, k: usize, tolerance: usize) -> Vec<u32> {
+        let k = k.min(self.slots.len());
+        // A box that would hold about k particles at the average density.
+        let density = self.slots.len() as f32 / (self.width * self.height);
+        if k == 0 || !x.is_finite() || !y.is_finite() || !density.is_finite() {
+            return Vec::new();
+        }
+        let mut reach = (k as f32 / (density * std::f32::consts::PI)).sqrt();
+        let diagonal = self.width.hypot(self.height);
+        let mut found = Vec::new();
+        loop {
+            found.clear();
+            if reach >= diagonal {
+                found.extend(self.slots.iter().map(|&slot| (distance_sq(p, slot as usize, x, y), p.id[slot as usize])));
+                found.select_nth_unstable_by(k - 1, nearest_first);
+                break;
+            }
+            self.scan(x - reach, y - reach, x + reach, y + reach, tolerance, |slot| found.push((distance_sq(p, slot, x, y), p.id[slot])));
+            if found.len() >= k {
+                found.select_nth_unstable_by(k - 1, nearest_first);
+                if found[k - 1].0 <= reach * reach || found.len() == self.slots.len() {
+                    break;
+                }
+            }
+            reach *= 2.0;
+        }
+        found.truncate(k);
+        found.sort_unstable_by(nearest_first);
+        found.into_iter().map(|(_, id)| id).collect()
+    }
+
+    /// Visits the slot of every particle whose centre's code lies in the box
+    /// from (x0, y0) to (x1, y1). Every centre inside the box is visited.
+    fn scan(&self, x0: f32, y0: f32, x1: f32, y1: f32, tolerance: usize, mut visit: impl FnMut(usize)) {
+        let zmin = zorder::code_at(x0, y0, self.width, self.height);
+        let zmax = zorder::code_at(x1, y1, self.width, self.height);
+        zorder::scan_rect(&self.codes, 0, zmin, zmax, tolerance, |run| {
+            for &slot in &self.slots[run] {
+                visit(slot as usize);
+            }
+        });
+    }
+}
+
+fn distance_sq(p: &Particles, slot: usize, x: f32, y: f32) -> f32 {
+    let (dx, dy) = (p.x[slot] - x, p.y[slot] - y);
+    dx * dx + dy * dy
+}
+
+fn nearest_first(a: &(f32, u32), b: &(f32, u32)) -> Ordering {
+    a.0.total_cmp(&b.0).then(a.1.cmp(&b.1))
+}
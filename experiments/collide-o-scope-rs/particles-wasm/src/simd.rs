@@ -0,0 +1,283 @@
+//! Four-lane `f32` vectors for the integrator and narrow phase. On wasm32
+//! built with `+simd128` they lower to `v128` instructions; everywhere else
+//! they are plain arrays, so the lane kernels still run (and are tested)
+//! natively. Every operation is the lane-wise IEEE equivalent of the scalar
+//! code it replaces, so both paths produce bit-identical results. `min` and
+//! `max` are the exception IEEE leaves open (NaN and ±0), so both paths use
+//! the ones defined here rather than `f32::min`/`max`.
+
+#[cfg(all(feature = "simd", target_arch = "wasm32", not(target_feature = "simd128")))]
+compile_error!("the `simd` feature needs wasm simd128: build with RUSTFLAGS=\"-C target-feature=+simd128\"");
+
+pub const LANES: usize = 4;
+
+/// `b` if it is less than `a`, otherwise `a`: a NaN or a tie keeps `a`.
+/// This is wasm's `f32x4.pmin` for one lane.
+#[inline]
+pub fn min(a: f32, b: f32) -> f32 {
+    if b < a {
+        b
+    } else {
+        a
+    }
+}
+
+/// `b` if it is greater than `a`, otherwise `a`, as wasm's `f32x4.pmax`.
+#[inline]
+pub fn max(a: f32, b: f32) -> f32 {
+    if a < b {
+        b
+    } else {
+        a
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+mod imp {
+    use core::arch::wasm32::*;
+    use std::ops::{Add, BitAnd, BitOr, Div, Mul, Sub};
+
+    #[derive(Clone, Copy)]
+    pub struct F32x4(v128);
+
+    #[derive(Clone, Copy)]
+    pub struct Mask(v128);
+
+    impl F32x4 {
+        #[inline]
+        pub fn splat(v: f32) -> F32x4 {
+            F32x4(f32x4_splat(v))
+        }
+
+        #[inline]
+        pub fn from_array(a: [f32; 4]) -> F32x4 {
+            F32x4(f32x4(a[0], a[1], a[2], a[3]))
+        }
+
+        #[inline]
+        pub fn load(src: &[f32]) -> F32x4 {
+            assert!(src.len() >= 4);
+            // SAFETY: four f32s are in bounds; v128_load has no alignment requirement.
+            F32x4(unsafe { v128_load(src.as_ptr() as *const v128) })
+        }
+
+        #[inline]
+        pub fn store(self, dst: &mut [f32]) {
+            assert!(dst.len() >= 4);
+            // SAFETY: as for `load`.
+            unsafe { v128_store(dst.as_mut_ptr() as *mut v128, self.0) }
+        }
+
+        #[inline]
+        pub fn to_array(self) -> [f32; 4] {
+            let mut a = [0.0; 4];
+            self.store(&mut a);
+            a
+        }
+
+        #[inline]
+        pub fn abs(self) -> F32x4 {
+            F32x4(f32x4_abs(self.0))
+        }
+
+        #[inline]
+        pub fn max(self, o: F32x4) -> F32x4 {
+            F32x4(f32x4_pmax(self.0, o.0))
+        }
+
+        #[inline]
+        pub fn min(self, o: F32x4) -> F32x4 {
+            F32x4(f32x4_pmin(self.0, o.0))
+        }
+
+        #[inline]
+        pub fn sqrt(self) -> F32x4 {
+            F32x4(f32x4_sqrt(self.0))
+        }
+
+        #[inline]
+        pub fn lt(self, o: F32x4) -> Mask {
+            Mask(f32x4_lt(self.0, o.0))
+        }
+
+        #[inline]
+        pub fn gt(self, o: F32x4) -> Mask {
+            Mask(f32x4_gt(self.0, o.0))
+        }
+    }
+
+    impl Mask {
+        /// `then` where the lane is set, `otherwise` elsewhere.
+        #[inline]
+        pub fn select(self, then: F32x4, otherwise: F32x4) -> F32x4 {
+            F32x4(v128_bitselect(then.0, otherwise.0, self.0))
+        }
+
+        /// Bit `k` is set when lane `k` is.
+        #[inline]
+        pub fn bits(self) -> u32 {
+            i32x4_bitmask(self.0) as u32
+        }
+    }
+
+    macro_rules! binary_op {
+        ($trait:ident, $method:ident, $intrinsic:ident) => {
+            impl $trait for F32x4 {
+                type Output = F32x4;
+
+                #[inline]
+                fn $method(self, o: F32x4) -> F32x4 {
+                    F32x4($intrinsic(self.0, o.0))
+                }
+            }
+        };
+    }
+
+    binary_op!(Add, add, f32x4_add);
+    binary_op!(Sub, sub, f32x4_sub);
+    binary_op!(Mul, mul, f32x4_mul);
+    binary_op!(Div, div, f32x4_div);
+
+    impl BitAnd for Mask {
+        type Output = Mask;
+
+        #[inline]
+        fn bitand(self, o: Mask) -> Mask {
+            Mask(v128_and(self.0, o.0))
+        }
+    }
+
+    impl BitOr for Mask {
+        type Output = Mask;
+
+        #[inline]
+        fn bitor(self, o: Mask) -> Mask {
+            Mask(v128_or(self.0, o.0))
+        }
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+mod imp {
+    use std::ops::{Add, BitAnd, BitOr, Div, Mul, Sub};
+
+    #[derive(Clone, Copy)]
+    pub struct F32x4([f32; 4]);
+
+    #[derive(Clone, Copy)]
+    pub struct Mask([bool; 4]);
+
+    impl F32x4 {
+        #[inline]
+        pub fn splat(v: f32) -> F32x4 {
+            F32x4([v; 4])
+        }
+
+        #[inline]
+        pub fn from_array(a: [f32; 4]) -> F32x4 {
+            F32x4(a)
+        }
+
+        #[inline]
+        pub fn load(src: &[f32]) -> F32x4 {
+            F32x4([src[0], src[1], src[2], src[3]])
+        }
+
+        #[inline]
+        pub fn store(self, dst: &mut [f32]) {
+            dst[..4].copy_from_slice(&self.0);
+        }
+
+        #[inline]
+        pub fn to_array(self) -> [f32; 4] {
+            self.0
+        }
+
+        #[inline]
+        fn zip(self, o: F32x4, f: impl Fn(f32, f32) -> f32) -> F32x4 {
+            F32x4(std::array::from_fn(|k| f(self.0[k], o.0[k])))
+        }
+
+        #[inline]
+        pub fn abs(self) -> F32x4 {
+            F32x4(self.0.map(f32::abs))
+        }
+
+        #[inline]
+        pub fn max(self, o: F32x4) -> F32x4 {
+            self.zip(o, super::max)
+        }
+
+        #[inline]
+        pub fn min(self, o: F32x4) -> F32x4 {
+            self.zip(o, super::min)
+        }
+
+        #[inline]
+        pub fn sqrt(self) -> F32x4 {
+            F32x4(self.0.map(f32::sqrt))
+        }
+
+        #[inline]
+        pub fn lt(self, o: F32x4) -> Mask {
+            Mask(std::array::from_fn(|k| self.0[k] < o.0[k]))
+        }
+
+        #[inline]
+        pub fn gt(self, o: F32x4) -> Mask {
+            Mask(std::array::from_fn(|k| self.0[k] > o.0[k]))
+        }
+    }
+
+    impl Mask {
+        /// `then` where the lane is set, `otherwise` elsewhere.
+        #[inline]
+        pub fn select(self, then: F32x4, otherwise: F32x4) -> F32x4 {
+            F32x4(std::array::from_fn(|k| if self.0[k] { then.0[k] } else { otherwise.0[k] }))
+        }
+
+        /// Bit `k` is set when lane `k` is.
+        #[inline]
+        pub fn bits(self) -> u32 {
+            (0..4).fold(0, |bits, k| bits | ((self.0[k] as u32) << k))
+        }
+    }
+
+    macro_rules! binary_op {
+        ($trait:ident, $method:ident, $op:tt) => {
+            impl $trait for F32x4 {
+                type Output = F32x4;
+
+                #[inline]
+                fn $method(self, o: F32x4) -> F32x4 {
+                    self.zip(o, |a, b| a $op b)
+                }
+            }
+        };
+    }
+
+    binary_op!(Add, add, +);
+    binary_op!(Sub, sub, -);
+    binary_op!(Mul, mul, *);
+    binary_op!(Div, div, /);
+
+    impl BitAnd for Mask {
+        type Output = Mask;
+
+        #[inline]
+        fn bitand(self, o: Mask) -> Mask {
+            Mask(std::array::from_fn(|k| self.0[k] && o.0[k]))
+        }
+    }
+
+    impl BitOr for Mask {
+        type Output = Mask;
+
+        #[inline]
+        fn bitor(self, o: Mask) -> Mask {
+            Mask(std::array::from_fn(|k| self.0[k] || o.0[k]))
+        }
+    }
+}
+
+pub use imp::F32x4;
@@ -0,0 +1,468 @@
+//! Binary save/restore of a whole simulation. All values are little-endian:
+//!
+//! ```text
+//! magic "CSNP", version u16
+//! width, height                                        f32
+//! particle_count u32, min_size, max_size, max_speed,
+//!   gravity, elasticity, fan_speed f32,
+//!   search_range i32, seed u64                         constructor parameters
+//! rng state u64, rng increment u64
+//! fixed_dt f32, substeps u32, accumulator f64, frame u64
+//! next_id u32
+//! n u32, then n each of x, y, vx, vy, radius, color_temp, prev_x, prev_y,
+//!   impulse_x, impulse_y (f32), z_code, id (u32) and colliding (u8)
+//! m u32, then m slot indices (u32) in Z-order
+//! next_emitter_id u32
+//! k u32, then k emitters of id u32, kind u8 and x, y, angle, width, reach,
+//!   strength (f32)
+//! next_obstacle_id u32
+//! o u32, then o obstacles of id u32, kind u8, point count u32 and that
+//!   many f32 points
+//! boundary mode u8, container point count u32 and that many f32 points
+//! next_body_id u32
+//! b u32, then b bodies of id u32, x, y, angle, vx, vy, spin (f32), motion
+//!   u8 and its parameters (f32): a u8 flag then, if set, the target x, y
+//!   and angle when scripted; anchor x, y, amplitude x, y, period and time
+//!   when oscillating; nothing when rotating. Then a shape count u32 and
+//!   that many shapes of kind u8, point count u32 and f32 points
+//! ```
+//!
+//! Mass is recomputed from the radius. Bump `SNAPSHOT_VERSION` whenever the
+//! format changes. Version 1 had no ids; its particles get ids `0..n`.
+//! Versions 1 and 2 had no emitters; they get the default fan for their
+//! `fan_speed`. Versions before 4 had no obstacles, versions before 5 had
+//! walls for a boundary, and versions before 6 had no bodies.
+
+use std::fmt;
+
+use wasm_bindgen::JsValue;
+
+use crate::bodies::{Bodies, Body, BodyMotion, BodyState, Motion};
+use crate::boundary::{Boundary, BoundaryMode};
+use crate::forces::{self, Emitter, EmitterKind, ForceField};
+use crate::obstacles::{Obstacle, ObstacleKind, Obstacles};
+use crate::{Particle, Rng, Simulation};
+
+pub const SNAPSHOT_VERSION: u16 = 6;
+const MAGIC: &[u8; 4] = b"CSNP";
+const PARTICLE_BYTES: usize = 10 * 4 + 4 + 4 + 1;
+const V1_PARTICLE_BYTES: usize = 10 * 4 + 4 + 1;
+const EMITTER_BYTES: usize = 4 + 1 + 6 * 4;
+const OBSTACLE_BYTES: usize = 4 + 1 + 4;
+const BODY_BYTES: usize = 4 + 6 * 4 + 1 + 4;
+const SHAPE_BYTES: usize = 1 + 4;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// Not a snapshot at all.
+    BadMagic,
+    /// Written by a newer (or unknown) version of the format.
+    UnsupportedVersion(u16),
+    /// Ends before the data it declares.
+    Truncated,
+    /// Well-formed but describes an impossible simulation.
+    Invalid(&'static str),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::BadMagic => write!(f, "not a simulation snapshot"),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "snapshot version {version} is not supported (expected {SNAPSHOT_VERSION})")
+            }
+            SnapshotError::Truncated => write!(f, "snapshot is truncated"),
+            SnapshotError::Invalid(reason) => write!(f, "invalid snapshot: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<SnapshotError> for JsValue {
+    fn from(error: SnapshotError) -> JsValue {
+        wasm_bindgen::JsError::new(&error.to_string()).into()
+    }
+}
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn u32(&mut self, v: u32) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    fn u64(&mut self, v: u64) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    fn f32(&mut self, v: f32) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    fn f32s(&mut self, values: &[f32]) {
+        for &v in values {
+            self.f32(v);
+        }
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], SnapshotError> {
+        if self.0.len() < len {
+            return Err(SnapshotError::Truncated);
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], SnapshotError> {
+        Ok(self.bytes(N)?.try_into().unwrap())
+    }
+
+    fn u16(&mut self) -> Result<u16, SnapshotError> {
+        self.array().map(u16::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, SnapshotError> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    fn i32(&mut self) -> Result<i32, SnapshotError> {
+        self.array().map(i32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> Result<u64, SnapshotError> {
+        self.array().map(u64::from_le_bytes)
+    }
+
+    fn f32(&mut self) -> Result<f32, SnapshotError> {
+        self.array().map(f32::from_le_bytes)
+    }
+
+    fn f64(&mut self) -> Result<f64, SnapshotError> {
+        self.array().map(f64::from_le_bytes)
+    }
+
+    /// A count of items `item_size` bytes each, checked against the bytes
+    /// left so a corrupt length can't trigger a huge allocation.
+    fn len(&mut self, item_size: usize) -> Result<usize, SnapshotError> {
+        let len = self.u32()? as usize;
+        if len.saturating_mul(item_size) > self.0.len() {
+            return Err(SnapshotError::Truncated);
+        }
+        Ok(len)
+    }
+
+    fn f32s(&mut self, len: usize) -> Result<Vec<f32>, SnapshotError> {
+        Ok(self.bytes(len * 4)?.chunks_exact(4).map(|b| f32::from_le_bytes(b.try_into().unwrap())).collect())
+    }
+}
+
+pub(crate) fn write(sim: &Simulation) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_into(sim, &mut out);
+    out
+}
+
+/// `write`, reusing `out`'s allocation.
+pub(crate) fn write_into(sim: &Simulation, out: &mut Vec<u8>) {
+    let p = &sim.particles;
+    out.clear();
+    out.reserve(96 + p.len() * PARTICLE_BYTES + sim.order.len() * 4);
+    let mut w = Writer(std::mem::take(out));
+    w.bytes(MAGIC);
+    w.bytes(&SNAPSHOT_VERSION.to_le_bytes());
+    w.f32(sim.width);
+    w.f32(sim.height);
+    w.u32(sim.particle_count as u32);
+    for v in [sim.min_size, sim.max_size, sim.max_speed, sim.gravity, sim.elasticity, sim.fan_speed] {
+        w.f32(v);
+    }
+    w.bytes(&sim.search_range.to_le_bytes());
+    w.u64(sim.seed);
+    let (state, inc) = sim.rng.to_parts();
+    w.u64(state);
+    w.u64(inc);
+    w.f32(sim.fixed_dt);
+    w.u32(sim.substeps);
+    w.bytes(&sim.accumulator.to_le_bytes());
+    w.u64(sim.frame);
+    w.u32(sim.next_id);
+
+    w.u32(p.len() as u32);
+    for values in [&p.x, &p.y, &p.vx, &p.vy, &p.radius, &p.temp, &p.prev_x, &p.prev_y, &p.impulse_x, &p.impulse_y] {
+        w.f32s(values);
+    }
+    for values in [&p.z_code, &p.id] {
+        for &v in values {
+            w.u32(v);
+        }
+    }
+    w.bytes(&p.colliding.iter().map(|&c| c as u8).collect::<Vec<u8>>());
+    w.u32(sim.order.len() as u32);
+    for &slot in &sim.order {
+        w.u32(slot);
+    }
+    w.u32(sim.forces.next_id());
+    w.u32(sim.forces.emitters().len() as u32);
+    for &(id, e) in sim.forces.emitters() {
+        w.u32(id);
+        w.bytes(&[e.kind as u8]);
+        w.f32s(&[e.x, e.y, e.angle, e.width, e.reach, e.strength]);
+    }
+    w.u32(sim.obstacles.next_id());
+    w.u32(sim.obstacles.obstacles().len() as u32);
+    for (id, obstacle) in sim.obstacles.obstacles() {
+        w.u32(*id);
+        w.bytes(&[obstacle.kind as u8]);
+        w.u32(obstacle.points.len() as u32);
+        w.f32s(&obstacle.points);
+    }
+    w.bytes(&[sim.boundary.mode() as u8]);
+    let container = sim.boundary.polygon().map_or(&[][..], |polygon| &polygon.points);
+    w.u32(container.len() as u32);
+    w.f32s(container);
+    w.u32(sim.bodies.next_id());
+    w.u32(sim.bodies.bodies().len() as u32);
+    for (id, body) in sim.bodies.bodies() {
+        let s = body.state;
+        w.u32(*id);
+        w.f32s(&[s.x, s.y, s.angle, s.vx, s.vy, s.spin]);
+        w.bytes(&[body.motion.kind() as u8]);
+        match body.motion {
+            Motion::Scripted { target: None } => w.bytes(&[0]),
+            Motion::Scripted { target: Some((x, y, angle)) } => {
+                w.bytes(&[1]);
+                w.f32s(&[x, y, angle]);
+            }
+            Motion::Oscillate { anchor, amplitude, period, time } => {
+                w.f32s(&[anchor.0, anchor.1, amplitude.0, amplitude.1, period, time]);
+            }
+            Motion::Rotate => {}
+        }
+        w.u32(body.shapes().count() as u32);
+        for shape in body.shapes() {
+            w.bytes(&[shape.kind as u8]);
+            w.u32(shape.points.len() as u32);
+            w.f32s(&shape.points);
+        }
+    }
+    *out = w.0;
+}
+
+/// Replaces `sim`'s state with the snapshot's. Everything is decoded and
+/// checked first, so on error `sim` is left untouched.
+pub(crate) fn read(sim: &mut Simulation, bytes: &[u8]) -> Result<(), SnapshotError> {
+    let mut r = Reader(bytes);
+    if r.bytes(MAGIC.len()).map_err(|_| SnapshotError::BadMagic)? != MAGIC {
+        return Err(SnapshotError::BadMagic);
+    }
+    let version = r.u16()?;
+    if !(1..=SNAPSHOT_VERSION).contains(&version) {
+        return Err(SnapshotError::UnsupportedVersion(version));
+    }
+
+    let width = r.f32()?;
+    let height = r.f32()?;
+    let particle_count = r.u32()? as usize;
+    let [min_size, max_size, max_speed, gravity, elasticity, fan_speed] =
+        [r.f32()?, r.f32()?, r.f32()?, r.f32()?, r.f32()?, r.f32()?];
+    let search_range = r.i32()?;
+    let seed = r.u64()?;
+    let rng = Rng::from_parts(r.u64()?, r.u64()?);
+    let fixed_dt = r.f32()?;
+    let substeps = r.u32()?;
+    let accumulator = r.f64()?;
+    let frame = r.u64()?;
+    let next_id = if version == 1 { None } else { Some(r.u32()?) };
+    if !(width > 0.0 && height > 0.0) {
+        return Err(SnapshotError::Invalid("world size must be positive"));
+    }
+    if !(fixed_dt > 0.0 && substeps > 0) {
+        return Err(SnapshotError::Invalid("timestep must be positive"));
+    }
+
+    let n = r.len(if version == 1 { V1_PARTICLE_BYTES } else { PARTICLE_BYTES })?;
+    let [x, y, vx, vy, radius, temp, prev_x, prev_y, impulse_x, impulse_y] = [
+        r.f32s(n)?,
+        r.f32s(n)?,
+        r.f32s(n)?,
+        r.f32s(n)?,
+        r.f32s(n)?,
+        r.f32s(n)?,
+        r.f32s(n)?,
+        r.f32s(n)?,
+        r.f32s(n)?,
+        r.f32s(n)?,
+    ];
+    let z_code: Vec<u32> = (0..n).map(|_| r.u32()).collect::<Result<_, _>>()?;
+    let id: Vec<u32> = match next_id {
+        Some(_) => (0..n).map(|_| r.u32()).collect::<Result<_, _>>()?,
+        None => (0..n as u32).collect(),
+    };
+    let colliding = r.bytes(n)?;
+    let m = r.len(4)?;
+    let order: Vec<u32> = (0..m).map(|_| r.u32()).collect::<Result<_, _>>()?;
+    let forces = if version < 3 {
+        let fan = forces::default_fan(width, height, min_size, max_size, fan_speed);
+        ForceField::from_parts(1, vec![(0, fan)])
+    } else {
+        let next_id = r.u32()?;
+        let k = r.len(EMITTER_BYTES)?;
+        let mut emitters = Vec::with_capacity(k);
+        for _ in 0..k {
+            let id = r.u32()?;
+            let kind = EmitterKind::from_u8(r.array::<1>()?[0]).ok_or(SnapshotError::Invalid("unknown emitter kind"))?;
+            let [x, y, angle, width, reach, strength] = [r.f32()?, r.f32()?, r.f32()?, r.f32()?, r.f32()?, r.f32()?];
+            emitters.push((id, Emitter { kind, x, y, angle, width, reach, strength }));
+        }
+        ForceField::from_parts(next_id, emitters)
+    };
+    let forces = forces.ok_or(SnapshotError::Invalid("emitter ids out of order"))?;
+    let obstacles = if version < 4 {
+        Obstacles::from_parts(0, Vec::new())
+    } else {
+        let next_id = r.u32()?;
+        let o = r.len(OBSTACLE_BYTES)?;
+        let mut obstacles = Vec::with_capacity(o);
+        for _ in 0..o {
+            let id = r.u32()?;
+            let kind = ObstacleKind::from_u8(r.array::<1>()?[0]).ok_or(SnapshotError::Invalid("unknown obstacle kind"))?;
+            let len = r.len(4)?;
+            let obstacle = Obstacle::new(kind, &r.f32s(len)?).ok_or(SnapshotError::Invalid("malformed obstacle"))?;
+            obstacles.push((id, obstacle));
+        }
+        Obstacles::from_parts(next_id, obstacles)
+    };
+    let obstacles = obstacles.ok_or(SnapshotError::Invalid("obstacle ids out of order"))?;
+    let boundary = if version < 5 {
+        Boundary::from_parts(BoundaryMode::Walls, None)
+    } else {
+        let mode = BoundaryMode::from_u8(r.array::<1>()?[0]).ok_or(SnapshotError::Invalid("unknown boundary mode"))?;
+        let len = r.len(4)?;
+        let container = match len {
+            0 => None,
+            _ => Some(Obstacle::new(ObstacleKind::Polygon, &r.f32s(len)?).ok_or(SnapshotError::Invalid("malformed container"))?),
+        };
+        Boundary::from_parts(mode, container)
+    };
+    let boundary = boundary.ok_or(SnapshotError::Invalid("polygon boundary without a polygon"))?;
+    let bodies = if version < 6 {
+        Bodies::from_parts(0, Vec::new())
+    } else {
+        let next_id = r.u32()?;
+        let b = r.len(BODY_BYTES)?;
+        let mut bodies = Vec::with_capacity(b);
+        for _ in 0..b {
+            let id = r.u32()?;
+            let [x, y, angle, vx, vy, spin] = [r.f32()?, r.f32()?, r.f32()?, r.f32()?, r.f32()?, r.f32()?];
+            let state = BodyState { x, y, angle, vx, vy, spin };
+            let motion = match BodyMotion::from_u8(r.array::<1>()?[0]).ok_or(SnapshotError::Invalid("unknown body motion"))? {
+                BodyMotion::Scripted => match r.array::<1>()?[0] {
+                    0 => Motion::Scripted { target: None },
+                    _ => Motion::Scripted { target: Some((r.f32()?, r.f32()?, r.f32()?)) },
+                },
+                BodyMotion::Oscillate => {
+                    let [ax, ay, amplitude_x, amplitude_y, period, time] = [r.f32()?, r.f32()?, r.f32()?, r.f32()?, r.f32()?, r.f32()?];
+                    Motion::Oscillate { anchor: (ax, ay), amplitude: (amplitude_x, amplitude_y), period, time }
+                }
+                BodyMotion::Rotate => Motion::Rotate,
+            };
+            let count = r.len(SHAPE_BYTES)?;
+            let mut shapes = Vec::with_capacity(count);
+            for _ in 0..count {
+                let kind = ObstacleKind::from_u8(r.array::<1>()?[0]).ok_or(SnapshotError::Invalid("unknown obstacle kind"))?;
+                let len = r.len(4)?;
+                shapes.push(Obstacle::new(kind, &r.f32s(len)?).ok_or(SnapshotError::Invalid("malformed body shape"))?);
+            }
+            bodies.push((id, Body::new(state, motion, shapes).ok_or(SnapshotError::Invalid("body oscillation needs a positive period"))?));
+        }
+        Bodies::from_parts(next_id, bodies)
+    };
+    let bodies = bodies.ok_or(SnapshotError::Invalid("body ids out of order"))?;
+    if !r.0.is_empty() {
+        return Err(SnapshotError::Invalid("trailing data"));
+    }
+    // Sorting appends slots m.. to `order`, so it must hold exactly 0..m.
+    if m > n {
+        return Err(SnapshotError::Invalid("more sorted slots than particles"));
+    }
+    let mut seen = vec![false; m];
+    for &slot in &order {
+        match seen.get_mut(slot as usize) {
+            Some(seen @ false) => *seen = true,
+            _ => return Err(SnapshotError::Invalid("sort order is not a permutation")),
+        }
+    }
+    // Slots are looked up by a binary search over the ids.
+    if !id.is_sorted_by(|a, b| a < b) {
+        return Err(SnapshotError::Invalid("particle ids are not ascending"));
+    }
+    let next_id = next_id.unwrap_or(n as u32);
+    if id.last().is_some_and(|&last| last >= next_id) {
+        return Err(SnapshotError::Invalid("particle id at or past the next one"));
+    }
+
+    let buffers = sim.buffer_addresses();
+    sim.particles.clear();
+    for i in 0..n {
+        sim.particles.push(Particle {
+            x: x[i],
+            y: y[i],
+            radius: radius[i],
+            mass: std::f32::consts::PI * radius[i] * radius[i],
+            vx: vx[i],
+            vy: vy[i],
+            colliding: colliding[i] != 0,
+            z_code: z_code[i],
+            impulse_x: impulse_x[i],
+            impulse_y: impulse_y[i],
+            color_temp: temp[i],
+            prev_x: prev_x[i],
+            prev_y: prev_y[i],
+            id: id[i],
+        });
+    }
+
+    sim.width = width;
+    sim.height = height;
+    sim.particle_count = particle_count;
+    sim.min_size = min_size;
+    sim.max_size = max_size;
+    sim.max_speed = max_speed;
+    sim.gravity = gravity;
+    sim.elasticity = elasticity;
+    sim.fan_speed = fan_speed;
+    sim.forces = forces;
+    // The pointer is live input rather than saved state.
+    sim.forces.set_pointer(sim.pointer.emitter());
+    sim.obstacles = obstacles;
+    sim.boundary = boundary;
+    sim.bodies = bodies;
+    sim.search_range = search_range;
+    sim.seed = seed;
+    sim.rng = rng;
+    sim.fixed_dt = fixed_dt;
+    sim.substeps = substeps;
+    sim.accumulator = accumulator;
+    sim.frame = frame;
+    // Diagnostics describe the run since the last reset or restore.
+    sim.collisions = 0;
+    sim.energy_lost = 0.0;
+    sim.total_energy_lost = 0.0;
+    sim.order.clear();
+    sim.order.extend_from_slice(&order);
+    sim.next_id = next_id;
+    sim.index.invalidate();
+    sim.reserve_order();
+    sim.note_moved_buffers(buffers);
+    Ok(())
+}
@@ -0,0 +1,65 @@
+const RADIX_BITS: u32 = 8;
+const BUCKETS: usize = 1 << RADIX_BITS;
+const PASSES: usize = 32 / RADIX_BITS as usize;
+
+/// LSD radix sort over 32-bit keys. The entry and scratch buffers are kept
+/// between calls, so sorting every frame doesn't allocate once warmed up.
+#[derive(Default)]
+pub struct RadixSorter {
+    entries: Vec<u64>,
+    scratch: Vec<u64>,
+}
+
+impl RadixSorter {
+    /// Stable sort of the slot indices in `order` by `keys[slot]`. Equal keys
+    /// keep their relative order from the previous frame.
+    pub fn sort_indices(&mut self, order: &mut [u32], keys: &[u32]) {
+        // Key in the high half, slot in the low half: sorting on the high
+        // half alone keeps equal keys in their original order.
+        self.entries.clear();
+        self.entries.extend(order.iter().map(|&slot| ((keys[slot as usize] as u64) << 32) | slot as u64));
+        self.sort_entries();
+
+        for (slot, &entry) in order.iter_mut().zip(self.entries.iter()) {
+            *slot = entry as u32;
+        }
+    }
+
+    fn sort_entries(&mut self) {
+        let n = self.entries.len();
+        let mut counts = [[0usize; BUCKETS]; PASSES];
+        for &entry in &self.entries {
+            let key = (entry >> 32) as u32;
+            for (pass, count) in counts.iter_mut().enumerate() {
+                count[digit(key, pass)] += 1;
+            }
+        }
+
+        self.scratch.clear();
+        self.scratch.resize(n, 0);
+        for (pass, count) in counts.iter().enumerate() {
+            // Every key shares this digit, so the pass would be a no-op.
+            if count.contains(&n) {
+                continue;
+            }
+
+            let mut offsets = [0usize; BUCKETS];
+            let mut total = 0;
+            for (offset, &c) in offsets.iter_mut().zip(count.iter()) {
+                *offset = total;
+                total += c;
+            }
+
+            for &entry in &self.entries {
+                let bucket = digit((entry >> 32) as u32, pass);
+                self.scratch[offsets[bucket]] = entry;
+                offsets[bucket] += 1;
+            }
+            std::mem::swap(&mut self.entries, &mut self.scratch);
+        }
+    }
+}
+
+fn digit(key: u32, pass: usize) -> usize {
+    ((key >> (pass as u32 * RADIX_BITS)) as usize) & (BUCKETS - 1)
+}
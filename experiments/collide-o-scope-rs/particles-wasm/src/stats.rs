@@ -0,0 +1,27 @@
+use wasm_bindgen::prelude::*;
+
+/// Where the time went during the last `update` (all of its steps) or the
+/// last direct `step`, from `Simulation::get_stats`. Times are in
+/// milliseconds and summed over steps and substeps.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameStats {
+    /// Fixed steps taken.
+    pub steps: u32,
+    /// Gravity, emitters, cooling, motion and the boundary.
+    pub integrate_ms: f64,
+    /// Recomputing every particle's Z-order code.
+    pub z_code_ms: f64,
+    /// Sorting particles by Z-order code (`get_sort_time_ms` per step).
+    pub sort_ms: f64,
+    /// Gathering positions into Z-order, building the broadphase and
+    /// querying it, including the exact overlap test of every candidate.
+    pub broadphase_ms: f64,
+    /// Ordering the overlapping pairs and resolving their collisions, then
+    /// bouncing particles off obstacles.
+    pub narrow_phase_ms: f64,
+    /// Candidate pairs the broadphase tested for overlap.
+    pub candidate_pairs: u64,
+    /// Candidates that overlapped.
+    pub contacts: u64,
+}
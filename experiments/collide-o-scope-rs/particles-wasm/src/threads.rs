@@ -0,0 +1,206 @@
+//! Spreads work over a persistent thread pool. With the `threads` feature,
+//! native builds use rayon's global pool, and wasm builds use one of Web
+//! Workers sharing the module's memory once `startThreadPool` has started it
+//! (see the README). Without the feature, or before the pool starts, the
+//! same work runs on the calling thread. In the browser, the caller takes a
+//! share and spins rather than blocks while the workers finish, since the
+//! page's main thread may not block. Callers split work the same way
+//! whatever runs it, so results never depend on which did.
+
+use std::ops::Range;
+
+/// Chunk `k` of `0..len` split into `count` contiguous ranges whose sizes
+/// differ by at most one.
+pub fn chunk(len: usize, count: usize, k: usize) -> Range<usize> {
+    let (base, extra) = (len / count, len % count);
+    let start = k * base + k.min(extra);
+    start..start + base + usize::from(k < extra)
+}
+
+/// Calls `work` once per output with that output's chunk of `0..len`.
+pub fn run_chunks<T: Send>(len: usize, outputs: &mut [T], work: impl Fn(Range<usize>, &mut T) + Sync) {
+    let count = outputs.len();
+    run_each(outputs, count, |k, output| work(chunk(len, count, k), output));
+}
+
+/// Calls `work` with each item and its index, on the pool if `threads` is
+/// above 1.
+#[cfg(feature = "threads")]
+pub fn run_each<T: Send>(items: &mut [T], threads: usize, work: impl Fn(usize, &mut T) + Sync) {
+    if threads <= 1 || items.len() <= 1 || !pool::started() {
+        items.iter_mut().enumerate().for_each(|(k, item)| work(k, item));
+        return;
+    }
+    pool::for_each(items, &work);
+}
+
+#[cfg(not(feature = "threads"))]
+pub fn run_each<T: Send>(items: &mut [T], _threads: usize, work: impl Fn(usize, &mut T) + Sync) {
+    items.iter_mut().enumerate().for_each(|(k, item)| work(k, item));
+}
+
+#[cfg(all(feature = "threads", not(target_arch = "wasm32")))]
+pub fn default_threads() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Browser builds stay on one thread until the page starts the pool and
+/// picks a count.
+#[cfg(not(all(feature = "threads", not(target_arch = "wasm32"))))]
+pub fn default_threads() -> usize {
+    1
+}
+
+#[cfg(feature = "threads")]
+mod pool {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn for_each<T: Send>(items: &mut [T], work: &(impl Fn(usize, &mut T) + Sync)) {
+        use rayon::prelude::*;
+
+        items.par_iter_mut().with_max_len(1).enumerate().for_each(|(k, item)| work(k, item));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn started() -> bool {
+        true
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub use web::{for_each, started};
+
+    /// Rayon's blocking calls would trap on the page's main thread, so the
+    /// caller hands out the items itself and spins until they are done.
+    #[cfg(target_arch = "wasm32")]
+    mod web {
+        use std::hint;
+        use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        use wasm_bindgen::prelude::*;
+
+        static STARTED: AtomicBool = AtomicBool::new(false);
+
+        pub fn started() -> bool {
+            STARTED.load(Ordering::Acquire)
+        }
+
+        /// Starts `threads` Web Workers sharing the module's memory. The
+        /// page must be cross-origin isolated. Once the promise resolves,
+        /// `set_threads` above 1 spreads each collision pass over them;
+        /// until then everything runs on the calling thread.
+        #[wasm_bindgen(js_name = startThreadPool)]
+        pub fn start_thread_pool(threads: usize) -> js_sys::Promise {
+            let started = Closure::once(|_: JsValue| STARTED.store(true, Ordering::Release));
+            let promise = wasm_bindgen_rayon::init_thread_pool(threads).then(&started);
+            started.forget();
+            promise
+        }
+
+        /// A slice's items, shared with the workers.
+        struct Items<T>(*mut T);
+
+        // SAFETY: `for_each` hands each item to one call only.
+        unsafe impl<T: Send> Send for Items<T> {}
+        unsafe impl<T: Send> Sync for Items<T> {}
+
+        impl<T> Items<T> {
+            /// # Safety
+            /// `k` must be in bounds and not handed out twice at once, and
+            /// the slice must outlive `'a`.
+            unsafe fn get<'a>(&self, k: usize) -> &'a mut T {
+                &mut *self.0.add(k)
+            }
+        }
+
+        /// One `for_each` call, shared with the workers. `work` points at the
+        /// caller's closure, which `call` knows the type of; a raw pointer,
+        /// since jobs can outlive it.
+        struct Job {
+            work: *const (),
+            call: unsafe fn(*const (), usize),
+            count: usize,
+            next: AtomicUsize,
+            done: AtomicUsize,
+        }
+
+        // SAFETY: `work` points at a `Sync` closure and is only called
+        // through for claimed indices, while `for_each` keeps it alive.
+        unsafe impl Send for Job {}
+        unsafe impl Sync for Job {}
+
+        impl Job {
+            /// Claims and runs indices until there are none left.
+            fn run(&self) {
+                loop {
+                    let k = self.next.fetch_add(1, Ordering::Relaxed);
+                    if k >= self.count {
+                        return;
+                    }
+                    let _done = Done(&self.done);
+                    // SAFETY: `k` was claimed below `count`, so `for_each`
+                    // is still waiting and `work` is alive.
+                    unsafe { (self.call)(self.work, k) };
+                }
+            }
+        }
+
+        /// # Safety
+        /// `work` must point at a live `F`.
+        unsafe fn call<F: Fn(usize) + Sync>(work: *const (), k: usize) {
+            (*work.cast::<F>())(k)
+        }
+
+        fn erase<F: Fn(usize) + Sync>(work: &F) -> (*const (), unsafe fn(*const (), usize)) {
+            ((work as *const F).cast(), call::<F>)
+        }
+
+        /// Counts an index as done when its call returns or unwinds.
+        struct Done<'a>(&'a AtomicUsize);
+
+        impl Drop for Done<'_> {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::Release);
+            }
+        }
+
+        /// Stops further claims and waits out the ones made, however the
+        /// caller leaves `for_each`.
+        struct Close<'a>(&'a Job);
+
+        impl Drop for Close<'_> {
+            fn drop(&mut self) {
+                let job = self.0;
+                let claimed = job.next.fetch_add(job.count, Ordering::Relaxed).min(job.count);
+                while job.done.load(Ordering::Acquire) < claimed {
+                    hint::spin_loop();
+                }
+            }
+        }
+
+        /// Calls `work` with each item, on the workers and this thread. This
+        /// thread claims items like any worker, so it only spins once the
+        /// last ones are already running.
+        pub fn for_each<T: Send>(items: &mut [T], work: &(impl Fn(usize, &mut T) + Sync)) {
+            let count = items.len();
+            let items = Items(items.as_mut_ptr());
+            // SAFETY: each index below `count` is claimed once, so no two
+            // calls share an item, and all of them end before `for_each`
+            // returns (see below), while `items` is still borrowed.
+            let each = |k| work(k, unsafe { items.get(k) });
+            let (each_ptr, call) = erase(&each);
+            let job = Arc::new(Job { work: each_ptr, call, count, next: AtomicUsize::new(0), done: AtomicUsize::new(0) });
+            // The workers only reach `each` through claimed indices. `Close`
+            // runs when this frame exits, whether by returning or unwinding:
+            // it ends claiming, then waits for every claimed call to count
+            // itself done, which `Done` does even if the call unwinds. So
+            // `each` and `items` outlive every call. Broadcast jobs that start
+            // late only find the indices used up and drop their `Arc`,
+            // without touching `each`. A panic can't strand a call either:
+            // wasm32 aborts on panic, as does rayon when one escapes a job.
+            let _close = Close(&job);
+            let shared = Arc::clone(&job);
+            rayon::spawn_broadcast(move |_| shared.run());
+            job.run();
+        }
+    }
+}
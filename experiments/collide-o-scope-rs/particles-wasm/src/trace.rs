@@ -0,0 +1,477 @@
+//! Recording and replay of particle traces. Each captured frame stores
+//! every particle's position, radius and colour temperature quantised to
+//! integers, and whether it was colliding. Values are written as
+//! zigzag varints of their change since the previous frame; the first frame
+//! of each chunk is a keyframe, so it is written against zero instead.
+//! Chunks start every `KEYFRAME_INTERVAL` frames and whenever the world is
+//! resized. The recorder keeps them in a ring buffer with a byte budget,
+//! dropping whole chunks from the front. Exported traces are little-endian:
+//!
+//! ```text
+//! magic "CTRC", version u16, position quantum f32, chunk count u32
+//! per chunk: width, height f32, frame count u32, frame byte offsets u32...,
+//!   byte length u32, bytes
+//! per frame: simulation frame (varint), particle count (varint), then the
+//!   x, y, radius and temperature deltas of every particle (zigzag varints)
+//!   and the collision flags packed eight to a byte
+//! ```
+
+use std::collections::VecDeque;
+use std::fmt;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{Particle, Simulation};
+
+pub const TRACE_VERSION: u16 = 1;
+const MAGIC: &[u8; 4] = b"CTRC";
+const KEYFRAME_INTERVAL: usize = 60;
+/// Positions and radii are stored in sixteenths of a pixel.
+const POSITION_QUANTUM: f32 = 1.0 / 16.0;
+const TEMP_LEVELS: f32 = 255.0;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TraceError {
+    /// Not a trace at all.
+    BadMagic,
+    /// Written by a newer (or unknown) version of the format.
+    UnsupportedVersion(u16),
+    /// Ends before the data it declares.
+    Truncated,
+    /// Well-formed but inconsistent.
+    Invalid(&'static str),
+}
+
+impl fmt::Display for TraceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TraceError::BadMagic => write!(f, "not a particle trace"),
+            TraceError::UnsupportedVersion(version) => {
+                write!(f, "trace version {version} is not supported (expected {TRACE_VERSION})")
+            }
+            TraceError::Truncated => write!(f, "trace is truncated"),
+            TraceError::Invalid(reason) => write!(f, "invalid trace: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for TraceError {}
+
+impl From<TraceError> for JsValue {
+    fn from(error: TraceError) -> JsValue {
+        JsError::new(&error.to_string()).into()
+    }
+}
+
+/// Quantised state of every particle in one frame; both the encoder and
+/// decoder keep the previous frame's to take deltas against.
+#[derive(Clone, Default)]
+struct FrameState {
+    frame: u64,
+    x: Vec<i32>,
+    y: Vec<i32>,
+    radius: Vec<i32>,
+    temp: Vec<i32>,
+    colliding: Vec<bool>,
+}
+
+impl FrameState {
+    fn clear(&mut self) {
+        self.x.clear();
+        self.y.clear();
+        self.radius.clear();
+        self.temp.clear();
+        self.colliding.clear();
+    }
+
+    fn capture(&mut self, sim: &Simulation) {
+        let p = &sim.particles;
+        let position = |v: f32| (v / POSITION_QUANTUM).round() as i32;
+        self.frame = sim.frame;
+        self.clear();
+        self.x.extend(p.x.iter().map(|&v| position(v)));
+        self.y.extend(p.y.iter().map(|&v| position(v)));
+        self.radius.extend(p.radius.iter().map(|&v| position(v)));
+        self.temp.extend(p.temp.iter().map(|&v| (v.clamp(0.0, 1.0) * TEMP_LEVELS).round() as i32));
+        self.colliding.extend_from_slice(&p.colliding);
+    }
+
+    fn particle(&self, i: usize) -> Particle {
+        let (x, y) = (self.x[i] as f32 * POSITION_QUANTUM, self.y[i] as f32 * POSITION_QUANTUM);
+        let radius = self.radius[i] as f32 * POSITION_QUANTUM;
+        Particle {
+            x,
+            y,
+            radius,
+            mass: std::f32::consts::PI * radius * radius,
+            vx: 0.0,
+            vy: 0.0,
+            colliding: self.colliding[i],
+            z_code: 0,
+            impulse_x: 0.0,
+            impulse_y: 0.0,
+            color_temp: self.temp[i] as f32 / TEMP_LEVELS,
+            prev_x: x,
+            prev_y: y,
+            // Traces don't record ids; slots stand in for them.
+            id: i as u32,
+        }
+    }
+
+    /// Appends this frame encoded against `base`, or against zero for a keyframe.
+    fn encode(&self, base: Option<&FrameState>, out: &mut Vec<u8>) {
+        write_varint(out, self.frame);
+        write_varint(out, self.x.len() as u64);
+        let empty = FrameState::default();
+        let base = base.unwrap_or(&empty);
+        for (values, base) in [(&self.x, &base.x), (&self.y, &base.y), (&self.radius, &base.radius), (&self.temp, &base.temp)] {
+            for (i, &v) in values.iter().enumerate() {
+                let delta = v.wrapping_sub(base.get(i).copied().unwrap_or(0));
+                write_varint(out, zigzag(delta));
+            }
+        }
+        for flags in self.colliding.chunks(8) {
+            out.push(flags.iter().enumerate().fold(0, |byte, (k, &c)| byte | ((c as u8) << k)));
+        }
+    }
+
+    /// Replaces this state with the frame at the front of `bytes`, which was
+    /// encoded against this state (or against zero if `keyframe`).
+    fn decode(&mut self, bytes: &mut &[u8], keyframe: bool) -> Result<(), TraceError> {
+        if keyframe {
+            self.clear();
+        }
+        self.frame = read_varint(bytes)?;
+        let n = read_varint(bytes)? as usize;
+        // Every particle takes at least four bytes of deltas.
+        if n > bytes.len() / 4 {
+            return Err(TraceError::Truncated);
+        }
+        for values in [&mut self.x, &mut self.y, &mut self.radius, &mut self.temp] {
+            values.resize(n, 0);
+            for v in values.iter_mut() {
+                *v = v.wrapping_add(unzigzag(read_varint(bytes)?));
+            }
+        }
+        let flags = take(bytes, n.div_ceil(8))?;
+        self.colliding.clear();
+        self.colliding.extend((0..n).map(|i| flags[i / 8] & (1 << (i % 8)) != 0));
+        Ok(())
+    }
+}
+
+/// A keyframe and the delta frames that follow it.
+struct Chunk {
+    width: f32,
+    height: f32,
+    offsets: Vec<u32>,
+    bytes: Vec<u8>,
+}
+
+impl Chunk {
+    fn frame(&self, k: usize) -> &[u8] {
+        let end = self.offsets.get(k + 1).map_or(self.bytes.len(), |&end| end as usize);
+        &self.bytes[self.offsets[k] as usize..end]
+    }
+}
+
+/// Captures frames from a `Simulation` into a ring buffer of at most
+/// `max_bytes`, oldest chunks dropped first.
+#[wasm_bindgen]
+pub struct Recorder {
+    max_bytes: usize,
+    chunks: VecDeque<Chunk>,
+    bytes: usize,
+    frames: usize,
+    last: Option<FrameState>,
+    current: FrameState,
+}
+
+#[wasm_bindgen]
+impl Recorder {
+    #[wasm_bindgen(constructor)]
+    pub fn new(max_bytes: usize) -> Recorder {
+        Recorder {
+            max_bytes,
+            chunks: VecDeque::new(),
+            bytes: 0,
+            frames: 0,
+            last: None,
+            current: FrameState::default(),
+        }
+    }
+
+    /// Records the simulation's current frame. Call after every `update`;
+    /// calls that find the same frame as last time are ignored.
+    pub fn capture(&mut self, simulation: &Simulation) {
+        if self.last.as_ref().is_some_and(|last| last.frame == simulation.frame) {
+            return;
+        }
+        self.current.capture(simulation);
+
+        let keyframe = self.chunks.back().is_none_or(|chunk| {
+            chunk.offsets.len() >= KEYFRAME_INTERVAL || chunk.width != simulation.width || chunk.height != simulation.height
+        });
+        if keyframe {
+            self.chunks.push_back(Chunk {
+                width: simulation.width,
+                height: simulation.height,
+                offsets: Vec::new(),
+                bytes: Vec::new(),
+            });
+        }
+        let chunk = self.chunks.back_mut().unwrap();
+        let before = chunk.bytes.len();
+        chunk.offsets.push(before as u32);
+        self.current.encode(if keyframe { None } else { self.last.as_ref() }, &mut chunk.bytes);
+        self.bytes += chunk.bytes.len() - before;
+        self.frames += 1;
+        std::mem::swap(self.last.get_or_insert_with(FrameState::default), &mut self.current);
+
+        // Never drop the chunk being written.
+        while self.bytes > self.max_bytes && self.chunks.len() > 1 {
+            let chunk = self.chunks.pop_front().unwrap();
+            self.bytes -= chunk.bytes.len();
+            self.frames -= chunk.offsets.len();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+        self.bytes = 0;
+        self.frames = 0;
+        self.last = None;
+    }
+
+    pub fn get_frame_count(&self) -> usize {
+        self.frames
+    }
+
+    /// Encoded size of the recorded frames.
+    pub fn get_byte_size(&self) -> usize {
+        self.bytes
+    }
+
+    /// The recording as a self-contained trace file for `Player`.
+    pub fn export(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.bytes + 16 + self.chunks.len() * 16 + self.frames * 4);
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&TRACE_VERSION.to_le_bytes());
+        out.extend_from_slice(&POSITION_QUANTUM.to_le_bytes());
+        out.extend_from_slice(&(self.chunks.len() as u32).to_le_bytes());
+        for chunk in &self.chunks {
+            out.extend_from_slice(&chunk.width.to_le_bytes());
+            out.extend_from_slice(&chunk.height.to_le_bytes());
+            out.extend_from_slice(&(chunk.offsets.len() as u32).to_le_bytes());
+            for offset in &chunk.offsets {
+                out.extend_from_slice(&offset.to_le_bytes());
+            }
+            out.extend_from_slice(&(chunk.bytes.len() as u32).to_le_bytes());
+            out.extend_from_slice(&chunk.bytes);
+        }
+        out
+    }
+}
+
+/// Plays back an exported trace. Frames are exposed through the same
+/// `get_particles_ptr` interface as `Simulation`, with `prev_x`/`prev_y`
+/// equal to `x`/`y` and velocities zeroed.
+#[wasm_bindgen]
+pub struct Player {
+    chunks: Vec<Chunk>,
+    /// Index of each chunk's keyframe among all frames.
+    starts: Vec<usize>,
+    frames: usize,
+    position: Option<usize>,
+    state: FrameState,
+    export: Vec<Particle>,
+    buffer_generation: u32,
+}
+
+#[wasm_bindgen]
+impl Player {
+    /// Parses and checks every frame of `bytes` up front, so seeking can't fail.
+    #[wasm_bindgen(constructor)]
+    pub fn new(bytes: &[u8]) -> Result<Player, TraceError> {
+        let mut r = bytes;
+        if take(&mut r, MAGIC.len()).map_err(|_| TraceError::BadMagic)? != MAGIC {
+            return Err(TraceError::BadMagic);
+        }
+        let version = u16::from_le_bytes(take(&mut r, 2)?.try_into().unwrap());
+        if version != TRACE_VERSION {
+            return Err(TraceError::UnsupportedVersion(version));
+        }
+        if read_f32(&mut r)? != POSITION_QUANTUM {
+            return Err(TraceError::Invalid("unexpected position quantum"));
+        }
+        let chunk_count = read_u32(&mut r)? as usize;
+
+        let mut chunks = Vec::new();
+        let mut starts = Vec::new();
+        let mut frames = 0;
+        let mut state = FrameState::default();
+        for _ in 0..chunk_count {
+            let width = read_f32(&mut r)?;
+            let height = read_f32(&mut r)?;
+            let count = read_u32(&mut r)? as usize;
+            if count == 0 || count > r.len() / 4 {
+                return Err(TraceError::Invalid("bad frame count"));
+            }
+            let offsets: Vec<u32> = (0..count).map(|_| read_u32(&mut r)).collect::<Result<_, _>>()?;
+            let len = read_u32(&mut r)? as usize;
+            let chunk = Chunk { width, height, offsets, bytes: take(&mut r, len)?.to_vec() };
+            if chunk.offsets[0] != 0 || chunk.offsets.windows(2).any(|w| w[0] > w[1]) || chunk.offsets[count - 1] as usize > len {
+                return Err(TraceError::Invalid("bad frame offsets"));
+            }
+            for k in 0..count {
+                let mut frame = chunk.frame(k);
+                state.decode(&mut frame, k == 0)?;
+                if !frame.is_empty() {
+                    return Err(TraceError::Invalid("frame has trailing bytes"));
+                }
+            }
+            starts.push(frames);
+            frames += count;
+            chunks.push(chunk);
+        }
+        if !r.is_empty() {
+            return Err(TraceError::Invalid("trailing data"));
+        }
+
+        Ok(Player {
+            chunks,
+            starts,
+            frames,
+            position: None,
+            state: FrameState::default(),
+            export: Vec::new(),
+            buffer_generation: 0,
+        })
+    }
+
+    pub fn get_frame_count(&self) -> usize {
+        self.frames
+    }
+
+    /// Moves to recorded frame `index` (clamped to the recording). Stepping
+    /// forward by one decodes a single frame; any other jump decodes from
+    /// the nearest keyframe before `index`.
+    pub fn seek(&mut self, index: usize) {
+        if self.frames == 0 {
+            return;
+        }
+        let index = index.min(self.frames - 1);
+        let chunk = self.starts.partition_point(|&start| start <= index) - 1;
+        let start = self.starts[chunk];
+        let from = match self.position {
+            Some(position) if position <= index && position >= start => position + 1,
+            _ => start,
+        };
+        for k in from - start..=index - start {
+            let mut frame = self.chunks[chunk].frame(k);
+            self.state.decode(&mut frame, k == 0).expect("frames are checked on load");
+        }
+        self.position = Some(index);
+    }
+
+    /// Index of the current recorded frame.
+    pub fn get_position(&self) -> usize {
+        self.position.unwrap_or(0)
+    }
+
+    /// The simulation frame number the current frame was captured at.
+    pub fn get_frame(&self) -> u64 {
+        self.state.frame
+    }
+
+    pub fn get_width(&self) -> f32 {
+        self.current_chunk().map_or(0.0, |chunk| chunk.width)
+    }
+
+    pub fn get_height(&self) -> f32 {
+        self.current_chunk().map_or(0.0, |chunk| chunk.height)
+    }
+
+    pub fn get_particle_count(&self) -> usize {
+        self.state.x.len()
+    }
+
+    /// Packs the current frame into `Particle` records (see
+    /// `particle_layout`) and returns the buffer, exactly as
+    /// `Simulation::get_particles_ptr` does.
+    pub fn get_particles_ptr(&mut self) -> *const Particle {
+        let capacity = self.export.capacity();
+        self.export.clear();
+        self.export.extend((0..self.state.x.len()).map(|i| self.state.particle(i)));
+        if self.export.capacity() != capacity {
+            self.buffer_generation += 1;
+        }
+        self.export.as_ptr()
+    }
+
+    pub fn get_capacity(&self) -> usize {
+        self.export.capacity()
+    }
+
+    pub fn get_buffer_generation(&self) -> u32 {
+        self.buffer_generation
+    }
+}
+
+impl Player {
+    /// The current frame's particles, as `Simulation::particles` returns them.
+    pub fn particles(&self) -> Vec<Particle> {
+        (0..self.state.x.len()).map(|i| self.state.particle(i)).collect()
+    }
+
+    fn current_chunk(&self) -> Option<&Chunk> {
+        let index = self.position?;
+        Some(&self.chunks[self.starts.partition_point(|&start| start <= index) - 1])
+    }
+}
+
+fn zigzag(v: i32) -> u64 {
+    ((v << 1) ^ (v >> 31)) as u32 as u64
+}
+
+fn unzigzag(v: u64) -> i32 {
+    let v = v as u32;
+    ((v >> 1) as i32) ^ -((v & 1) as i32)
+}
+
+pub(crate) fn write_varint(out: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        out.push(v as u8 | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Result<u64, TraceError> {
+    let mut v = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = take(bytes, 1)?[0];
+        v |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(v);
+        }
+    }
+    Err(TraceError::Invalid("varint too long"))
+}
+
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8], TraceError> {
+    if bytes.len() < len {
+        return Err(TraceError::Truncated);
+    }
+    let (head, tail) = bytes.split_at(len);
+    *bytes = tail;
+    Ok(head)
+}
+
+fn read_u32(bytes: &mut &[u8]) -> Result<u32, TraceError> {
+    Ok(u32::from_le_bytes(take(bytes, 4)?.try_into().unwrap()))
+}
+
+fn read_f32(bytes: &mut &[u8]) -> Result<f32, TraceError> {
+    Ok(f32::from_le_bytes(take(bytes, 4)?.try_into().unwrap()))
+}
@@ -0,0 +1,159 @@
+//! Morton (Z-order) codes over a 16-bit grid, and the BIGMIN/LITMAX
+//! operations from Tropf & Herzog (1981) for range queries over a sorted
+//! array of codes. x occupies the even bits, y the odd bits.
+
+use std::ops::Range;
+
+pub const GRID_MAX: u32 = 0xFFFF;
+
+const X_BITS: u32 = 0x5555_5555;
+const Y_BITS: u32 = 0xAAAA_AAAA;
+
+pub struct ZOrder;
+
+impl ZOrder {
+    fn interleave(n: u32) -> u32 {
+        let n = (n | (n << 8)) & 0x00FF00FF;
+        let n = (n | (n << 4)) & 0x0F0F0F0F;
+        let n = (n | (n << 2)) & 0x33333333;
+        (n | (n << 1)) & 0x55555555
+    }
+
+    pub fn encode(x: u32, y: u32) -> u32 {
+        ZOrder::interleave(x) | (ZOrder::interleave(y) << 1)
+    }
+
+    /// Whether `z` lies in the rectangle spanned by the codes of its
+    /// lower-left (`zmin`) and upper-right (`zmax`) corners. Each
+    /// dimension's bits compare like the coordinate itself.
+    pub fn in_rect(z: u32, zmin: u32, zmax: u32) -> bool {
+        let (x, y) = (z & X_BITS, z & Y_BITS);
+        x >= zmin & X_BITS && x <= zmax & X_BITS && y >= zmin & Y_BITS && y <= zmax & Y_BITS
+    }
+
+    /// Smallest code greater than `z` that lies inside the rectangle
+    /// `[zmin, zmax]`. Only meaningful when `zmin < z < zmax` and `z` is
+    /// outside the rectangle.
+    pub fn bigmin(z: u32, mut zmin: u32, mut zmax: u32) -> u32 {
+        let mut bigmin = 0;
+        for bit in (0..32).rev() {
+            let mask = 1 << bit;
+            match (z & mask != 0, zmin & mask != 0, zmax & mask != 0) {
+                (false, false, true) => {
+                    bigmin = load_1000(zmin, bit);
+                    zmax = load_0111(zmax, bit);
+                }
+                (false, true, true) => return zmin,
+                (true, false, false) => return bigmin,
+                (true, false, true) => zmin = load_1000(zmin, bit),
+                _ => {}
+            }
+        }
+        bigmin
+    }
+
+    /// Largest code less than `z` that lies inside the rectangle
+    /// `[zmin, zmax]`, under the same conditions as `bigmin`.
+    pub fn litmax(z: u32, mut zmin: u32, mut zmax: u32) -> u32 {
+        let mut litmax = 0;
+        for bit in (0..32).rev() {
+            let mask = 1 << bit;
+            match (z & mask != 0, zmin & mask != 0, zmax & mask != 0) {
+                (false, false, true) => zmax = load_0111(zmax, bit),
+                (false, true, true) => return litmax,
+                (true, false, false) => return zmax,
+                (true, false, true) => {
+                    litmax = load_0111(zmax, bit);
+                    zmin = load_1000(zmin, bit);
+                }
+                _ => {}
+            }
+        }
+        litmax
+    }
+}
+
+/// Bits below `bit` that belong to the same dimension as `bit`.
+fn same_dimension_below(bit: u32) -> u32 {
+    let dimension = if bit & 1 == 0 { X_BITS } else { Y_BITS };
+    dimension & ((1u32 << bit) - 1)
+}
+
+/// Sets `bit` and clears the lower bits of its dimension ("1000...").
+fn load_1000(z: u32, bit: u32) -> u32 {
+    (z | (1 << bit)) & !same_dimension_below(bit)
+}
+
+/// Clears `bit` and sets the lower bits of its dimension ("0111...").
+fn load_0111(z: u32, bit: u32) -> u32 {
+    (z & !(1 << bit)) | same_dimension_below(bit)
+}
+
+fn grid_coord(value: f32, extent: f32) -> u32 {
+    ((value / extent) * GRID_MAX as f32).clamp(0.0, GRID_MAX as f32) as u32
+}
+
+/// Code of world position (x, y) in a `width` x `height` world. Positions
+/// outside the world are clamped to its edge.
+pub fn code_at(x: f32, y: f32, width: f32, height: f32) -> u32 {
+    ZOrder::encode(grid_coord(x, width), grid_coord(y, height))
+}
+
+/// Codes of the corners of the square reaching `reach` from (x, y), padded
+/// by a grid cell so float rounding can't clip a neighbour.
+pub fn query_bounds(x: f32, y: f32, reach: f32, width: f32, height: f32) -> (u32, u32) {
+    let x0 = grid_coord(x - reach, width).saturating_sub(1);
+    let y0 = grid_coord(y - reach, height).saturating_sub(1);
+    let x1 = (grid_coord(x + reach, width) + 1).min(GRID_MAX);
+    let y1 = (grid_coord(y + reach, height) + 1).min(GRID_MAX);
+    (ZOrder::encode(x0, y0), ZOrder::encode(x1, y1))
+}
+
+/// Visits, in order, the index of every code in `codes[start..]` that lies in
+/// the rectangle `[zmin, zmax]`, as runs of consecutive indices. `codes` must
+/// be sorted. After `tolerance` consecutive codes outside the rectangle the
+/// scan stops stepping and jumps to the first code at or above BIGMIN.
+pub fn scan_rect(codes: &[u32], start: usize, zmin: u32, zmax: u32, tolerance: usize, mut visit: impl FnMut(Range<usize>)) {
+    let mut k = start + codes[start.min(codes.len())..].partition_point(|&z| z < zmin);
+    let mut run = k;
+    let mut misses = 0;
+    while k < codes.len() {
+        let z = codes[k];
+        if z > zmax {
+            break;
+        }
+        if ZOrder::in_rect(z, zmin, zmax) {
+            misses = 0;
+            k += 1;
+            continue;
+        }
+        if run < k {
+            visit(run..k);
+        }
+        if misses < tolerance {
+            misses += 1;
+            k += 1;
+        } else {
+            let next = ZOrder::bigmin(z, zmin, zmax);
+            k = gallop(codes, k + 1, next);
+            misses = 0;
+        }
+        run = k;
+    }
+    if run < k {
+        visit(run..k);
+    }
+}
+
+/// First index at or after `from` whose code is >= `target`. Gallops before
+/// bisecting, since the target is usually only a few entries ahead.
+fn gallop(codes: &[u32], from: usize, target: u32) -> usize {
+    let mut lo = from;
+    let mut step = 1;
+    while lo + step < codes.len() && codes[lo + step] < target {
+        lo += step;
+        step *= 2;
+    }
+    let hi = (lo + step + 1).min(codes.len());
+    lo + codes[lo..hi].partition_point(|&z| z < target)
+}
@@ -0,0 +1,151 @@
+mod common;
+
+use common::place;
+use particles_wasm::{BoundaryMode, Simulation};
+
+fn world(width: f32, height: f32, mode: BoundaryMode) -> Simulation {
+    let mut simulation = common::world(width, height);
+    assert!(simulation.set_boundary(mode));
+    simulation
+}
+
+#[test]
+fn periodic_worlds_wrap_and_collide_across_the_seams() {
+    let mut simulation = world(800.0, 600.0, BoundaryMode::Periodic);
+    let id = place(&mut simulation, 798.0, 598.0, 4.0, 3.0);
+    simulation.step();
+    let particle = simulation.get_particle(id).unwrap();
+    assert_eq!((particle.x, particle.y), (2.0, 1.0));
+    assert_eq!((particle.prev_x, particle.prev_y), (-2.0, -2.0));
+
+    // Head on across each seam.
+    let mut simulation = world(800.0, 600.0, BoundaryMode::Periodic);
+    let a = place(&mut simulation, 3.0, 10.0, -1.0, 0.0);
+    let b = place(&mut simulation, 796.0, 10.0, 1.0, 0.0);
+    let c = place(&mut simulation, 400.0, 597.0, 0.0, 0.5);
+    let d = place(&mut simulation, 400.0, 4.0, 0.0, -0.5);
+    simulation.step();
+    let velocity = |id| {
+        let particle = simulation.get_particle(id).unwrap();
+        (particle.vx, particle.vy)
+    };
+    assert_eq!([velocity(a), velocity(b)], [(1.0, 0.0), (-1.0, 0.0)]);
+    assert_eq!([velocity(c), velocity(d)], [(0.0, -0.5), (0.0, 0.5)]);
+
+    // Two cells across, so each column's neighbours across the seams are
+    // the other column.
+    let mut simulation = world(20.0, 20.0, BoundaryMode::Periodic);
+    let a = place(&mut simulation, 3.0, 10.0, -1.0, 0.0);
+    let b = place(&mut simulation, 16.0, 10.0, 1.0, 0.0);
+    simulation.step();
+    assert_eq!(simulation.get_particle(a).unwrap().vx, 1.0);
+    assert_eq!(simulation.get_particle(b).unwrap().vx, -1.0);
+}
+
+#[test]
+fn periodic_collisions_do_not_depend_on_threads() {
+    // From 40 diameters across down to less than one, where a particle
+    // can meet another across both seams at once.
+    for (width, height, count) in [(320.0, 240.0, 150), (20.0, 300.0, 40), (6.0, 10.0, 2)] {
+        let run = |threads| {
+            let mut simulation = Simulation::new(width, height, count, 2.0, 4.0, 4.0, 0.0, 1.0, 0.0, 3, 3);
+            simulation.clear_emitters();
+            assert!(simulation.set_boundary(BoundaryMode::Periodic));
+            simulation.set_threads(threads);
+            for _ in 0..30 {
+                simulation.step();
+            }
+            simulation.particles()
+        };
+        let single = run(1);
+        assert!(single.iter().all(|p| p.x.is_finite() && p.y.is_finite()));
+        assert_eq!(run(4), single, "{width} × {height}");
+    }
+}
+
+#[test]
+fn open_worlds_remove_particles_that_leave() {
+    let mut simulation = world(800.0, 600.0, BoundaryMode::Open);
+    let leaving = place(&mut simulation, 798.0, 300.0, 5.0, 0.0);
+    let staying = place(&mut simulation, 400.0, 300.0, 5.0, 0.0);
+    simulation.step();
+    assert!(simulation.get_particle(leaving).is_some());
+    simulation.step();
+    assert!(simulation.get_particle(leaving).is_none());
+    assert_eq!(simulation.get_slot(staying), Some(0));
+    assert_eq!(simulation.get_particle_count(), 1);
+}
+
+#[test]
+fn containers_keep_particles_in() {
+    let mut simulation = Simulation::new(800.0, 600.0, 300, 2.0, 6.0, 8.0, 0.3, 0.8, 0.0, 3, 7);
+    simulation.clear_emitters();
+    assert!(!simulation.set_boundary(BoundaryMode::Polygon));
+    assert!(simulation.set_boundary(BoundaryMode::Circle));
+    assert_eq!(simulation.get_boundary_points(), [400.0, 300.0, 300.0]);
+    for _ in 0..30 {
+        simulation.step();
+    }
+    for p in simulation.particles() {
+        let d = ((p.x - 400.0).powi(2) + (p.y - 300.0).powi(2)).sqrt();
+        // Collisions in the pile at the bottom can push a particle into
+        // the edge after the container has acted, but never past it.
+        assert!(d <= 300.0 + p.radius, "{d}");
+    }
+
+    // A U, open at the top.
+    let u = [100.0, 100.0, 300.0, 100.0, 300.0, 400.0, 500.0, 400.0, 500.0, 100.0, 700.0, 100.0, 700.0, 550.0, 100.0, 550.0];
+    assert!(!simulation.set_boundary_polygon(&u[..4]));
+    assert!(simulation.set_boundary_polygon(&u));
+    assert_eq!(simulation.get_boundary(), BoundaryMode::Polygon);
+    assert_eq!(simulation.get_boundary_points(), u);
+    for _ in 0..30 {
+        simulation.step();
+    }
+    let inside = |x: f32, y: f32| {
+        let in_box = (100.0..=700.0).contains(&x) && (100.0..=550.0).contains(&y);
+        let in_gap = x > 300.0 && x < 500.0 && y < 400.0;
+        in_box && !in_gap
+    };
+    for p in simulation.particles() {
+        assert!(inside(p.x, p.y), "{}, {}", p.x, p.y);
+    }
+
+    assert!(simulation.set_boundary(BoundaryMode::Walls));
+    assert!(simulation.get_boundary_points().is_empty());
+    assert!(simulation.set_boundary(BoundaryMode::Polygon));
+}
+
+#[test]
+fn particles_bigger_than_a_circle_stay_at_its_centre() {
+    let mut simulation = world(20.0, 20.0, BoundaryMode::Circle);
+    let id = place(&mut simulation, 14.0, 10.0, 1.0, 0.5);
+    let mut particle = simulation.get_particle(id).unwrap();
+    particle.radius = 15.0;
+    simulation.set_particle(id, &particle);
+    for _ in 0..5 {
+        simulation.step();
+        let particle = simulation.get_particle(id).unwrap();
+        assert_eq!((particle.x, particle.y), (10.0, 10.0));
+    }
+}
+
+#[test]
+fn snapshots_keep_the_boundary() {
+    let mut original = Simulation::new(800.0, 600.0, 800, 2.0, 9.0, 6.0, 0.2, 0.9, 0.5, 3, 21);
+    original.set_boundary_polygon(&[0.0, 0.0, 800.0, 100.0, 700.0, 600.0, 100.0, 500.0]);
+    original.set_boundary(BoundaryMode::Periodic);
+    for _ in 0..10 {
+        original.step();
+    }
+    let mut restored = Simulation::new(100.0, 100.0, 0, 1.0, 2.0, 1.0, 0.0, 1.0, 0.0, 1, 1);
+    restored.restore(&original.snapshot()).unwrap();
+    assert_eq!(restored.get_boundary(), BoundaryMode::Periodic);
+    for _ in 0..30 {
+        original.step();
+        restored.step();
+    }
+    assert_eq!(restored.particles(), original.particles());
+    assert!(restored.set_boundary(BoundaryMode::Polygon));
+    assert_eq!(restored.get_boundary_points()[2..4], [800.0, 100.0]);
+}
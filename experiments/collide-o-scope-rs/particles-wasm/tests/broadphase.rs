@@ -0,0 +1,59 @@
+use particles_wasm::broadphase::BroadphaseKind;
+use particles_wasm::Simulation;
+
+const KINDS: [BroadphaseKind; 4] = [
+    BroadphaseKind::ZOrder,
+    BroadphaseKind::UniformGrid,
+    BroadphaseKind::LooseQuadtree,
+    BroadphaseKind::BruteForce,
+];
+
+fn simulation(kind: BroadphaseKind) -> Simulation {
+    let mut simulation = Simulation::new(800.0, 600.0, 1500, 2.0, 9.0, 6.0, 0.2, 0.7, 0.5, 3, 8);
+    simulation.set_broadphase(kind);
+    simulation
+}
+
+#[test]
+fn every_broadphase_finds_every_collision() {
+    for kind in KINDS {
+        let mut simulation = simulation(kind);
+        for _ in 0..20 {
+            simulation.step();
+            assert_eq!(simulation.count_missed_collisions(), 0, "{kind:?}");
+        }
+    }
+}
+
+#[test]
+fn every_broadphase_gives_the_same_response() {
+    let mut reference = simulation(BroadphaseKind::BruteForce);
+    let mut others: Vec<Simulation> = KINDS[..3].iter().map(|&kind| simulation(kind)).collect();
+    for _ in 0..60 {
+        reference.step();
+        for other in others.iter_mut() {
+            other.step();
+            assert_eq!(other.particles(), reference.particles(), "{:?}", other.get_broadphase());
+        }
+    }
+}
+
+#[test]
+fn every_broadphase_reports_the_same_contacts() {
+    let mut simulations = KINDS.map(simulation);
+    for _ in 0..10 {
+        for simulation in &mut simulations {
+            simulation.step();
+        }
+        let stats = simulations.each_ref().map(|simulation| simulation.get_stats());
+        let brute_force = stats[3];
+        // One collision pass per step, testing every pair once.
+        assert_eq!(brute_force.candidate_pairs, 1500 * 1499 / 2);
+        for (kind, stats) in KINDS.iter().zip(stats) {
+            assert_eq!(stats.steps, 1);
+            assert_eq!(stats.contacts, brute_force.contacts, "{kind:?}");
+            assert!(stats.candidate_pairs >= stats.contacts, "{kind:?}");
+        }
+        assert!(stats[0].candidate_pairs < brute_force.candidate_pairs / 100);
+    }
+}
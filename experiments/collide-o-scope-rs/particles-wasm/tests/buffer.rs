@@ -0,0 +1,73 @@
+use particles_wasm::Simulation;
+
+#[test]
+fn reserved_buffer_survives_reset_and_adds() {
+    let mut simulation = Simulation::new(800.0, 600.0, 100, 4.0, 12.0, 4.0, 0.0, 0.7, 0.5, 3, 1);
+    simulation.reserve(1000);
+    let ptr = simulation.get_particles_ptr();
+    let generation = simulation.get_buffer_generation();
+
+    simulation.set_particle_count(900);
+    simulation.reset();
+    for i in 0..100 {
+        simulation.add_particle(i as f32 * 8.0, 300.0);
+        simulation.step();
+    }
+    assert_eq!(simulation.get_particle_count(), 1000);
+    assert_eq!(simulation.get_particles_ptr(), ptr);
+    assert_eq!(simulation.get_buffer_generation(), generation);
+}
+
+#[test]
+fn growing_past_capacity_bumps_the_generation() {
+    let mut simulation = Simulation::new(800.0, 600.0, 10, 4.0, 12.0, 4.0, 0.0, 0.7, 0.5, 3, 1);
+    let generation = simulation.get_buffer_generation();
+    let capacity = simulation.get_capacity();
+    for _ in 0..=capacity {
+        simulation.add_particle(400.0, 300.0);
+    }
+    assert!(simulation.get_buffer_generation() > generation);
+}
+
+fn pointers(simulation: &mut Simulation) -> [usize; 13] {
+    [
+        simulation.get_particles_ptr() as usize,
+        simulation.get_x_ptr() as usize,
+        simulation.get_y_ptr() as usize,
+        simulation.get_prev_x_ptr() as usize,
+        simulation.get_prev_y_ptr() as usize,
+        simulation.get_vx_ptr() as usize,
+        simulation.get_vy_ptr() as usize,
+        simulation.get_radii_ptr() as usize,
+        simulation.get_masses_ptr() as usize,
+        simulation.get_temperatures_ptr() as usize,
+        simulation.get_colliding_ptr() as usize,
+        simulation.get_ids_ptr() as usize,
+        simulation.get_order_ptr() as usize,
+    ]
+}
+
+#[test]
+fn no_pointer_moves_without_a_generation_bump() {
+    let mut simulation = Simulation::new(800.0, 600.0, 3, 4.0, 12.0, 4.0, 0.0, 0.7, 0.5, 3, 1);
+    let mut before = pointers(&mut simulation);
+    let mut generation = simulation.get_buffer_generation();
+    let mut bumps = 0;
+    for i in 0..300 {
+        simulation.add_particle(100.0 + (i % 60) as f32 * 10.0, 100.0 + (i / 60) as f32 * 30.0);
+        simulation.step();
+        let after = pointers(&mut simulation);
+        if simulation.get_buffer_generation() == generation {
+            assert_eq!(after, before, "moved without a bump after {} adds", i + 1);
+        } else {
+            bumps += 1;
+        }
+        (before, generation) = (after, simulation.get_buffer_generation());
+    }
+    assert!(bumps > 0);
+    let snapshot = simulation.snapshot();
+    let mut restored = Simulation::new(800.0, 600.0, 0, 4.0, 12.0, 4.0, 0.0, 0.7, 0.5, 3, 1);
+    let (before, generation) = (pointers(&mut restored), restored.get_buffer_generation());
+    restored.restore(&snapshot).unwrap();
+    assert!(pointers(&mut restored) == before || restored.get_buffer_generation() != generation);
+}
@@ -0,0 +1,24 @@
+//! Fixtures shared by the integration tests. Each test file uses only some.
+#![allow(dead_code)]
+
+pub mod snapshot;
+
+use particles_wasm::Simulation;
+
+/// An empty world for particles of radius 4, with no emitters, gravity or
+/// energy loss.
+pub fn world(width: f32, height: f32) -> Simulation {
+    let mut simulation = Simulation::new(width, height, 0, 4.0, 4.0, 0.0, 0.0, 1.0, 0.0, 3, 1);
+    simulation.clear_emitters();
+    simulation
+}
+
+/// Adds a particle at exactly (x, y), moving at (vx, vy), and returns its id.
+pub fn place(simulation: &mut Simulation, x: f32, y: f32, vx: f32, vy: f32) -> u32 {
+    simulation.add_particle(x, y);
+    let id = simulation.particles().last().unwrap().id;
+    let mut particle = simulation.get_particle(id).unwrap();
+    (particle.x, particle.y, particle.prev_x, particle.prev_y, particle.vx, particle.vy) = (x, y, x, y, vx, vy);
+    simulation.set_particle(id, &particle);
+    id
+}
@@ -0,0 +1,156 @@
+//! A snapshot taken apart into its sections, so tests can corrupt one field
+//! or write the same state in an older version's layout without knowing
+//! where anything sits in the bytes. Only the sections tests look into are
+//! decoded; the header and the bodies stay raw.
+
+use particles_wasm::SNAPSHOT_VERSION;
+
+/// Width through frame: 12 four-byte and 5 eight-byte fields.
+const HEADER_BYTES: usize = 12 * 4 + 5 * 8;
+
+#[derive(Clone)]
+pub struct Snapshot {
+    pub header: Vec<u8>,
+    pub next_id: u32,
+    /// x, y, vx, vy, radius, color_temp, prev_x, prev_y, impulse_x and
+    /// impulse_y, one array each.
+    pub floats: [Vec<f32>; 10],
+    pub z_code: Vec<u32>,
+    pub id: Vec<u32>,
+    pub colliding: Vec<u8>,
+    pub order: Vec<u32>,
+    pub next_emitter_id: u32,
+    /// Id, kind and x, y, angle, width, reach, strength.
+    pub emitters: Vec<(u32, u8, [f32; 6])>,
+    pub next_obstacle_id: u32,
+    /// Id, kind and points.
+    pub obstacles: Vec<(u32, u8, Vec<f32>)>,
+    pub boundary: u8,
+    pub container: Vec<f32>,
+    /// Everything from the next body id on.
+    pub bodies: Vec<u8>,
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn take(&mut self, n: usize) -> &[u8] {
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        head
+    }
+
+    fn u8(&mut self) -> u8 {
+        self.take(1)[0]
+    }
+
+    fn u32(&mut self) -> u32 {
+        u32::from_le_bytes(self.take(4).try_into().unwrap())
+    }
+
+    fn f32(&mut self) -> f32 {
+        f32::from_le_bytes(self.take(4).try_into().unwrap())
+    }
+}
+
+impl Snapshot {
+    /// Splits a snapshot of the current version, checking that writing it
+    /// back gives the same bytes.
+    pub fn parse(bytes: &[u8]) -> Snapshot {
+        let mut r = Reader(bytes);
+        assert_eq!(r.take(4), b"CSNP");
+        assert_eq!(u16::from_le_bytes(r.take(2).try_into().unwrap()), SNAPSHOT_VERSION);
+        let header = r.take(HEADER_BYTES).to_vec();
+        let next_id = r.u32();
+        let n = r.u32() as usize;
+        let floats = std::array::from_fn(|_| (0..n).map(|_| r.f32()).collect());
+        let z_code = (0..n).map(|_| r.u32()).collect();
+        let id = (0..n).map(|_| r.u32()).collect();
+        let colliding = r.take(n).to_vec();
+        let m = r.u32() as usize;
+        let order = (0..m).map(|_| r.u32()).collect();
+        let next_emitter_id = r.u32();
+        let k = r.u32();
+        let emitters = (0..k).map(|_| (r.u32(), r.u8(), std::array::from_fn(|_| r.f32()))).collect();
+        let next_obstacle_id = r.u32();
+        let o = r.u32();
+        let obstacles = (0..o)
+            .map(|_| {
+                let (id, kind, len) = (r.u32(), r.u8(), r.u32());
+                (id, kind, (0..len).map(|_| r.f32()).collect())
+            })
+            .collect();
+        let boundary = r.u8();
+        let len = r.u32();
+        let container = (0..len).map(|_| r.f32()).collect();
+        let bodies = r.0.to_vec();
+        let snapshot = Snapshot {
+            header,
+            next_id,
+            floats,
+            z_code,
+            id,
+            colliding,
+            order,
+            next_emitter_id,
+            emitters,
+            next_obstacle_id,
+            obstacles,
+            boundary,
+            container,
+            bodies,
+        };
+        assert_eq!(snapshot.write(SNAPSHOT_VERSION), bytes);
+        snapshot
+    }
+
+    /// The bytes `version` would have stored, leaving out the sections it
+    /// didn't have yet.
+    pub fn write(&self, version: u16) -> Vec<u8> {
+        let mut w = b"CSNP".to_vec();
+        w.extend(version.to_le_bytes());
+        w.extend(&self.header);
+        if version >= 2 {
+            w.extend(self.next_id.to_le_bytes());
+        }
+        w.extend((self.z_code.len() as u32).to_le_bytes());
+        for values in &self.floats {
+            w.extend(values.iter().flat_map(|v| v.to_le_bytes()));
+        }
+        w.extend(self.z_code.iter().flat_map(|v| v.to_le_bytes()));
+        if version >= 2 {
+            w.extend(self.id.iter().flat_map(|v| v.to_le_bytes()));
+        }
+        w.extend(&self.colliding);
+        w.extend((self.order.len() as u32).to_le_bytes());
+        w.extend(self.order.iter().flat_map(|v| v.to_le_bytes()));
+        if version >= 3 {
+            w.extend(self.next_emitter_id.to_le_bytes());
+            w.extend((self.emitters.len() as u32).to_le_bytes());
+            for (id, kind, values) in &self.emitters {
+                w.extend(id.to_le_bytes());
+                w.push(*kind);
+                w.extend(values.iter().flat_map(|v| v.to_le_bytes()));
+            }
+        }
+        if version >= 4 {
+            w.extend(self.next_obstacle_id.to_le_bytes());
+            w.extend((self.obstacles.len() as u32).to_le_bytes());
+            for (id, kind, points) in &self.obstacles {
+                w.extend(id.to_le_bytes());
+                w.push(*kind);
+                w.extend((points.len() as u32).to_le_bytes());
+                w.extend(points.iter().flat_map(|v| v.to_le_bytes()));
+            }
+        }
+        if version >= 5 {
+            w.push(self.boundary);
+            w.extend((self.container.len() as u32).to_le_bytes());
+            w.extend(self.container.iter().flat_map(|v| v.to_le_bytes()));
+        }
+        if version >= 6 {
+            w.extend(&self.bodies);
+        }
+        w
+    }
+}
@@ -0,0 +1,42 @@
+use particles_wasm::Simulation;
+
+fn simulation(seed: u64) -> Simulation {
+    Simulation::new(800.0, 600.0, 500, 4.0, 12.0, 4.0, 0.1, 0.7, 0.5, 3, seed)
+}
+
+#[test]
+fn same_seed_gives_same_layout() {
+    let a = simulation(42);
+    let b = simulation(42);
+    assert_eq!(a.particles(), b.particles());
+}
+
+#[test]
+fn different_seeds_give_different_layouts() {
+    let a = simulation(1);
+    let b = simulation(2);
+    assert_ne!(a.particles(), b.particles());
+}
+
+#[test]
+fn same_seed_gives_same_trajectory() {
+    let mut a = simulation(7);
+    let mut b = simulation(7);
+    for _ in 0..200 {
+        a.step();
+        b.step();
+    }
+    assert_eq!(a.particles(), b.particles());
+}
+
+#[test]
+fn reseed_and_reset_reproduce_a_fresh_simulation() {
+    let mut a = simulation(3);
+    for _ in 0..50 {
+        a.step();
+    }
+    a.add_particle(100.0, 100.0);
+    a.reseed(9);
+    a.reset();
+    assert_eq!(a.particles(), simulation(9).particles());
+}
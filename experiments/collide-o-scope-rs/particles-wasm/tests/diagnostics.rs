@@ -0,0 +1,73 @@
+use particles_wasm::Simulation;
+
+fn simulation(particle_count: usize, gravity: f32, elasticity: f32) -> Simulation {
+    let mut simulation = Simulation::new(800.0, 600.0, particle_count, 2.0, 9.0, 6.0, gravity, elasticity, 0.0, 3, 7);
+    simulation.set_substeps(2);
+    simulation
+}
+
+#[test]
+fn lost_energy_accounts_for_every_change_in_kinetic_energy() {
+    for elasticity in [1.0, 0.7, 0.0] {
+        let mut simulation = simulation(1500, 0.0, elasticity);
+        let start = simulation.diagnostics().kinetic_energy;
+        let mut collisions = 0;
+        for _ in 0..120 {
+            simulation.step();
+            collisions += simulation.diagnostics().collisions;
+        }
+        let end = simulation.diagnostics();
+        assert!(collisions > 0);
+        let balance = start - end.kinetic_energy - end.total_energy_lost;
+        assert!(balance.abs() < start * 1e-4, "elasticity {elasticity}: {balance} of {start}");
+        if elasticity < 1.0 {
+            assert!(end.total_energy_lost > start * 0.1, "elasticity {elasticity}");
+        }
+    }
+}
+
+#[test]
+fn elastic_wall_bounces_conserve_energy() {
+    let mut simulation = Simulation::new(800.0, 600.0, 4, 1.0, 2.0, 6.0, 0.0, 1.0, 0.0, 3, 7);
+    let start = simulation.diagnostics();
+    for _ in 0..600 {
+        simulation.step();
+        assert_eq!(simulation.diagnostics().collisions, 0);
+    }
+    let end = simulation.diagnostics();
+    assert_eq!(end.total_energy_lost, 0.0);
+    assert!((end.kinetic_energy - start.kinetic_energy).abs() < start.kinetic_energy * 1e-6);
+    assert_eq!(end.mean_speed, start.mean_speed);
+}
+
+#[test]
+fn potential_energy_turns_into_kinetic_energy() {
+    let mut simulation = simulation(8, 0.2, 1.0);
+    let start = simulation.diagnostics();
+    assert!(start.potential_energy > 0.0);
+    for _ in 0..20 {
+        simulation.step();
+    }
+    let end = simulation.diagnostics();
+    let total = |d: &particles_wasm::Diagnostics| d.kinetic_energy + d.potential_energy;
+    assert!(end.potential_energy < start.potential_energy);
+    assert!((total(&end) - total(&start)).abs() < total(&start) * 0.01);
+}
+
+#[test]
+fn elastic_gas_relaxes_to_maxwell_boltzmann() {
+    let mut simulation = simulation(1500, 0.0, 1.0);
+    for _ in 0..300 {
+        simulation.step();
+    }
+    let bins = 20;
+    let max_speed = (simulation.diagnostics().mean_speed * 4.0) as f32;
+    let counts = simulation.speed_histogram(bins, max_speed);
+    let expected = simulation.maxwell_boltzmann_histogram(bins, max_speed);
+    assert_eq!(counts.iter().sum::<u32>(), 1500);
+    assert!((expected.iter().sum::<f64>() - 1500.0).abs() < 1e-6);
+
+    // Share of particles that would have to change bin to match the fit.
+    let distance = counts.iter().zip(&expected).map(|(&c, &e)| (c as f64 - e).abs()).sum::<f64>() / 2.0 / 1500.0;
+    assert!(distance < 0.1, "{distance}");
+}
@@ -0,0 +1,44 @@
+use particles_wasm::Simulation;
+
+fn simulation(max_events: usize) -> Simulation {
+    let mut simulation = Simulation::new(800.0, 600.0, 1500, 2.0, 9.0, 6.0, 0.2, 0.7, 0.5, 3, 9);
+    simulation.set_substeps(2);
+    simulation.set_event_capacity(max_events);
+    simulation
+}
+
+#[test]
+fn every_resolved_collision_is_recorded_below_the_cap() {
+    let mut off = simulation(0);
+    let mut on = simulation(100_000);
+    for _ in 0..30 {
+        off.step();
+        on.step();
+        assert_eq!(off.get_event_count(), 0);
+        assert_eq!(on.get_event_count(), on.diagnostics().collisions as usize);
+        // Recording never changes the simulation.
+        assert_eq!(on.particles(), off.particles());
+
+        let particles = on.particles();
+        for event in on.collision_events() {
+            assert!(event.impulse >= 0.0);
+            assert!((event.nx.hypot(event.ny) - 1.0).abs() < 1e-4);
+            assert!(event.a != event.b && (event.b as usize) < particles.len());
+        }
+        let impulses: Vec<f32> = on.collision_events().iter().map(|event| event.impulse).collect();
+        assert!(impulses.windows(2).all(|pair| pair[0] >= pair[1]));
+    }
+}
+
+#[test]
+fn the_strongest_events_are_kept() {
+    let mut all = simulation(100_000);
+    let mut capped = simulation(16);
+    for _ in 0..30 {
+        all.update(2.0 / 60.0);
+        capped.update(2.0 / 60.0);
+        let expected = &all.collision_events()[..all.get_event_count().min(16)];
+        assert_eq!(capped.collision_events(), expected);
+    }
+    assert_eq!(capped.get_event_count(), 16);
+}
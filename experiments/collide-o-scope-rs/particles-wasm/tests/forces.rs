@@ -0,0 +1,101 @@
+use std::f32::consts::FRAC_PI_2;
+
+mod common;
+
+use common::{place, world};
+use particles_wasm::{Emitter, EmitterKind, Simulation};
+
+/// Velocity after one step of a particle starting at rest at (x, y).
+fn push(emitter: Emitter, x: f32, y: f32) -> (f32, f32) {
+    let mut simulation = world(800.0, 600.0);
+    simulation.add_emitter(&emitter);
+    place(&mut simulation, x, y, 0.0, 0.0);
+    simulation.step();
+    let particle = simulation.get_particle(0).unwrap();
+    (particle.vx, particle.vy)
+}
+
+#[test]
+fn each_kind_pushes_the_right_way() {
+    let point = |kind| Emitter::new(kind, 400.0, 300.0, 0.0, 0.0, 100.0, 1.0);
+    let (vx, vy) = push(point(EmitterKind::Attractor), 450.0, 300.0);
+    assert!((vx + 0.5).abs() < 1e-5 && vy.abs() < 1e-5, "{vx}, {vy}");
+    let (vx, vy) = push(point(EmitterKind::Repulsor), 400.0, 375.0);
+    assert!(vx.abs() < 1e-5 && (vy - 0.25).abs() < 1e-5, "{vx}, {vy}");
+    // Clockwise on screen: below the centre is moving left.
+    let (vx, vy) = push(point(EmitterKind::Vortex), 400.0, 350.0);
+    assert!((vx + 0.5).abs() < 1e-5 && vy.abs() < 1e-5, "{vx}, {vy}");
+    assert_eq!(push(point(EmitterKind::Attractor), 550.0, 300.0), (0.0, 0.0));
+
+    let band = |kind| Emitter::new(kind, 100.0, 300.0, 0.0, 100.0, 400.0, 0.5);
+    let (vx, vy) = push(band(EmitterKind::Wind), 400.0, 330.0);
+    assert!((vx - 0.5).abs() < 1e-5 && vy.abs() < 1e-5, "{vx}, {vy}");
+    let (vx, vy) = push(band(EmitterKind::Fan), 200.0, 325.0);
+    assert!((vx - 0.5 * 0.75 * 0.5).abs() < 1e-5 && vy.abs() < 1e-5, "{vx}, {vy}");
+    assert_eq!(push(band(EmitterKind::Wind), 400.0, 360.0), (0.0, 0.0));
+    assert_eq!(push(band(EmitterKind::Fan), 50.0, 300.0), (0.0, 0.0));
+
+    let up = Emitter::new(EmitterKind::Wind, 400.0, 600.0, -FRAC_PI_2, 100.0, 300.0, 0.5);
+    let (vx, vy) = push(up, 400.0, 500.0);
+    assert!(vx.abs() < 1e-5 && (vy + 0.5).abs() < 1e-5, "{vx}, {vy}");
+}
+
+#[test]
+fn fan_speed_drives_the_default_fan() {
+    let mut simulation = Simulation::new(800.0, 600.0, 10, 4.0, 12.0, 4.0, 0.0, 0.7, 0.5, 3, 1);
+    assert_eq!(simulation.get_emitter_ids(), [0]);
+    let fan = simulation.get_emitter(0).unwrap();
+    assert_eq!((fan.kind, fan.x, fan.y, fan.width, fan.reach, fan.strength), (EmitterKind::Fan, 400.0, 600.0, 32.0, 300.0, 0.75));
+
+    simulation.set_fan_speed(2.0);
+    assert_eq!(simulation.get_emitter(0).unwrap().strength, 3.0);
+
+    let wind = simulation.add_emitter(&Emitter::new(EmitterKind::Wind, 0.0, 0.0, 0.0, 100.0, 100.0, 1.0));
+    assert!(simulation.remove_emitter(0));
+    assert!(!simulation.remove_emitter(0));
+    simulation.set_fan_speed(1.0);
+    assert_eq!(simulation.get_fan_speed(), 1.0);
+    assert_eq!(simulation.get_emitter_ids(), [wind]);
+    assert!(!simulation.set_emitter(0, &fan));
+}
+
+fn stirred(simd: bool) -> Simulation {
+    let mut simulation = Simulation::new(800.0, 600.0, 1001, 2.0, 9.0, 6.0, 0.1, 0.8, 0.5, 3, 9);
+    simulation.set_simd(simd);
+    simulation.add_emitter(&Emitter::new(EmitterKind::Attractor, 200.0, 200.0, 0.0, 0.0, 150.0, 0.3));
+    simulation.add_emitter(&Emitter::new(EmitterKind::Repulsor, 600.0, 200.0, 0.0, 0.0, 120.0, 0.4));
+    simulation.add_emitter(&Emitter::new(EmitterKind::Vortex, 400.0, 400.0, 0.0, 0.0, 200.0, -0.2));
+    simulation.add_emitter(&Emitter::new(EmitterKind::Wind, 0.0, 100.0, 0.3, 150.0, 800.0, 0.05));
+    simulation.add_emitter(&Emitter::new(EmitterKind::Fan, 800.0, 300.0, 3.0, 80.0, 400.0, 0.6));
+    simulation
+}
+
+#[test]
+fn simd_and_scalar_emitters_agree_exactly() {
+    let (mut scalar, mut lanes) = (stirred(false), stirred(true));
+    for _ in 0..60 {
+        scalar.step();
+        lanes.step();
+    }
+    assert_eq!(scalar.particles(), lanes.particles());
+}
+
+#[test]
+fn snapshots_keep_emitters() {
+    let mut original = stirred(false);
+    original.remove_emitter(2);
+    let bytes = original.snapshot();
+    let mut restored = world(800.0, 600.0);
+    restored.restore(&bytes).unwrap();
+    assert_eq!(restored.get_emitter_ids(), [0, 1, 3, 4, 5]);
+    for id in restored.get_emitter_ids() {
+        assert_eq!(restored.get_emitter(id), original.get_emitter(id));
+    }
+    assert_eq!(restored.add_emitter(&Emitter::new(EmitterKind::Vortex, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0)), 6);
+
+    for _ in 0..30 {
+        original.step();
+        restored.step();
+    }
+    assert_eq!(restored.particles(), original.particles());
+}
@@ -0,0 +1,117 @@
+use particles_wasm::Simulation;
+
+fn simulation(seed: u64) -> Simulation {
+    let mut simulation = Simulation::new(800.0, 600.0, 1000, 2.0, 9.0, 6.0, 0.2, 0.7, 0.5, 3, seed);
+    simulation.set_substeps(2);
+    simulation
+}
+
+/// Steps `frames` times, returning the snapshot taken before each step.
+fn run(simulation: &mut Simulation, frames: usize) -> Vec<Vec<u8>> {
+    (0..frames)
+        .map(|_| {
+            let before = simulation.snapshot();
+            simulation.step();
+            before
+        })
+        .collect()
+}
+
+#[test]
+fn step_back_restores_every_state_and_replays_exactly() {
+    let mut simulation = simulation(3);
+    simulation.set_history_length(100);
+    let states = run(&mut simulation, 100);
+    let end = simulation.snapshot();
+
+    for frame in (0..100).rev() {
+        assert!(simulation.step_back());
+        assert_eq!(simulation.get_frame(), frame as u64);
+        assert_eq!(simulation.snapshot(), states[frame], "frame {frame}");
+    }
+    assert!(!simulation.step_back());
+
+    for _ in 0..100 {
+        simulation.step();
+    }
+    assert_eq!(simulation.snapshot(), end);
+}
+
+#[test]
+fn rewind_to_reaches_any_kept_frame() {
+    let mut simulation = simulation(4);
+    simulation.set_history_length(200);
+    let states = run(&mut simulation, 150);
+
+    for target in [140, 97, 61, 60, 31, 2] {
+        assert!(simulation.rewind_to(target));
+        assert_eq!(simulation.snapshot(), states[target as usize], "frame {target}");
+    }
+    assert!(!simulation.rewind_to(5));
+
+    // The history below the rewound frame still works after stepping on.
+    run(&mut simulation, 10);
+    assert!(simulation.rewind_to(1));
+    assert_eq!(simulation.snapshot(), states[1]);
+}
+
+#[test]
+fn history_is_capped_at_its_length() {
+    let mut simulation = simulation(5);
+    simulation.set_history_length(20);
+    let states = run(&mut simulation, 80);
+    let bytes = simulation.get_history_bytes();
+
+    assert_eq!(simulation.get_history_start(), 60);
+    assert!(bytes < 20 * states[0].len());
+    run(&mut simulation, 80);
+    assert!(simulation.get_history_bytes() <= bytes * 3 / 2);
+
+    assert!(simulation.rewind_to(0));
+    assert_eq!(simulation.get_frame(), 140);
+
+    simulation.set_history_length(0);
+    assert_eq!(simulation.get_history_bytes(), 0);
+    assert!(!simulation.step_back());
+}
+
+#[test]
+fn edits_between_steps_are_kept() {
+    let mut simulation = simulation(6);
+    simulation.set_history_length(50);
+    let mut states = run(&mut simulation, 10);
+    simulation.add_particle(400.0, 300.0);
+    states.extend(run(&mut simulation, 10));
+
+    // Step back, change something and step on: the states before stay intact.
+    assert!(simulation.rewind_to(15));
+    simulation.set_gravity(0.0);
+    run(&mut simulation, 5);
+    assert!(simulation.rewind_to(14));
+    assert_eq!(simulation.snapshot(), states[14]);
+    assert!(simulation.rewind_to(3));
+    assert_eq!(simulation.snapshot(), states[3]);
+}
+
+#[test]
+fn rewinding_restores_the_running_diagnostics() {
+    let mut simulation = simulation(9);
+    simulation.set_history_length(100);
+    let counters = |simulation: &Simulation| {
+        let diagnostics = simulation.diagnostics();
+        (diagnostics.collisions, diagnostics.energy_lost, diagnostics.total_energy_lost)
+    };
+    let seen: Vec<_> = (0..60)
+        .map(|_| {
+            let before = counters(&simulation);
+            simulation.step();
+            before
+        })
+        .collect();
+    assert!(seen[59].2 > 0.0);
+
+    assert!(simulation.rewind_to(45));
+    assert_eq!(counters(&simulation), seen[45]);
+    assert!(simulation.step_back());
+    assert_eq!(counters(&simulation), seen[44]);
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use common::snapshot::Snapshot;
+use particles_wasm::{Simulation, SnapshotError, SNAPSHOT_VERSION};
+
+fn simulation() -> Simulation {
+    Simulation::new(800.0, 600.0, 500, 2.0, 9.0, 6.0, 0.2, 0.7, 0.5, 3, 11)
+}
+
+#[test]
+fn ids_follow_particles_through_steps() {
+    let mut simulation = simulation();
+    simulation.add_particle(400.0, 300.0);
+    let ids: Vec<u32> = simulation.particles().iter().map(|p| p.id).collect();
+    assert_eq!(ids, (0..501).collect::<Vec<u32>>());
+
+    for _ in 0..30 {
+        simulation.step();
+    }
+    for (slot, particle) in simulation.particles().iter().enumerate() {
+        assert_eq!(particle.id, ids[slot]);
+        assert_eq!(simulation.get_slot(particle.id), Some(slot));
+        assert_eq!(simulation.get_particle(particle.id).as_ref(), Some(particle));
+    }
+    assert_eq!(simulation.get_slot(501), None);
+    assert!(simulation.get_particle(501).is_none());
+
+    simulation.reset();
+    assert_eq!(simulation.particles().last().unwrap().id, 499);
+    assert_eq!(simulation.get_slot(500), None);
+}
+
+#[test]
+fn set_particle_moves_the_particle_with_that_id() {
+    let mut simulation = simulation();
+    let mut particle = simulation.get_particle(42).unwrap();
+    particle.x = 100.0;
+    particle.y = 50.0;
+    particle.prev_x = 100.0;
+    particle.prev_y = 50.0;
+    particle.vx = 0.0;
+    particle.vy = 0.0;
+    particle.radius = 10.0;
+    particle.id = 7;
+    assert!(simulation.set_particle(42, &particle));
+    assert!(!simulation.set_particle(500, &particle));
+
+    let updated = simulation.get_particle(42).unwrap();
+    assert_eq!((updated.x, updated.y, updated.vx, updated.vy, updated.radius), (100.0, 50.0, 0.0, 0.0, 10.0));
+    assert_eq!(updated.mass, std::f32::consts::PI * 100.0);
+    assert_eq!(updated.id, 42);
+    assert_ne!(simulation.get_particle(7).unwrap().x, 100.0);
+}
+
+#[test]
+fn snapshots_keep_ids() {
+    let mut original = simulation();
+    for _ in 0..10 {
+        original.step();
+    }
+    let bytes = original.snapshot();
+    let mut restored = Simulation::new(320.0, 240.0, 10, 1.0, 2.0, 1.0, 0.0, 1.0, 0.0, 1, 99);
+    restored.restore(&bytes).unwrap();
+    assert_eq!(restored.particles(), original.particles());
+
+    original.add_particle(400.0, 300.0);
+    restored.add_particle(400.0, 300.0);
+    assert_eq!(restored.particles().last().unwrap().id, 500);
+    assert_eq!(restored.get_particle(500), original.get_particle(500));
+}
+
+#[test]
+fn version_1_snapshots_load_with_ids_in_slot_order() {
+    let mut original = simulation();
+    original.step();
+    let current = original.snapshot();
+
+    // Version 1 lacks next_id, the id array and everything after the sort
+    // order. The emitters are just the default fan, and the rest is empty.
+    let v1 = Snapshot::parse(&current).write(1);
+
+    let mut restored = simulation();
+    restored.restore(&v1).unwrap();
+    assert_eq!(restored.particles(), original.particles());
+    assert_eq!(restored.snapshot(), current);
+}
+
+#[test]
+fn restores_check_ids_without_trusting_next_id() {
+    let mut original = simulation();
+    original.remove_particle(3);
+    let parts = Snapshot::parse(&original.snapshot());
+    let restore = |edit: &dyn Fn(&mut Snapshot)| {
+        let mut parts = parts.clone();
+        edit(&mut parts);
+        simulation().restore(&parts.write(SNAPSHOT_VERSION))
+    };
+
+    // A huge next id costs nothing, and counts on from there.
+    let mut restored = simulation();
+    let mut huge = parts.clone();
+    huge.next_id = u32::MAX - 1;
+    restored.restore(&huge.write(SNAPSHOT_VERSION)).unwrap();
+    restored.add_particle(400.0, 300.0);
+    assert_eq!(restored.get_slot(u32::MAX - 1), Some(499));
+    assert_eq!(restored.get_slot(4), Some(3));
+    assert_eq!(restored.get_slot(3), None);
+
+    assert!(matches!(restore(&|s| s.next_id = 499), Err(SnapshotError::Invalid(_))));
+    assert!(matches!(restore(&|s| s.id.swap(0, 1)), Err(SnapshotError::Invalid(_))));
+    assert!(matches!(restore(&|s| s.id[1] = s.id[0]), Err(SnapshotError::Invalid(_))));
+}
+
+#[test]
+fn ids_keep_counting_through_removals() {
+    // Ids keep counting up while the particles come and go.
+    let mut simulation = simulation();
+    for round in 0..20u32 {
+        simulation.clear();
+        for i in 0..50 {
+            simulation.add_particle(100.0 + i as f32 * 12.0, 300.0);
+        }
+        let first = 500 + round * 50;
+        assert_eq!(simulation.get_slot(first), Some(0));
+        assert_eq!(simulation.get_slot(first - 1), None);
+        assert_eq!(simulation.particles().last().unwrap().id, first + 49);
+    }
+}
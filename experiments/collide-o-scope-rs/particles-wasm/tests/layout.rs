@@ -0,0 +1,47 @@
+use particles_wasm::layout::{collision_event_layout, particle_layout, particle_stride, EVENT_LAYOUT_VERSION, PARTICLE_LAYOUT_VERSION};
+
+// If this fails, `Particle` changed shape: bump PARTICLE_LAYOUT_VERSION and
+// update the expected descriptor.
+#[test]
+fn layout_descriptor_is_pinned_to_its_version() {
+    assert_eq!(PARTICLE_LAYOUT_VERSION, 2);
+    assert_eq!(particle_stride(), 56);
+    assert_eq!(
+        particle_layout(),
+        concat!(
+            r#"{"version":2,"stride":56,"fields":["#,
+            r#"{"name":"x","offset":0,"type":"f32"},"#,
+            r#"{"name":"y","offset":4,"type":"f32"},"#,
+            r#"{"name":"radius","offset":8,"type":"f32"},"#,
+            r#"{"name":"mass","offset":12,"type":"f32"},"#,
+            r#"{"name":"vx","offset":16,"type":"f32"},"#,
+            r#"{"name":"vy","offset":20,"type":"f32"},"#,
+            r#"{"name":"colliding","offset":24,"type":"u8"},"#,
+            r#"{"name":"z_code","offset":28,"type":"u32"},"#,
+            r#"{"name":"impulse_x","offset":32,"type":"f32"},"#,
+            r#"{"name":"impulse_y","offset":36,"type":"f32"},"#,
+            r#"{"name":"color_temp","offset":40,"type":"f32"},"#,
+            r#"{"name":"prev_x","offset":44,"type":"f32"},"#,
+            r#"{"name":"prev_y","offset":48,"type":"f32"},"#,
+            r#"{"name":"id","offset":52,"type":"u32"}]}"#,
+        )
+    );
+}
+
+#[test]
+fn event_layout_is_pinned_to_its_version() {
+    assert_eq!(EVENT_LAYOUT_VERSION, 1);
+    assert_eq!(
+        collision_event_layout(),
+        concat!(
+            r#"{"version":1,"stride":28,"fields":["#,
+            r#"{"name":"a","offset":0,"type":"u32"},"#,
+            r#"{"name":"b","offset":4,"type":"u32"},"#,
+            r#"{"name":"x","offset":8,"type":"f32"},"#,
+            r#"{"name":"y","offset":12,"type":"f32"},"#,
+            r#"{"name":"nx","offset":16,"type":"f32"},"#,
+            r#"{"name":"ny","offset":20,"type":"f32"},"#,
+            r#"{"name":"impulse","offset":24,"type":"f32"}]}"#,
+        )
+    );
+}
@@ -0,0 +1,147 @@
+mod common;
+
+use common::{place, world};
+use particles_wasm::{ObstacleKind, Simulation, NO_PARTICLE};
+
+fn run(simulation: &mut Simulation, steps: usize) -> (f32, f32, f32, f32) {
+    for _ in 0..steps {
+        simulation.step();
+    }
+    let particle = simulation.get_particle(0).unwrap();
+    (particle.x, particle.y, particle.vx, particle.vy)
+}
+
+#[test]
+fn particles_bounce_off_each_kind() {
+    // Straight down onto a floor that spans many grid cells.
+    let mut simulation = world(800.0, 600.0);
+    place(&mut simulation, 400.0, 100.0, 0.0, 5.0);
+    assert_eq!(simulation.add_segments(&[100.0, 200.0, 700.0, 200.0]), Some(0));
+    let (_, y, vx, vy) = run(&mut simulation, 40);
+    assert!(y < 196.0 && vx == 0.0 && (vy + 5.0).abs() < 1e-4, "{y}, {vy}");
+
+    // Far faster than its radius per step, still no tunnelling.
+    let mut simulation = world(800.0, 600.0);
+    place(&mut simulation, 400.0, 100.0, 0.0, 30.0);
+    simulation.add_segments(&[100.0, 200.0, 700.0, 200.0]);
+    let (_, y, _, vy) = run(&mut simulation, 4);
+    assert!(y <= 196.0 && vy == -30.0, "{y}, {vy}");
+
+    // Off a 45° slope, the velocity turns through a right angle.
+    let mut simulation = world(800.0, 600.0);
+    place(&mut simulation, 400.0, 100.0, 0.0, 4.0);
+    simulation.add_segments(&[300.0, 300.0, 500.0, 100.0]);
+    let (_, _, vx, vy) = run(&mut simulation, 40);
+    assert!((vx + 4.0).abs() < 1e-3 && vy.abs() < 1e-3, "{vx}, {vy}");
+
+    // Head on into a circle, straight back.
+    let mut simulation = world(800.0, 600.0);
+    place(&mut simulation, 300.0, 300.0, 4.0, 0.0);
+    assert_eq!(simulation.add_circle(400.0, 300.0, 50.0), Some(0));
+    let (x, _, vx, vy) = run(&mut simulation, 40);
+    assert!(x < 346.0 && (vx + 4.0).abs() < 1e-4 && vy == 0.0, "{x}, {vx}");
+
+    // Up into the underside of a concave polygon's notch.
+    let mut simulation = world(800.0, 600.0);
+    place(&mut simulation, 400.0, 400.0, 0.0, -4.0);
+    let notched = [300.0, 100.0, 500.0, 100.0, 500.0, 300.0, 400.0, 200.0, 300.0, 300.0];
+    assert_eq!(simulation.add_polygon(&notched), Some(0));
+    let (_, y, _, vy) = run(&mut simulation, 60);
+    assert!(y > 204.0 && vy > 0.0, "{y}, {vy}");
+}
+
+#[test]
+fn particles_inside_a_polygon_are_pushed_out() {
+    let mut simulation = world(800.0, 600.0);
+    place(&mut simulation, 400.0, 102.0, 0.0, 0.0);
+    simulation.add_polygon(&[300.0, 100.0, 500.0, 100.0, 500.0, 300.0, 300.0, 300.0]);
+    let (x, y, _, _) = run(&mut simulation, 1);
+    assert_eq!(x, 400.0);
+    assert!(y <= 96.0, "{y}");
+}
+
+#[test]
+fn obstacles_can_be_listed_read_back_and_removed() {
+    let mut simulation = world(800.0, 600.0);
+    place(&mut simulation, 10.0, 10.0, 0.0, 0.0);
+    assert_eq!(simulation.add_segments(&[0.0, 0.0]), None);
+    assert_eq!(simulation.add_segments(&[0.0, 0.0, 1.0]), None);
+    assert_eq!(simulation.add_polygon(&[0.0, 0.0, 1.0, 0.0]), None);
+    assert_eq!(simulation.add_circle(0.0, 0.0, 0.0), None);
+    assert_eq!(simulation.add_circle(f32::NAN, 0.0, 1.0), None);
+
+    let wall = simulation.add_segments(&[0.0, 0.0, 10.0, 0.0, 10.0, 10.0]).unwrap();
+    let peg = simulation.add_circle(5.0, 6.0, 7.0).unwrap();
+    let box_ = simulation.add_polygon(&[1.0, 1.0, 2.0, 1.0, 2.0, 2.0]).unwrap();
+    assert_eq!(simulation.get_obstacle_ids(), [wall, peg, box_]);
+    assert_eq!(simulation.get_obstacle_kind(peg), Some(ObstacleKind::Circle));
+    assert_eq!(simulation.get_obstacle_points(peg), [5.0, 6.0, 7.0]);
+    assert_eq!(simulation.get_obstacle_points(wall), [0.0, 0.0, 10.0, 0.0, 10.0, 10.0]);
+
+    assert!(simulation.remove_obstacle(peg));
+    assert!(!simulation.remove_obstacle(peg));
+    assert_eq!(simulation.get_obstacle_kind(peg), None);
+    assert!(simulation.get_obstacle_points(peg).is_empty());
+    assert_eq!(simulation.add_circle(1.0, 1.0, 1.0), Some(3));
+    simulation.clear_obstacles();
+    assert!(simulation.get_obstacle_ids().is_empty());
+}
+
+#[test]
+fn bounces_off_obstacles_are_recorded() {
+    let mut simulation = world(800.0, 600.0);
+    place(&mut simulation, 400.0, 100.0, 0.0, 5.0);
+    simulation.set_event_capacity(8);
+    simulation.add_segments(&[100.0, 200.0, 700.0, 200.0]);
+    let mass = simulation.get_particle(0).unwrap().mass;
+    let mut events = Vec::new();
+    for _ in 0..40 {
+        simulation.step();
+        events.extend_from_slice(simulation.collision_events());
+    }
+    let [event] = events[..] else {
+        panic!("{events:?}");
+    };
+    assert_eq!((event.a, event.b), (0, NO_PARTICLE));
+    assert_eq!((event.nx, event.ny), (0.0, -1.0));
+    assert!((event.y - 200.0).abs() < 1e-4 && event.x == 400.0, "{event:?}");
+    assert!((event.impulse - 10.0 * mass).abs() < 1e-3, "{event:?}");
+    // The collision count is still of particle pairs only.
+    assert_eq!(simulation.diagnostics().collisions, 0);
+}
+
+fn galton(seed: u64) -> Simulation {
+    let mut simulation = Simulation::new(800.0, 600.0, 400, 2.0, 5.0, 4.0, 0.2, 0.8, 0.0, 3, seed);
+    for row in 0..8 {
+        for col in 0..12 {
+            let x = 60.0 + col as f32 * 60.0 + (row % 2) as f32 * 30.0;
+            simulation.add_circle(x, 200.0 + row as f32 * 40.0, 6.0);
+        }
+    }
+    simulation.add_segments(&[0.0, 100.0, 380.0, 160.0]);
+    simulation.add_polygon(&[420.0, 160.0, 800.0, 100.0, 800.0, 120.0]);
+    simulation
+}
+
+#[test]
+fn snapshots_keep_obstacles() {
+    let mut original = galton(3);
+    original.remove_obstacle(5);
+    for _ in 0..10 {
+        original.step();
+    }
+    let mut restored = Simulation::new(100.0, 100.0, 0, 1.0, 2.0, 1.0, 0.0, 1.0, 0.0, 1, 1);
+    restored.restore(&original.snapshot()).unwrap();
+    assert_eq!(restored.get_obstacle_ids(), original.get_obstacle_ids());
+    for id in restored.get_obstacle_ids() {
+        assert_eq!(restored.get_obstacle_points(id), original.get_obstacle_points(id));
+    }
+    assert_eq!(restored.add_circle(1.0, 1.0, 1.0), Some(98));
+
+    original.add_circle(1.0, 1.0, 1.0);
+    for _ in 0..30 {
+        original.step();
+        restored.step();
+    }
+    assert_eq!(restored.particles(), original.particles());
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use common::{place, world};
+use particles_wasm::{PointerMode, Simulation};
+
+fn velocity(simulation: &Simulation, id: u32) -> (f32, f32) {
+    let particle = simulation.get_particle(id).unwrap();
+    (particle.vx, particle.vy)
+}
+
+#[test]
+fn pointer_modes_pull_push_and_stir() {
+    let after_step = |mode| {
+        let mut simulation = world(800.0, 600.0);
+        place(&mut simulation, 450.0, 300.0, 0.0, 0.0);
+        simulation.set_pointer_radius(100.0);
+        simulation.set_pointer_strength(1.0);
+        simulation.set_pointer(400.0, 300.0, mode);
+        simulation.step();
+        velocity(&simulation, 0)
+    };
+    assert!((after_step(PointerMode::Attract).0 + 0.5).abs() < 1e-5);
+    assert!((after_step(PointerMode::Repel).0 - 0.5).abs() < 1e-5);
+    assert!((after_step(PointerMode::Stir).1 - 0.5).abs() < 1e-5);
+    assert_eq!(after_step(PointerMode::Off), (0.0, 0.0));
+
+    let mut simulation = world(800.0, 600.0);
+    place(&mut simulation, 450.0, 300.0, 0.0, 0.0);
+    simulation.set_pointer(400.0, 300.0, PointerMode::Attract);
+    simulation.set_pointer_radius(40.0);
+    simulation.step();
+    assert_eq!(velocity(&simulation, 0), (0.0, 0.0));
+}
+
+#[test]
+fn the_pointer_survives_restores_but_is_not_saved() {
+    let mut simulation = world(800.0, 600.0);
+    place(&mut simulation, 450.0, 300.0, 0.0, 0.0);
+    let bytes = simulation.snapshot();
+    simulation.set_pointer(400.0, 300.0, PointerMode::Repel);
+    simulation.restore(&bytes).unwrap();
+    simulation.step();
+    assert!(velocity(&simulation, 0).0 > 0.0);
+    assert_eq!(simulation.get_pointer_mode(), PointerMode::Repel);
+
+    let mut restored = world(800.0, 600.0);
+    place(&mut restored, 0.0, 0.0, 0.0, 0.0);
+    restored.restore(&simulation.snapshot()).unwrap();
+    let before = velocity(&restored, 0);
+    restored.step();
+    assert_eq!(velocity(&restored, 0), before);
+}
+
+#[test]
+fn dragging_follows_the_pointer_and_release_flings() {
+    let mut simulation = world(800.0, 600.0);
+    place(&mut simulation, 200.0, 200.0, 0.0, 0.0);
+    simulation.set_gravity(0.2);
+    assert!(!simulation.grab(1));
+    assert!(simulation.grab(0));
+    assert_eq!(simulation.get_grabbed(), Some(0));
+    simulation.drag_to(300.0, 250.0);
+    for _ in 0..60 {
+        simulation.step();
+    }
+    let held = simulation.get_particle(0).unwrap();
+    // Gravity sags it by g / stiffness below the anchor.
+    assert!((held.x - 300.0).abs() < 0.1 && (held.y - 250.8).abs() < 0.1, "{}, {}", held.x, held.y);
+
+    assert!(simulation.release(3.0, -2.0));
+    assert!(!simulation.release(0.0, 0.0));
+    assert_eq!(simulation.get_grabbed(), None);
+    assert_eq!(velocity(&simulation, 0), (3.0, -2.0));
+    simulation.step();
+    assert!(simulation.get_particle(0).unwrap().x > 302.0);
+}
+
+#[test]
+fn removing_the_grabbed_particle_lets_go() {
+    let mut simulation = world(800.0, 600.0);
+    place(&mut simulation, 200.0, 200.0, 0.0, 0.0);
+    simulation.add_particle(500.0, 500.0);
+    assert!(simulation.grab(0));
+    simulation.remove_particle(0);
+    assert_eq!(simulation.get_grabbed(), None);
+    simulation.drag_to(500.0, 500.0);
+    simulation.step();
+    assert!(!simulation.release(1.0, 1.0));
+    assert_ne!(velocity(&simulation, 1), (1.0, 1.0));
+}
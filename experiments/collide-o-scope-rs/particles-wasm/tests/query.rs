@@ -0,0 +1,93 @@
+use particles_wasm::{Particle, Simulation};
+
+fn simulation() -> Simulation {
+    let mut simulation = Simulation::new(800.0, 600.0, 2000, 2.0, 9.0, 6.0, 0.2, 0.7, 0.5, 3, 5);
+    for _ in 0..20 {
+        simulation.step();
+    }
+    simulation
+}
+
+fn distance(p: &Particle, x: f32, y: f32) -> f32 {
+    ((p.x - x).powi(2) + (p.y - y).powi(2)).sqrt()
+}
+
+fn points() -> impl Iterator<Item = (f32, f32)> {
+    (0..40).map(|i| ((i * 97 % 800) as f32 + 0.5, (i * 61 % 600) as f32 + 0.25)).chain([(0.0, 0.0), (800.0, 600.0), (-50.0, 300.0)])
+}
+
+// Brute-force answers to check the indexed queries against.
+fn check(simulation: &mut Simulation) {
+    let particles = simulation.particles();
+    for (x, y) in points() {
+        let mut under: Vec<&Particle> = particles.iter().filter(|p| distance(p, x, y) <= p.radius).collect();
+        under.sort_by(|p, q| distance(p, x, y).total_cmp(&distance(q, x, y)).then(p.id.cmp(&q.id)));
+        assert_eq!(simulation.pick(x, y), under.first().map(|p| p.id), "pick at {x}, {y}");
+
+        let within: Vec<u32> = particles.iter().filter(|p| distance(p, x, y) <= 30.0 + p.radius).map(|p| p.id).collect();
+        assert_eq!(simulation.query_radius(x, y, 30.0), within, "radius around {x}, {y}");
+
+        let (x1, y1) = (x + 120.0, y - 45.0);
+        let touching = |p: &&Particle| distance(p, p.x.clamp(x, x1), p.y.clamp(y1, y)) <= p.radius;
+        let inside: Vec<u32> = particles.iter().filter(touching).map(|p| p.id).collect();
+        assert_eq!(simulation.query_rect(x, y, x1, y1), inside, "rect from {x}, {y}");
+
+        let mut by_distance: Vec<&Particle> = particles.iter().collect();
+        by_distance.sort_by(|p, q| distance(p, x, y).total_cmp(&distance(q, x, y)).then(p.id.cmp(&q.id)));
+        for k in [1, 7, 100] {
+            let nearest: Vec<u32> = by_distance[..k].iter().map(|p| p.id).collect();
+            assert_eq!(simulation.k_nearest(x, y, k), nearest, "{k} nearest to {x}, {y}");
+        }
+    }
+}
+
+#[test]
+fn queries_match_brute_force() {
+    let mut simulation = simulation();
+    check(&mut simulation);
+    assert_eq!(simulation.k_nearest(400.0, 300.0, 5000).len(), 2000);
+    assert_eq!(simulation.query_rect(0.0, 0.0, 800.0, 600.0).len(), 2000);
+}
+
+#[test]
+fn queries_see_particles_added_or_moved_since_the_last_step() {
+    let mut simulation = simulation();
+    check(&mut simulation);
+    for i in 0..50 {
+        simulation.add_particle(10.0 + i as f32 * 15.0, 580.0);
+    }
+    let mut particle = simulation.get_particle(3).unwrap();
+    particle.x = 401.0;
+    particle.y = 299.0;
+    assert!(simulation.set_particle(3, &particle));
+    assert_eq!(simulation.pick(401.0, 299.0), Some(3));
+    check(&mut simulation);
+
+    simulation.step();
+    check(&mut simulation);
+}
+
+#[test]
+fn empty_simulations_find_nothing() {
+    let mut simulation = Simulation::new(800.0, 600.0, 0, 2.0, 9.0, 6.0, 0.0, 1.0, 0.0, 3, 5);
+    assert_eq!(simulation.pick(400.0, 300.0), None);
+    assert!(simulation.query_radius(400.0, 300.0, 1000.0).is_empty());
+    assert!(simulation.query_rect(0.0, 0.0, 800.0, 600.0).is_empty());
+    assert!(simulation.k_nearest(400.0, 300.0, 3).is_empty());
+}
+
+#[test]
+fn nearest_gives_up_on_points_it_cannot_place() {
+    let mut simulation = Simulation::new(800.0, 600.0, 50, 2.0, 9.0, 6.0, 0.0, 1.0, 0.0, 3, 5);
+    simulation.step();
+    for (x, y) in [(f32::NAN, 300.0), (400.0, f32::INFINITY), (f32::NEG_INFINITY, f32::NAN)] {
+        assert!(simulation.k_nearest(x, y, 3).is_empty(), "{x}, {y}");
+    }
+
+    // Far outside the world, every particle is in the running.
+    let distance = |p: &Particle| (p.x - 1e6) * (p.x - 1e6) + (p.y - 300.0) * (p.y - 300.0);
+    let mut by_distance = simulation.particles();
+    by_distance.sort_by(|a, b| distance(a).total_cmp(&distance(b)).then(a.id.cmp(&b.id)));
+    let nearest: Vec<u32> = by_distance[..10].iter().map(|p| p.id).collect();
+    assert_eq!(simulation.k_nearest(1e6, 300.0, 10), nearest);
+}
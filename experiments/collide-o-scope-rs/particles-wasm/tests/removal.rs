@@ -0,0 +1,107 @@
+use particles_wasm::Simulation;
+
+fn simulation() -> Simulation {
+    let mut simulation = Simulation::new(800.0, 600.0, 1000, 2.0, 9.0, 6.0, 0.2, 0.7, 0.5, 3, 13);
+    for _ in 0..10 {
+        simulation.step();
+    }
+    simulation
+}
+
+fn ids(simulation: &Simulation) -> Vec<u32> {
+    simulation.particles().iter().map(|p| p.id).collect()
+}
+
+// Every remaining id still maps to its slot, and the run carries on exactly
+// as a snapshot of it would.
+fn assert_consistent(simulation: &mut Simulation) {
+    for (slot, particle) in simulation.particles().iter().enumerate() {
+        assert_eq!(simulation.get_slot(particle.id), Some(slot));
+    }
+    let mut copy = Simulation::new(100.0, 100.0, 0, 1.0, 2.0, 1.0, 0.0, 1.0, 0.0, 1, 1);
+    copy.restore(&simulation.snapshot()).unwrap();
+    for _ in 0..20 {
+        simulation.step();
+        copy.step();
+    }
+    assert_eq!(copy.particles(), simulation.particles());
+}
+
+#[test]
+fn removing_a_particle_leaves_the_others_alone() {
+    let mut simulation = simulation();
+    let before = simulation.particles();
+    assert!(simulation.remove_particle(500));
+    assert!(!simulation.remove_particle(500));
+    assert!(!simulation.remove_particle(1000));
+
+    assert_eq!(simulation.get_particle_count(), 999);
+    assert_eq!(simulation.get_slot(500), None);
+    assert!(simulation.get_particle(500).is_none());
+    let after: Vec<_> = before.into_iter().filter(|p| p.id != 500).collect();
+    assert_eq!(simulation.particles(), after);
+    assert_consistent(&mut simulation);
+}
+
+#[test]
+fn remove_in_radius_erases_everything_it_touches() {
+    let mut simulation = simulation();
+    let touching = simulation.query_radius(400.0, 300.0, 60.0);
+    assert!(touching.len() > 10);
+    assert_eq!(simulation.remove_in_radius(400.0, 300.0, 60.0), touching);
+    assert!(simulation.query_radius(400.0, 300.0, 60.0).is_empty());
+    assert_eq!(simulation.get_particle_count(), 1000 - touching.len());
+    assert!(ids(&simulation).iter().all(|id| !touching.contains(id)));
+    assert_consistent(&mut simulation);
+}
+
+#[test]
+fn shrinking_the_count_removes_the_newest_particles() {
+    let mut simulation = simulation();
+    simulation.add_particle(400.0, 300.0);
+    simulation.remove_particle(3);
+    simulation.set_particle_count(600);
+    let mut expected: Vec<u32> = (0..601).filter(|&id| id != 3).collect();
+    assert_eq!(ids(&simulation), expected);
+    assert_consistent(&mut simulation);
+
+    // Growing only takes effect on reset.
+    simulation.set_particle_count(700);
+    assert_eq!(simulation.get_particle_count(), 600);
+    simulation.add_particle(400.0, 300.0);
+    expected.push(1001);
+    assert_eq!(ids(&simulation), expected);
+    simulation.reset();
+    assert_eq!(ids(&simulation), (0..700).collect::<Vec<u32>>());
+}
+
+#[test]
+fn clear_removes_everything_but_ids_keep_counting() {
+    let mut simulation = simulation();
+    simulation.clear();
+    assert_eq!(simulation.get_particle_count(), 0);
+    assert_eq!(simulation.get_slot(0), None);
+    simulation.step();
+    simulation.add_particle(400.0, 300.0);
+    assert_eq!(ids(&simulation), [1000]);
+    assert_consistent(&mut simulation);
+    simulation.reset();
+    assert_eq!(simulation.get_particle_count(), 1000);
+}
+
+#[test]
+fn rewinding_brings_removed_particles_back() {
+    let mut simulation = simulation();
+    simulation.set_history_length(10);
+    simulation.step();
+    let before = simulation.particles();
+    simulation.remove_in_radius(200.0, 200.0, 100.0);
+    simulation.step();
+    simulation.step();
+    // The history holds the state before each step, so the removal is folded
+    // into frame 11; frame 10 still has every particle.
+    assert!(simulation.rewind_to(10));
+    simulation.step();
+    assert_eq!(simulation.particles(), before);
+    assert_eq!(simulation.get_particle(before[0].id).as_ref(), Some(&before[0]));
+}
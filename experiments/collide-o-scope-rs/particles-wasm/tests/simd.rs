@@ -0,0 +1,61 @@
+//! Also runs on wasm with the lanes lowered to `v128`:
+//!
+//! ```text
+//! RUSTFLAGS="-C target-feature=+simd128" \
+//! CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUNNER=wasm-bindgen-test-runner \
+//! cargo test --target wasm32-unknown-unknown --test simd
+//! ```
+
+use particles_wasm::broadphase::BroadphaseKind;
+use particles_wasm::simd::{self, F32x4};
+use particles_wasm::Simulation;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::wasm_bindgen_test as test;
+
+// 1501 particles, so the lane kernels also leave a scalar remainder.
+fn simulation(kind: BroadphaseKind, simd: bool) -> Simulation {
+    let mut simulation = Simulation::new(800.0, 600.0, 1501, 2.0, 9.0, 6.0, 0.2, 0.7, 0.8, 3, 13);
+    simulation.set_broadphase(kind);
+    simulation.set_simd(simd);
+    simulation
+}
+
+#[test]
+fn simd_and_scalar_paths_are_identical() {
+    for kind in [BroadphaseKind::ZOrder, BroadphaseKind::UniformGrid] {
+        let mut scalar = simulation(kind, false);
+        let mut simd = simulation(kind, true);
+        for frame in 0..200 {
+            scalar.step();
+            simd.step();
+            assert_eq!(scalar.get_collision_count(), simd.get_collision_count(), "{kind:?} frame {frame}");
+        }
+        assert_eq!(scalar.particles(), simd.particles(), "{kind:?}");
+    }
+}
+
+#[test]
+fn simd_path_finds_every_collision() {
+    let mut simulation = simulation(BroadphaseKind::ZOrder, true);
+    for _ in 0..20 {
+        simulation.step();
+        assert_eq!(simulation.count_missed_collisions(), 0);
+    }
+}
+
+#[test]
+fn lane_min_and_max_match_the_scalar_ones() {
+    let nan = f32::NAN;
+    let cases = [(1.0, 2.0), (2.0, 1.0), (nan, 1.0), (1.0, nan), (0.0, -0.0), (-0.0, 0.0), (nan, nan), (3.0, 3.0)];
+    for pair in cases.chunks(4) {
+        let a = F32x4::from_array(std::array::from_fn(|k| pair[k].0));
+        let b = F32x4::from_array(std::array::from_fn(|k| pair[k].1));
+        for (k, &(x, y)) in pair.iter().enumerate() {
+            assert_eq!(a.min(b).to_array()[k].to_bits(), simd::min(x, y).to_bits(), "min({x}, {y})");
+            assert_eq!(a.max(b).to_array()[k].to_bits(), simd::max(x, y).to_bits(), "max({x}, {y})");
+        }
+    }
+    // A NaN or a tie keeps the first operand, wherever it is.
+    assert!(simd::min(nan, 1.0).is_nan() && simd::max(1.0, nan) == 1.0);
+    assert!(simd::min(0.0, -0.0).is_sign_positive() && simd::max(-0.0, 0.0).is_sign_negative());
+}
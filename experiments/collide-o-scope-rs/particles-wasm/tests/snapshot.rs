@@ -0,0 +1,87 @@
+mod common;
+
+use common::snapshot::Snapshot;
+use particles_wasm::{Simulation, SnapshotError, SNAPSHOT_VERSION};
+
+fn simulation(seed: u64) -> Simulation {
+    let mut simulation = Simulation::new(800.0, 600.0, 1000, 2.0, 9.0, 6.0, 0.2, 0.7, 0.5, 3, seed);
+    simulation.set_substeps(2);
+    simulation
+}
+
+#[test]
+fn restore_continues_the_run_exactly() {
+    let mut original = simulation(3);
+    for _ in 0..50 {
+        original.step();
+    }
+    original.update(0.01);
+    let bytes = original.snapshot();
+
+    // Different parameters and seed, all of which the snapshot replaces.
+    let mut restored = Simulation::new(320.0, 240.0, 10, 1.0, 2.0, 1.0, 0.0, 1.0, 0.0, 1, 99);
+    restored.restore(&bytes).unwrap();
+    assert_eq!(restored.particles(), original.particles());
+    assert_eq!(restored.get_frame(), original.get_frame());
+    assert_eq!(restored.snapshot(), bytes);
+
+    for _ in 0..50 {
+        original.update(1.0 / 60.0);
+        restored.update(1.0 / 60.0);
+    }
+    original.add_particle(400.0, 300.0);
+    restored.add_particle(400.0, 300.0);
+    original.step();
+    restored.step();
+    assert_eq!(restored.particles(), original.particles());
+}
+
+#[test]
+fn rejects_bad_snapshots_without_changing_anything() {
+    let mut source = simulation(5);
+    source.step();
+    let bytes = source.snapshot();
+    let mut simulation = simulation(4);
+    let before = simulation.particles();
+
+    assert_eq!(simulation.restore(b"nope"), Err(SnapshotError::BadMagic));
+
+    let newer = Snapshot::parse(&bytes).write(SNAPSHOT_VERSION + 1);
+    assert_eq!(simulation.restore(&newer), Err(SnapshotError::UnsupportedVersion(SNAPSHOT_VERSION + 1)));
+
+    for len in [6, 40, bytes.len() / 2, bytes.len() - 1] {
+        assert_eq!(simulation.restore(&bytes[..len]), Err(SnapshotError::Truncated), "{len}");
+    }
+
+    let invalid = |edit: &dyn Fn(&mut Snapshot), bytes: &[u8]| {
+        let mut parts = Snapshot::parse(bytes);
+        edit(&mut parts);
+        parts.write(SNAPSHOT_VERSION)
+    };
+    let duplicated = invalid(&|s| s.order[1] = s.order[0], &bytes);
+    assert!(matches!(simulation.restore(&duplicated), Err(SnapshotError::Invalid(_))));
+    let unknown_kind = invalid(&|s| s.emitters[0].1 = 9, &bytes);
+    assert!(matches!(simulation.restore(&unknown_kind), Err(SnapshotError::Invalid(_))));
+
+    let mut pegged = source;
+    pegged.add_circle(100.0, 100.0, 10.0);
+    let negative_radius = invalid(&|s| s.obstacles[0].2[2] = -10.0, &pegged.snapshot());
+    assert!(matches!(simulation.restore(&negative_radius), Err(SnapshotError::Invalid(_))));
+
+    assert_eq!(simulation.particles(), before);
+}
+
+#[test]
+fn older_versions_load_with_defaults_for_what_they_lack() {
+    // The default fan, no obstacles, walls and no bodies: what every older
+    // version fills in.
+    let mut original = simulation(6);
+    original.step();
+    let current = original.snapshot();
+    let parts = Snapshot::parse(&current);
+    for version in 2..SNAPSHOT_VERSION {
+        let mut restored = simulation(1);
+        restored.restore(&parts.write(version)).unwrap();
+        assert_eq!(restored.snapshot(), current, "version {version}");
+    }
+}
@@ -0,0 +1,30 @@
+use particles_wasm::sort::RadixSorter;
+use particles_wasm::{Rng, Simulation, SortAlgorithm};
+
+#[test]
+fn radix_sort_matches_stable_comparison_sort() {
+    let mut rng = Rng::new(5);
+    // Few distinct keys, so stability is exercised, plus full-width keys.
+    let keys: Vec<u32> = (0..5000)
+        .map(|i| if i % 2 == 0 { rng.next_u32() % 64 } else { rng.next_u32() })
+        .collect();
+    let mut order: Vec<u32> = (0..keys.len() as u32).rev().collect();
+    let mut expected = order.clone();
+    expected.sort_by_key(|&slot| keys[slot as usize]);
+
+    RadixSorter::default().sort_indices(&mut order, &keys);
+    assert_eq!(order, expected);
+}
+
+#[test]
+fn sort_algorithms_give_identical_trajectories() {
+    let mut radix = Simulation::new(800.0, 600.0, 2000, 2.0, 6.0, 4.0, 0.1, 0.7, 0.5, 3, 21);
+    let mut comparison = Simulation::new(800.0, 600.0, 2000, 2.0, 6.0, 4.0, 0.1, 0.7, 0.5, 3, 21);
+    radix.set_sort_algorithm(SortAlgorithm::Radix);
+    comparison.set_sort_algorithm(SortAlgorithm::Comparison);
+    for _ in 0..100 {
+        radix.step();
+        comparison.step();
+    }
+    assert_eq!(radix.particles(), comparison.particles());
+}
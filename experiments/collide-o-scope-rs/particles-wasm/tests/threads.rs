@@ -0,0 +1,72 @@
+use particles_wasm::broadphase::BroadphaseKind;
+use particles_wasm::{Simulation, NO_PARTICLE};
+
+const KINDS: [BroadphaseKind; 4] = [
+    BroadphaseKind::ZOrder,
+    BroadphaseKind::UniformGrid,
+    BroadphaseKind::LooseQuadtree,
+    BroadphaseKind::BruteForce,
+];
+
+fn simulation(kind: BroadphaseKind, threads: usize) -> Simulation {
+    let mut simulation = Simulation::new(800.0, 600.0, 1500, 2.0, 9.0, 6.0, 0.2, 0.7, 0.5, 3, 17);
+    simulation.set_broadphase(kind);
+    simulation.set_threads(threads);
+    simulation
+}
+
+#[test]
+fn thread_count_does_not_change_the_trajectory() {
+    for kind in KINDS {
+        let mut single = simulation(kind, 1);
+        // 7 doesn't divide 1500, so chunks differ in size.
+        let mut split = simulation(kind, 7);
+        for frame in 0..60 {
+            single.step();
+            split.step();
+            assert_eq!(single.get_collision_count(), split.get_collision_count(), "{kind:?} frame {frame}");
+        }
+        assert_eq!(single.particles(), split.particles(), "{kind:?}");
+    }
+}
+
+#[test]
+fn more_threads_than_particles() {
+    let mut single = Simulation::new(100.0, 100.0, 5, 8.0, 12.0, 6.0, 0.2, 0.7, 0.5, 3, 17);
+    let mut split = Simulation::new(100.0, 100.0, 5, 8.0, 12.0, 6.0, 0.2, 0.7, 0.5, 3, 17);
+    single.set_threads(1);
+    split.set_threads(16);
+    for _ in 0..30 {
+        single.step();
+        split.step();
+    }
+    assert_eq!(single.particles(), split.particles());
+}
+
+#[test]
+fn thread_count_does_not_change_obstacle_contacts() {
+    let world = |threads| {
+        let mut simulation = Simulation::new(800.0, 600.0, 600, 2.0, 6.0, 6.0, 0.2, 0.8, 0.0, 3, 5);
+        simulation.set_threads(threads);
+        simulation.set_event_capacity(100_000);
+        for row in 0..6 {
+            for col in 0..12 {
+                simulation.add_circle(60.0 + col as f32 * 60.0 + (row % 2) as f32 * 30.0, 150.0 + row as f32 * 50.0, 6.0);
+            }
+        }
+        simulation.add_polygon(&[300.0, 520.0, 500.0, 520.0, 400.0, 460.0]);
+        let paddle = simulation.add_body(400.0, 300.0);
+        simulation.add_body_segments(paddle, &[-120.0, 0.0, 120.0, 0.0]);
+        simulation.set_body_rotation(paddle, 0.05);
+        simulation
+    };
+    let (mut single, mut split) = (world(1), world(7));
+    for frame in 0..60 {
+        single.step();
+        split.step();
+        assert_eq!(single.collision_events(), split.collision_events(), "frame {frame}");
+        assert_eq!(single.diagnostics().energy_lost, split.diagnostics().energy_lost, "frame {frame}");
+    }
+    assert!(single.collision_events().iter().any(|event| event.b == NO_PARTICLE));
+    assert_eq!(single.particles(), split.particles());
+}
@@ -0,0 +1,60 @@
+use particles_wasm::Simulation;
+
+fn simulation() -> Simulation {
+    Simulation::new(800.0, 600.0, 300, 4.0, 12.0, 4.0, 0.1, 0.7, 0.5, 3, 11)
+}
+
+fn run_for_one_second(refresh_rate: u32) -> Simulation {
+    let mut simulation = simulation();
+    for _ in 0..refresh_rate {
+        simulation.update(1.0 / refresh_rate as f32);
+    }
+    simulation
+}
+
+#[test]
+fn motion_is_independent_of_refresh_rate() {
+    let at_60 = run_for_one_second(60);
+    let at_144 = run_for_one_second(144);
+    assert_eq!(at_60.get_frame(), 60);
+    assert_eq!(at_144.get_frame(), 60);
+    assert_eq!(at_60.particles(), at_144.particles());
+}
+
+#[test]
+fn partial_steps_are_banked_as_interpolation_alpha() {
+    let mut simulation = simulation();
+    simulation.update(0.5 / 60.0);
+    assert_eq!(simulation.get_frame(), 0);
+    assert!((simulation.get_interpolation_alpha() - 0.5).abs() < 1e-3);
+    simulation.update(0.5 / 60.0);
+    assert_eq!(simulation.get_frame(), 1);
+}
+
+#[test]
+fn long_stalls_are_capped() {
+    let mut simulation = simulation();
+    simulation.update(10.0);
+    assert_eq!(simulation.get_frame(), 15);
+}
+
+#[test]
+fn non_finite_elapsed_time_is_ignored() {
+    let mut simulation = simulation();
+    simulation.update(f32::NAN);
+    simulation.update(f32::INFINITY);
+    assert_eq!(simulation.get_frame(), 0);
+    simulation.update(1.0 / 60.0);
+    assert_eq!(simulation.get_frame(), 1);
+}
+
+#[test]
+fn stats_cover_every_step_of_an_update() {
+    let mut simulation = simulation();
+    simulation.update(3.5 / 60.0);
+    let stats = simulation.get_stats();
+    assert_eq!(stats.steps, 3);
+    assert!(stats.candidate_pairs > 0);
+    simulation.update(0.1 / 60.0);
+    assert_eq!(simulation.get_stats().steps, 0);
+}
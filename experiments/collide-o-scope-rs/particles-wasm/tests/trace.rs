@@ -0,0 +1,91 @@
+use particles_wasm::{Particle, Player, Recorder, Simulation, TraceError, TRACE_VERSION};
+
+fn simulation() -> Simulation {
+    Simulation::new(800.0, 600.0, 500, 2.0, 9.0, 6.0, 0.2, 0.7, 0.5, 3, 11)
+}
+
+/// Records `frames` steps, returning the recorder and the particles of each.
+fn record(frames: usize, max_bytes: usize) -> (Recorder, Vec<Vec<Particle>>) {
+    let mut simulation = simulation();
+    let mut recorder = Recorder::new(max_bytes);
+    let mut history = Vec::new();
+    for frame in 0..frames {
+        simulation.step();
+        if frame == frames / 2 {
+            simulation.add_particle(400.0, 300.0);
+        }
+        recorder.capture(&simulation);
+        // Repeated captures of the same frame are ignored.
+        recorder.capture(&simulation);
+        history.push(simulation.particles());
+    }
+    (recorder, history)
+}
+
+fn assert_close(replayed: &[Particle], recorded: &[Particle]) {
+    assert_eq!(replayed.len(), recorded.len());
+    for (a, b) in replayed.iter().zip(recorded) {
+        assert!((a.x - b.x).abs() <= 1.0 / 32.0 && (a.y - b.y).abs() <= 1.0 / 32.0, "{a:?} {b:?}");
+        assert!((a.radius - b.radius).abs() <= 1.0 / 32.0);
+        assert!((a.color_temp - b.color_temp).abs() <= 0.5 / 255.0 + 1e-6);
+        assert_eq!(a.colliding, b.colliding);
+    }
+}
+
+#[test]
+fn replays_every_frame_within_quantisation() {
+    let (recorder, history) = record(150, usize::MAX);
+    assert_eq!(recorder.get_frame_count(), 150);
+    let mut player = Player::new(&recorder.export()).unwrap();
+    assert_eq!(player.get_frame_count(), 150);
+
+    for (index, recorded) in history.iter().enumerate() {
+        player.seek(index);
+        assert_eq!(player.get_frame(), index as u64 + 1);
+        assert_close(&player.particles(), recorded);
+    }
+    // Scrubbing backwards and jumping around give the same frames.
+    for index in [149, 3, 90, 61, 60, 59, 0, 120] {
+        player.seek(index);
+        assert_close(&player.particles(), &history[index]);
+    }
+}
+
+#[test]
+fn ring_buffer_drops_the_oldest_chunks() {
+    let (full, _) = record(300, usize::MAX);
+    let budget = full.get_byte_size() / 2;
+    let (recorder, history) = record(300, budget);
+    assert!(recorder.get_byte_size() <= budget);
+    let kept = recorder.get_frame_count();
+    assert!(kept < 300 && kept > 0);
+
+    let mut player = Player::new(&recorder.export()).unwrap();
+    player.seek(0);
+    assert_eq!(player.get_frame(), (300 - kept) as u64 + 1);
+    assert_close(&player.particles(), &history[300 - kept]);
+    player.seek(kept - 1);
+    assert_close(&player.particles(), &history[299]);
+}
+
+#[test]
+fn deltas_are_smaller_than_raw_positions() {
+    let (recorder, _) = record(120, usize::MAX);
+    // x, y, radius and temperature as raw f32s would take 16 bytes a particle.
+    assert!(recorder.get_byte_size() < 120 * 500 * 16 / 2, "{}", recorder.get_byte_size());
+}
+
+#[test]
+fn rejects_bad_traces() {
+    let (recorder, _) = record(10, usize::MAX);
+    let bytes = recorder.export();
+    assert_eq!(Player::new(b"nope").err(), Some(TraceError::BadMagic));
+
+    let mut newer = bytes.clone();
+    newer[4..6].copy_from_slice(&(TRACE_VERSION + 1).to_le_bytes());
+    assert_eq!(Player::new(&newer).err(), Some(TraceError::UnsupportedVersion(TRACE_VERSION + 1)));
+
+    for len in [8, 20, bytes.len() / 2, bytes.len() - 1] {
+        assert!(Player::new(&bytes[..len]).is_err(), "{len}");
+    }
+}
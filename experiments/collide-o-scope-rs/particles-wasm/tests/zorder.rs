@@ -0,0 +1,53 @@
+use particles_wasm::zorder::{self, ZOrder};
+use particles_wasm::{Rng, Simulation};
+
+fn random_rect(rng: &mut Rng) -> (u32, u32) {
+    let (x0, y0) = (rng.next_u32() % 60, rng.next_u32() % 60);
+    let (x1, y1) = (x0 + rng.next_u32() % 8, y0 + rng.next_u32() % 8);
+    (ZOrder::encode(x0, y0), ZOrder::encode(x1, y1))
+}
+
+#[test]
+fn bigmin_and_litmax_match_brute_force() {
+    let mut rng = Rng::new(1);
+    for _ in 0..200 {
+        let (zmin, zmax) = random_rect(&mut rng);
+        for z in zmin + 1..zmax {
+            if ZOrder::in_rect(z, zmin, zmax) {
+                continue;
+            }
+            let bigmin = (z + 1..=zmax).find(|&c| ZOrder::in_rect(c, zmin, zmax)).unwrap();
+            let litmax = (zmin..z).rev().find(|&c| ZOrder::in_rect(c, zmin, zmax)).unwrap();
+            assert_eq!(ZOrder::bigmin(z, zmin, zmax), bigmin);
+            assert_eq!(ZOrder::litmax(z, zmin, zmax), litmax);
+        }
+    }
+}
+
+#[test]
+fn scan_rect_visits_exactly_the_codes_inside() {
+    let mut rng = Rng::new(2);
+    let mut codes: Vec<u32> = (0..2000).map(|_| ZOrder::encode(rng.next_u32() % 64, rng.next_u32() % 64)).collect();
+    codes.sort_unstable();
+    for tolerance in [0, 3, 50] {
+        for _ in 0..100 {
+            let (zmin, zmax) = random_rect(&mut rng);
+            let mut visited = Vec::new();
+            zorder::scan_rect(&codes, 0, zmin, zmax, tolerance, |run| visited.extend(run));
+            let expected: Vec<usize> = (0..codes.len()).filter(|&k| ZOrder::in_rect(codes[k], zmin, zmax)).collect();
+            assert_eq!(visited, expected);
+        }
+    }
+}
+
+#[test]
+fn neighbour_search_misses_nothing_at_any_search_range() {
+    let mut simulation = Simulation::new(800.0, 600.0, 1500, 2.0, 9.0, 6.0, 0.2, 0.7, 0.5, 3, 4);
+    for range in [0, 1, 3, 20] {
+        simulation.set_search_range(range);
+        for _ in 0..20 {
+            simulation.step();
+            assert_eq!(simulation.count_missed_collisions(), 0);
+        }
+    }
+}